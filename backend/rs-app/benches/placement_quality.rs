@@ -0,0 +1,274 @@
+//! Placement quality regression suite.
+//!
+//! Runs every strategy over the datasets in `backend/generate-dataset/` and over
+//! seeded manifests from the generator module, writes the figures to a results file and compares
+//! them with the stored baseline:
+//!
+//!     cargo bench --bench placement_quality                      # check against the baseline
+//!     cargo bench --bench placement_quality -- --filter iss      # only matching cases
+//!     cargo bench --bench placement_quality -- --update-baseline # accept the current results
+//!
+//! Exits with 1 when any case got worse than the baseline by more than `--threshold`.
+//! Runtimes are machine-dependent, so they only fail the run with `--max-slowdown`.
+
+use chrono::NaiveDate;
+use clap::Parser;
+use placement_service::config::Config;
+use placement_service::integrity::{self, Violation};
+use placement_service::models::{Container, Item, PlacementRequest};
+use placement_service::report::{self, RunSummary};
+use placement_service::simulation::ContainerState;
+use placement_service::strategy::Strategy;
+use placement_service::validation::Validate;
+use placement_service::dataset;
+use placement_service::generator::{self, GeneratorOptions};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+
+const MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
+const SYNTHETIC_SEEDS: [u64; 3] = [1, 2, 3];
+const SYNTHETIC_ITEMS: usize = 300;
+
+#[derive(Debug, Parser)]
+struct Args {
+    /// Only run cases whose name contains this
+    #[arg(long)]
+    filter: Option<String>,
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/target/placement-quality.json"))]
+    results: PathBuf,
+    #[arg(long, default_value = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/placement_quality_baseline.json"))]
+    baseline: PathBuf,
+    /// Store this run's results as the new baseline instead of comparing
+    #[arg(long)]
+    update_baseline: bool,
+    /// Largest tolerated drop in placed share, utilisation and preferred-zone rate,
+    /// and relative rise in mean retrieval steps
+    #[arg(long, default_value_t = 0.02)]
+    threshold: f64,
+    /// Fail when a case runs this many times slower than its baseline
+    #[arg(long)]
+    max_slowdown: Option<f64>,
+    // Passed by `cargo bench`
+    #[arg(long, hide = true)]
+    bench: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CaseResult {
+    case: String,
+    #[serde(flatten)]
+    summary: RunSummary,
+    violations: usize,
+}
+
+struct Case {
+    name: String,
+    items: Vec<Item>,
+    containers: Vec<Container>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    Config::default().install();
+
+    let cases = match load_cases(args.filter.as_deref()) {
+        Ok(cases) => cases,
+        Err(e) => {
+            eprintln!("placement_quality: {}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("{:<24} {:<14} {:>11} {:>7} {:>7} {:>7} {:>5} {:>10}", "case", "strategy", "placed", "util", "pref", "steps", "viol", "ms");
+    let mut results = Vec::new();
+    for case in &cases {
+        for strategy in Strategy::ALL {
+            let result = match run_case(case, strategy) {
+                Ok(result) => result,
+                Err(e) => {
+                    eprintln!("placement_quality: {} / {}: {}", case.name, strategy.name(), e);
+                    return ExitCode::FAILURE;
+                }
+            };
+            let s = &result.summary;
+            println!(
+                "{:<24} {:<14} {:>11} {:>7.3} {:>7.3} {:>7.3} {:>5} {:>10.1}",
+                result.case, s.strategy, format!("{}/{}", s.placed, s.items), s.volume_utilisation,
+                s.preferred_zone_rate, s.mean_retrieval_steps, result.violations, s.duration_ms,
+            );
+            results.push(result);
+        }
+    }
+
+    if let Err(e) = write_results(&args.results, &results) {
+        eprintln!("placement_quality: {}", e);
+        return ExitCode::FAILURE;
+    }
+    println!("\nResults written to {}", args.results.display());
+
+    if args.update_baseline {
+        return match write_results(&args.baseline, &merge_baseline(&args.baseline, results)) {
+            Ok(()) => {
+                println!("Baseline updated: {}", args.baseline.display());
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("placement_quality: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    let baseline = match read_results(&args.baseline) {
+        Ok(baseline) => baseline,
+        Err(e) => {
+            eprintln!("placement_quality: no usable baseline ({}); run with --update-baseline to create one", e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let regressions = compare(&baseline, &results, &args);
+    if regressions.is_empty() {
+        println!("No regressions against {}", args.baseline.display());
+        ExitCode::SUCCESS
+    } else {
+        println!("\n{} regression(s) against {}:", regressions.len(), args.baseline.display());
+        for r in &regressions {
+            println!("  {}", r);
+        }
+        ExitCode::FAILURE
+    }
+}
+
+fn run_case(case: &Case, strategy: Strategy) -> Result<CaseResult, String> {
+    let started = Instant::now();
+    let outcome = strategy.run(&case.items, &case.containers, false)?;
+    let duration = started.elapsed();
+
+    let mut state: ContainerState = HashMap::new();
+    for p in &outcome.placements {
+        state.entry(p.container_id.clone()).or_default().push((p.item_id.clone(), p.position.clone()));
+    }
+    let masses: HashMap<String, f64> = case.items.iter().filter_map(|i| i.mass.map(|m| (i.item_id.clone(), m))).collect();
+    let violations: Vec<Violation> = integrity::check_arrangement(&case.containers, &state, &masses);
+    for v in violations.iter().take(3) {
+        eprintln!("{} / {}: {:?}", case.name, strategy.name(), v);
+    }
+
+    Ok(CaseResult {
+        case: case.name.clone(),
+        summary: report::summarize(strategy.name(), &case.containers, &case.items, &outcome.placements, &outcome.rearrangements, duration),
+        violations: violations.len(),
+    })
+}
+
+// --- Cases ---
+
+fn load_cases(filter: Option<&str>) -> Result<Vec<Case>, String> {
+    let dir = Path::new(MANIFEST_DIR).join("../generate-dataset");
+    // (case, items file, containers file); JSON files may hold both
+    let datasets = [
+        ("placement", "placement.json", "placement.json"),
+        ("rearrangement", "rearrangement.json", "rearrangement.json"),
+        ("iss_data", "iss_data.json", "iss_data.json"),
+        ("rearrangement_csv", "rearrangement_item.csv", "rearrangementt_container.csv"),
+        ("input_csv", "input_items.csv", "containers.csv"),
+        ("input_json", "input_items.json", "input_container.json"),
+    ];
+
+    let wanted = |name: &str| filter.is_none_or(|f| name.contains(f));
+    let mut cases = Vec::new();
+    for (name, items, containers) in datasets {
+        if !wanted(name) { continue; }
+        cases.push(Case {
+            name: name.to_string(),
+            items: dataset::read_items(&dir.join(items)).map_err(|e| e.to_string())?,
+            containers: dataset::read_containers(&dir.join(containers)).map_err(|e| e.to_string())?,
+        });
+    }
+    for seed in SYNTHETIC_SEEDS {
+        let name = format!("synthetic-{}", seed);
+        if !wanted(&name) { continue; }
+        let data = generator::generate(&GeneratorOptions {
+            seed,
+            items: SYNTHETIC_ITEMS,
+            // Fixed so expiry dates, and with them the results, do not drift
+            today: NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid date"),
+            ..GeneratorOptions::default()
+        });
+        cases.push(Case { name, items: data.items, containers: data.containers });
+    }
+
+    for case in &cases {
+        let request = PlacementRequest {
+            items: case.items.clone(),
+            containers: case.containers.clone(),
+            use_stored_containers: false,
+            zones: None,
+            explain: false,
+        };
+        request.validate().map_err(|e| format!("case {} has invalid input: {:?}", case.name, e.details()))?;
+    }
+    Ok(cases)
+}
+
+// --- Results ---
+
+fn write_results(path: &Path, results: &[CaseResult]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    }
+    let text = serde_json::to_string_pretty(results).map_err(|e| e.to_string())?;
+    std::fs::write(path, text + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+fn read_results(path: &Path) -> Result<Vec<CaseResult>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("invalid {}: {}", path.display(), e))
+}
+
+// A filtered run only replaces the cases it ran
+fn merge_baseline(path: &Path, results: Vec<CaseResult>) -> Vec<CaseResult> {
+    let mut merged: Vec<CaseResult> = read_results(path).unwrap_or_default().into_iter()
+        .filter(|old| !results.iter().any(|new| new.case == old.case && new.summary.strategy == old.summary.strategy))
+        .collect();
+    merged.extend(results);
+    merged
+}
+
+fn compare(baseline: &[CaseResult], results: &[CaseResult], args: &Args) -> Vec<String> {
+    let mut regressions = Vec::new();
+    for result in results {
+        let Some(base) = baseline.iter().find(|b| b.case == result.case && b.summary.strategy == result.summary.strategy) else {
+            println!("{} / {}: not in the baseline", result.case, result.summary.strategy);
+            continue;
+        };
+        let (now, was) = (&result.summary, &base.summary);
+        let label = format!("{} / {}", result.case, now.strategy);
+        let share = |s: &RunSummary| if s.items > 0 { s.placed as f64 / s.items as f64 } else { 1.0 };
+
+        let mut drop = |metric: &str, now: f64, was: f64| {
+            if was - now > args.threshold {
+                regressions.push(format!("{}: {} fell from {:.3} to {:.3}", label, metric, was, now));
+            }
+        };
+        drop("placed share", share(now), share(was));
+        drop("volume utilisation", now.volume_utilisation, was.volume_utilisation);
+        drop("preferred-zone rate", now.preferred_zone_rate, was.preferred_zone_rate);
+
+        if now.mean_retrieval_steps - was.mean_retrieval_steps > args.threshold * was.mean_retrieval_steps.max(1.0) {
+            regressions.push(format!("{}: mean retrieval steps rose from {:.3} to {:.3}", label, was.mean_retrieval_steps, now.mean_retrieval_steps));
+        }
+        if result.violations > base.violations {
+            regressions.push(format!("{}: validity violations rose from {} to {}", label, base.violations, result.violations));
+        }
+        if let Some(factor) = args.max_slowdown {
+            if now.duration_ms > was.duration_ms * factor {
+                regressions.push(format!("{}: runtime rose from {:.1} ms to {:.1} ms", label, was.duration_ms, now.duration_ms));
+            }
+        }
+    }
+    regressions
+}
@@ -0,0 +1,3 @@
+-- Bumped whenever a container's contents or geometry change, so writers can
+-- detect that the state they planned against is stale
+ALTER TABLE containers ADD COLUMN version INTEGER NOT NULL DEFAULT 0;
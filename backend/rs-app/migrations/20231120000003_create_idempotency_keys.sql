@@ -0,0 +1,11 @@
+-- Responses to requests sent with an Idempotency-Key header, replayed on retries
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    "key" TEXT PRIMARY KEY,
+    fingerprint TEXT NOT NULL, -- Hash of method, path and body of the first request
+    status INTEGER, -- NULL while the first request is still being handled
+    "contentType" TEXT,
+    body BLOB,
+    "createdAt" TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_createdAt ON idempotency_keys("createdAt");
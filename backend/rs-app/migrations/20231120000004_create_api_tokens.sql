@@ -0,0 +1,10 @@
+-- API tokens; only a SHA-256 hash of each token is stored
+CREATE TABLE IF NOT EXISTS api_tokens (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    "tokenHash" TEXT NOT NULL UNIQUE,
+    role TEXT CHECK(role IN ('crew', 'planner', 'admin')) NOT NULL,
+    "createdAt" TEXT NOT NULL,
+    "lastUsedAt" TEXT,
+    "revokedAt" TEXT
+);
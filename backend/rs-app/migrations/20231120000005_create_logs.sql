@@ -0,0 +1,11 @@
+-- Who placed or retrieved what, and when; rows are only ever appended
+CREATE TABLE IF NOT EXISTS logs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp TEXT NOT NULL,
+    "userId" TEXT NOT NULL,
+    "actionType" TEXT CHECK("actionType" IN ('placement', 'retrieval')) NOT NULL,
+    "itemId" TEXT NOT NULL,
+    "containerId" TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_logs_timestamp ON logs(timestamp);
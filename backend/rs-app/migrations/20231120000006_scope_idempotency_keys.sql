@@ -0,0 +1,22 @@
+-- Idempotency keys are chosen by clients, so two callers may pick the same one;
+-- scope them by caller so neither can block or replay the other's request
+CREATE TABLE idempotency_keys_scoped (
+    owner TEXT NOT NULL, -- Name of the authenticated caller, empty for anonymous requests
+    "key" TEXT NOT NULL,
+    fingerprint TEXT NOT NULL, -- Hash of method, path and body of the first request
+    status INTEGER, -- NULL while the first request is still being handled
+    "contentType" TEXT,
+    body BLOB,
+    "createdAt" TEXT NOT NULL,
+    PRIMARY KEY (owner, "key")
+);
+
+-- Earlier fingerprints hash in the caller, so no new request can match these;
+-- they are kept unowned only so in-flight keys still block until they expire
+INSERT INTO idempotency_keys_scoped (owner, "key", fingerprint, status, "contentType", body, "createdAt")
+    SELECT '', "key", fingerprint, status, "contentType", body, "createdAt" FROM idempotency_keys;
+
+DROP TABLE idempotency_keys;
+ALTER TABLE idempotency_keys_scoped RENAME TO idempotency_keys;
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_createdAt ON idempotency_keys("createdAt");
@@ -0,0 +1,143 @@
+use crate::config::AuthConfig;
+use crate::errors::ApiError;
+use crate::models::Role;
+use actix_web::body::BoxBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header;
+use actix_web::middleware::Next;
+use actix_web::{web, FromRequest, HttpMessage, HttpRequest, ResponseError};
+use chrono::Utc;
+use futures::future::{ready, Ready};
+use jsonwebtoken::{decode, Algorithm, DecodingKey, Validation};
+use tracing::{debug, warn};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use subtle::ConstantTimeEq;
+
+const TOKEN_PREFIX: &str = "iss_";
+
+/// Who is making the request, resolved by [`authenticate`].
+#[derive(Debug, Clone)]
+pub struct Identity {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: String,
+    role: Role,
+}
+
+pub fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub fn generate_token() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret);
+    format!("{}{}", TOKEN_PREFIX, secret.iter().map(|b| format!("{:02x}", b)).collect::<String>())
+}
+
+fn bearer_token(req: &ServiceRequest) -> Option<String> {
+    if let Some(value) = req.headers().get(header::AUTHORIZATION) {
+        return value.to_str().ok()
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(|t| t.trim().to_string());
+    }
+    // Browsers' EventSource cannot send headers, so the event stream also takes a query parameter
+    if req.path().ends_with("/events") {
+        return web::Query::<TokenQuery>::from_query(req.query_string()).ok().and_then(|q| q.into_inner().access_token);
+    }
+    None
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    access_token: Option<String>,
+}
+
+async fn resolve(db_pool: &SqlitePool, config: &AuthConfig, token: &str) -> Result<Option<Identity>, sqlx::Error> {
+    // Compared in constant time, so response timing does not reveal how much of a guess matched
+    let is_admin_token = config.admin_token.as_ref().is_some_and(|admin| bool::from(admin.as_bytes().ct_eq(token.as_bytes())));
+    if is_admin_token {
+        return Ok(Some(Identity { name: "admin".to_string(), role: Role::Admin }));
+    }
+
+    if let (Some(secret), 3) = (&config.jwt_secret, token.split('.').count()) {
+        let claims = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &Validation::new(Algorithm::HS256));
+        return Ok(match claims {
+            Ok(data) => Some(Identity { name: data.claims.sub, role: data.claims.role }),
+            Err(e) => {
+                debug!("Rejected JWT: {}", e);
+                None
+            }
+        });
+    }
+
+    let row: Option<(String, String)> = sqlx::query_as(
+        r#"UPDATE api_tokens SET "lastUsedAt" = ? WHERE "tokenHash" = ? AND "revokedAt" IS NULL RETURNING name, role"#)
+        .bind(Utc::now())
+        .bind(hash_token(token))
+        .fetch_optional(db_pool)
+        .await?;
+    Ok(row.and_then(|(name, role)| Role::parse(&role).map(|role| Identity { name, role })))
+}
+
+/// Resolves the bearer token (if any) into an [`Identity`] stored in the request
+/// extensions. Requests without a token pass through; routes that need one are
+/// wrapped in a role check.
+pub async fn authenticate(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let Some(config) = req.app_data::<web::Data<AuthConfig>>().cloned() else {
+        warn!("Authentication middleware has no config; treating the request as anonymous");
+        return next.call(req).await;
+    };
+    if config.disabled {
+        req.extensions_mut().insert(Identity { name: "anonymous".to_string(), role: Role::Admin });
+        return next.call(req).await;
+    }
+
+    if let Some(token) = bearer_token(&req) {
+        let Some(db_pool) = req.app_data::<web::Data<SqlitePool>>().cloned() else {
+            return Ok(req.into_response(ApiError::Unauthorized.error_response()));
+        };
+        match resolve(&db_pool, &config, &token).await {
+            Ok(Some(identity)) => { req.extensions_mut().insert(identity); }
+            Ok(None) => return Ok(req.into_response(ApiError::Unauthorized.error_response())),
+            Err(e) => return Ok(req.into_response(ApiError::from(e).error_response())),
+        }
+    }
+    next.call(req).await
+}
+
+async fn require(role: Role, req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let allowed = req.extensions().get::<Identity>().map(|identity| identity.role >= role);
+    match allowed {
+        Some(true) => next.call(req).await,
+        Some(false) => Ok(req.into_response(ApiError::Forbidden(role.as_str()).error_response())),
+        None => Ok(req.into_response(ApiError::Unauthorized.error_response())),
+    }
+}
+
+pub async fn require_crew(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    require(Role::Crew, req, next).await
+}
+
+pub async fn require_planner(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    require(Role::Planner, req, next).await
+}
+
+pub async fn require_admin(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    require(Role::Admin, req, next).await
+}
+
+impl FromRequest for Identity {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(req.extensions().get::<Identity>().cloned().ok_or_else(|| ApiError::Unauthorized.into()))
+    }
+}
@@ -0,0 +1,225 @@
+//! Offline placement planning: reads items and containers from dataset files,
+//! runs the placement engine and writes the results, without a server or database.
+//! Can also generate such dataset files.
+
+use chrono::NaiveDate;
+use clap::{Parser, Subcommand, ValueEnum};
+use placement_service::config::Config;
+use placement_service::models::*;
+use placement_service::strategy::Strategy;
+use placement_service::validation::Validate;
+use placement_service::generator::{self, GeneratorOptions};
+use placement_service::{dataset, report};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Instant;
+use tracing_subscriber::EnvFilter;
+
+/// Plan cargo placement from dataset files (the formats in generate-dataset/),
+/// or generate such files.
+#[derive(Debug, Parser)]
+#[command(name = "placement-cli", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    Plan(PlanArgs),
+    Generate(GenerateArgs),
+}
+
+/// Place items into containers.
+///
+/// Writes placements, rearrangements and metrics.json to the output directory.
+/// Exits with 0 when every item was placed, 2 when some were not and 1 on errors.
+#[derive(Debug, clap::Args)]
+struct PlanArgs {
+    /// Items to place (.json or .csv)
+    #[arg(long)]
+    items: PathBuf,
+    /// Containers to place them in (.json or .csv); defaults to the `containers` of the items file
+    #[arg(long)]
+    containers: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = Strategy::Phased)]
+    strategy: Strategy,
+    /// Config file whose [placement] section sets the tuning; other sections are ignored
+    #[arg(long)]
+    config: Option<PathBuf>,
+    #[arg(long)]
+    high_priority_threshold: Option<i32>,
+    #[arg(long)]
+    grid_divisions: Option<u32>,
+    #[arg(long)]
+    min_grid_step: Option<f64>,
+    #[arg(long)]
+    fallback_grid_step: Option<f64>,
+    #[arg(long)]
+    tolerance: Option<f64>,
+    /// Attach the reasoning behind each placement (phased strategy only)
+    #[arg(long)]
+    explain: bool,
+    #[arg(long, default_value = ".")]
+    out_dir: PathBuf,
+    /// Format of the placements and rearrangements files; metrics are always JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+}
+
+/// Generate a synthetic manifest like generate-dataset/generate_item.py and generate_container.py.
+///
+/// Writes items and containers (and, with --placed, an arrangement of the first
+/// items) to the output directory. The same seed and date give the same files.
+#[derive(Debug, clap::Args)]
+struct GenerateArgs {
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    #[arg(long, default_value_t = 2000)]
+    items: usize,
+    #[arg(long, default_value_t = 2)]
+    min_containers_per_zone: usize,
+    #[arg(long, default_value_t = 5)]
+    max_containers_per_zone: usize,
+    /// Plan this many of the items into the containers and write the arrangement
+    #[arg(long, default_value_t = 0)]
+    placed: usize,
+    /// Day expiry dates are counted from (YYYY-MM-DD); defaults to today
+    #[arg(long)]
+    today: Option<NaiveDate>,
+    #[arg(long, default_value = ".")]
+    out_dir: PathBuf,
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Csv,
+}
+
+impl OutputFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")))
+        .init();
+
+    let result = match &cli.command {
+        Command::Plan(args) => plan(args).map(|summary| {
+            eprintln!(
+                "{}: placed {}/{} items ({} failed, {} moved) in {:.1} ms; output in {}",
+                summary.strategy, summary.placed, summary.items, summary.failed, summary.rearranged,
+                summary.duration_ms, args.out_dir.display(),
+            );
+            if summary.failed == 0 { ExitCode::SUCCESS } else { ExitCode::from(2) }
+        }),
+        Command::Generate(args) => generate(args).map(|_| ExitCode::SUCCESS),
+    };
+    result.unwrap_or_else(|message| {
+        eprintln!("placement-cli: {}", message);
+        ExitCode::FAILURE
+    })
+}
+
+fn plan(args: &PlanArgs) -> Result<report::RunSummary, String> {
+    let config = load_config(args)?;
+    config.install();
+
+    let items = dataset::read_items(&args.items).map_err(|e| e.to_string())?;
+    let containers = dataset::read_containers(args.containers.as_deref().unwrap_or(&args.items)).map_err(|e| e.to_string())?;
+    let request = PlacementRequest {
+        items,
+        containers,
+        use_stored_containers: false,
+        zones: None,
+        explain: args.explain,
+    };
+    if let Err(e) = request.validate() {
+        let details: Vec<String> = e.details().iter().map(|d| format!("{}: {}", d.field, d.message)).collect();
+        return Err(format!("invalid input:\n  {}", details.join("\n  ")));
+    }
+
+    let started = Instant::now();
+    let outcome = args.strategy.run(&request.items, &request.containers, request.explain)?;
+    let summary = report::summarize(args.strategy.name(), &request.containers, &request.items, &outcome.placements, &outcome.rearrangements, started.elapsed());
+
+    std::fs::create_dir_all(&args.out_dir).map_err(|e| format!("cannot create {}: {}", args.out_dir.display(), e))?;
+    match args.format {
+        // Written here rather than by the dataset module so explanations of unplaced items are kept
+        OutputFormat::Json => write_json(&args.out_dir.join("placements.json"), &PlacementsFile { placements: &outcome.placements, unplaced: &outcome.unplaced })?,
+        OutputFormat::Csv => dataset::write_placements(&args.out_dir.join("placements.csv"), &outcome.placements).map_err(|e| e.to_string())?,
+    }
+    let rearrangements = args.out_dir.join(format!("rearrangements.{}", args.format.extension()));
+    dataset::write_rearrangements(&rearrangements, &outcome.rearrangements).map_err(|e| e.to_string())?;
+    write_json(&args.out_dir.join("metrics.json"), &summary)?;
+    Ok(summary)
+}
+
+fn generate(args: &GenerateArgs) -> Result<(), String> {
+    if args.min_containers_per_zone == 0 || args.min_containers_per_zone > args.max_containers_per_zone {
+        return Err("--min-containers-per-zone must be at least 1 and at most --max-containers-per-zone".to_string());
+    }
+    let mut options = GeneratorOptions {
+        seed: args.seed,
+        items: args.items,
+        containers_per_zone: args.min_containers_per_zone..=args.max_containers_per_zone,
+        placed: args.placed,
+        ..GeneratorOptions::default()
+    };
+    if let Some(today) = args.today {
+        options.today = today;
+    }
+    let data = generator::generate(&options);
+
+    std::fs::create_dir_all(&args.out_dir).map_err(|e| format!("cannot create {}: {}", args.out_dir.display(), e))?;
+    let path = |name: &str| args.out_dir.join(format!("{}.{}", name, args.format.extension()));
+    dataset::write_items(&path("items"), &data.items).map_err(|e| e.to_string())?;
+    dataset::write_containers(&path("containers"), &data.containers).map_err(|e| e.to_string())?;
+    if args.placed > 0 {
+        dataset::write_placements(&path("arrangement"), &data.placements).map_err(|e| e.to_string())?;
+    }
+    eprintln!(
+        "Generated {} items in {} containers ({} pre-placed); output in {}",
+        data.items.len(), data.containers.len(), data.placements.len(), args.out_dir.display(),
+    );
+    Ok(())
+}
+
+fn load_config(args: &PlanArgs) -> Result<Config, String> {
+    let mut config = match &args.config {
+        Some(path) => Config::from_file(path).map_err(|e| e.to_string())?,
+        None => Config::default(),
+    };
+    let p = &mut config.placement;
+    if let Some(v) = args.high_priority_threshold { p.high_priority_threshold = v; }
+    if let Some(v) = args.grid_divisions { p.grid_divisions = v; }
+    if let Some(v) = args.min_grid_step { p.min_grid_step = v; }
+    if let Some(v) = args.fallback_grid_step { p.fallback_grid_step = v; }
+    if let Some(v) = args.tolerance { p.tolerance = v; }
+    config.validate().map_err(|e| e.to_string())?;
+    Ok(config)
+}
+
+#[derive(Serialize)]
+struct PlacementsFile<'a> {
+    placements: &'a [PlacementResult],
+    #[serde(skip_serializing_if = "<[UnplacedItem]>::is_empty")]
+    unplaced: &'a [UnplacedItem],
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, text + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
@@ -0,0 +1,124 @@
+use crate::config::{self, tolerance};
+use crate::models::*;
+use crate::rearrangement::{self, PlanError};
+use crate::simulation::{carried_by, find_spot_in_container, ContainerState};
+use tracing::debug;
+use std::collections::HashMap;
+
+pub struct CompactionPlan {
+    pub steps: Vec<RearrangementStep>,
+    pub moves: usize,
+    pub truncated: bool,
+}
+
+fn dims_of(position: &Position) -> (f64, f64, f64) {
+    (
+        position.end_coordinates.width - position.start_coordinates.width,
+        position.end_coordinates.depth - position.start_coordinates.depth,
+        position.end_coordinates.height - position.start_coordinates.height,
+    )
+}
+
+// High-priority items improve by moving towards the open face, the rest by moving
+// towards the back wall. Both then prefer lower and further-left spots.
+fn is_improvement(current: &Position, candidate: &Position, is_high_priority: bool) -> bool {
+    let (cur, cand) = (&current.start_coordinates, &candidate.start_coordinates);
+    let depth_gain = if is_high_priority { cur.depth - cand.depth } else { cand.depth - cur.depth };
+    if depth_gain.abs() > tolerance() { return depth_gain > 0.0; }
+    if (cur.height - cand.height).abs() > tolerance() { return cand.height < cur.height; }
+    cand.width + tolerance() < cur.width
+}
+
+/// Plans a compaction of `container`: high-priority items are pulled to the front,
+/// the rest pushed to the back, so the free space ends up in one block. Only items
+/// whose spot actually improves are moved, items something rests on stay put, and
+/// at most `max_moves` moves are planned.
+pub fn plan_compaction(
+    container: &Container,
+    placements: &[(String, Position)],
+    priorities: &HashMap<String, i32>,
+    max_moves: usize,
+) -> Result<CompactionPlan, PlanError> {
+    let mut current: Vec<(String, Position)> = placements.to_vec();
+    let mut order: Vec<String> = placements.iter().map(|(id, _)| id.clone()).collect();
+    order.sort_by_key(|id| std::cmp::Reverse(priorities.get(id).copied().unwrap_or(0)));
+
+    let mut moves: Vec<RearrangementStep> = Vec::new();
+    let mut truncated = false;
+
+    'passes: for pass in 0..config::placement().compaction_max_passes {
+        let mut moved_in_pass = false;
+        for item_id in &order {
+            let Some(idx) = current.iter().position(|(id, _)| id == item_id) else { continue };
+            let (_, from_position) = current.remove(idx);
+            // Items resting on this one alone would be left floating
+            if carried_by(&from_position, &current).next().is_some() {
+                current.insert(idx, (item_id.clone(), from_position));
+                continue;
+            }
+            let is_high_prio = priorities.get(item_id).copied().unwrap_or(0) >= config::placement().high_priority_threshold;
+
+            let better = find_spot_in_container(dims_of(&from_position), container, &current, is_high_prio)
+                .map(|(position, _)| position)
+                .filter(|position| is_improvement(&from_position, position, is_high_prio));
+
+            match better {
+                Some(to_position) if moves.len() < max_moves => {
+                    debug!("Compaction pass {}: moving {} in {}", pass + 1, item_id, container.container_id);
+                    moves.push(RearrangementStep {
+                        step: 0,
+                        action: "move".to_string(),
+                        item_id: item_id.clone(),
+                        from_container: Some(container.container_id.clone()),
+                        from_position: Some(from_position),
+                        to_container: Some(container.container_id.clone()),
+                        to_position: Some(to_position.clone()),
+                    });
+                    current.push((item_id.clone(), to_position));
+                    moved_in_pass = true;
+                }
+                Some(_) => {
+                    current.push((item_id.clone(), from_position));
+                    truncated = true;
+                    break 'passes;
+                }
+                None => current.push((item_id.clone(), from_position)),
+            }
+        }
+        if !moved_in_pass { break; }
+    }
+
+    let initial: ContainerState = HashMap::from([(container.container_id.clone(), placements.to_vec())]);
+    let collapsed = collapse_moves(&moves);
+    let (steps, move_count) = match rearrangement::sequence_plan(&initial, &collapsed) {
+        Ok(steps) => (steps, collapsed.len()),
+        Err(e) => {
+            debug!("Collapsed compaction plan not executable ({}), keeping individual moves", e);
+            (rearrangement::sequence_plan(&initial, &moves)?, moves.len())
+        }
+    };
+
+    Ok(CompactionPlan { steps, moves: move_count, truncated })
+}
+
+// Replaces repeated moves of the same item with a single move from its original
+// spot to its final one, ordered by when the item reaches its final spot.
+fn collapse_moves(moves: &[RearrangementStep]) -> Vec<RearrangementStep> {
+    let mut collapsed: Vec<RearrangementStep> = Vec::new();
+    for m in moves {
+        match collapsed.iter().position(|c| c.item_id == m.item_id) {
+            Some(idx) => {
+                let mut merged = collapsed.remove(idx);
+                merged.to_position = m.to_position.clone();
+                collapsed.push(merged);
+            }
+            None => collapsed.push(m.clone()),
+        }
+    }
+    // An item that ends up where it started needs no move at all
+    collapsed.retain(|m| match (&m.from_position, &m.to_position) {
+        (Some(from), Some(to)) => !rearrangement::same_position(from, to),
+        _ => true,
+    });
+    collapsed
+}
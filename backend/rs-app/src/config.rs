@@ -0,0 +1,299 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::OnceLock;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Service settings, read from a TOML file (`CONFIG_FILE`, default `config.toml`)
+/// and then overridden by environment variables. Missing keys take the defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub auth: AuthConfig,
+    pub idempotency: IdempotencySettings,
+    pub placement: PlacementTuning,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    // Origins the frontend is served from; empty refuses cross-origin requests
+    pub cors_allowed_origins: Vec<String>,
+    pub max_body_bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    // Every request is treated as an admin; only allowed on a loopback bind address
+    pub disabled: bool,
+    // Accepted as an admin token without a database entry, to create the first tokens
+    #[serde(serialize_with = "redact")]
+    #[schema(value_type = Option<String>)]
+    pub admin_token: Option<String>,
+    // Enables HS256-signed JWTs carrying `sub` and `role` claims
+    #[serde(serialize_with = "redact")]
+    #[schema(value_type = Option<String>)]
+    pub jwt_secret: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencySettings {
+    // How long responses to requests with an Idempotency-Key are replayed for
+    pub ttl_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // Directives such as "info,placement_service=debug"; RUST_LOG wins when set
+    pub filter: String,
+    pub format: LogFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    Text,
+    // One JSON object per line, with the fields of every enclosing span
+    Json,
+}
+
+/// Knobs of the placement heuristics, shared by the planner, suggestions and compaction.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct PlacementTuning {
+    // Items at or above this priority are kept near the open face
+    pub high_priority_threshold: i32,
+    // The spot search tries this many steps along the container's width and depth
+    pub grid_divisions: u32,
+    pub min_grid_step: f64,
+    // Finest step of the extreme-point engine's fallback grid search, in cm
+    pub fallback_grid_step: f64,
+    // Slack for floating-point comparisons of coordinates
+    pub tolerance: f64,
+    pub compaction_max_passes: usize,
+    // Weights of the suggestion score components; they must sum to 1
+    pub zone_weight: f64,
+    pub retrieval_weight: f64,
+    pub utilisation_weight: f64,
+    pub stability_weight: f64,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            bind_address: "127.0.0.1:8080".to_string(),
+            cors_allowed_origins: Vec::new(),
+            max_body_bytes: 2 * 1024 * 1024,
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig { url: "sqlite:new_placement_app.db".to_string(), max_connections: 5 }
+    }
+}
+
+impl Default for IdempotencySettings {
+    fn default() -> Self {
+        IdempotencySettings { ttl_secs: 24 * 60 * 60 }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig { filter: "info".to_string(), format: LogFormat::Text }
+    }
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!("unknown log format '{}'; expected text or json", other)),
+        }
+    }
+}
+
+impl Default for PlacementTuning {
+    fn default() -> Self {
+        PlacementTuning {
+            high_priority_threshold: 50,
+            grid_divisions: 10,
+            min_grid_step: 0.01,
+            fallback_grid_step: 1.0,
+            tolerance: 1e-6,
+            compaction_max_passes: 5,
+            zone_weight: 0.4,
+            retrieval_weight: 0.3,
+            utilisation_weight: 0.15,
+            stability_weight: 0.15,
+        }
+    }
+}
+
+// Secrets are shown as set or unset, never echoed back
+fn redact<S: Serializer>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_some("********"),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Cannot read config file {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("Invalid config file {}: {source}", path.display())]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("Invalid value for {var}: {message}")]
+    Env { var: &'static str, message: String },
+    #[error("Invalid configuration:\n  {}", .0.join("\n  "))]
+    Invalid(Vec<String>),
+}
+
+static PLACEMENT: OnceLock<PlacementTuning> = OnceLock::new();
+
+/// Placement tuning in effect; the defaults until [`Config::install`] runs.
+pub fn placement() -> &'static PlacementTuning {
+    PLACEMENT.get_or_init(PlacementTuning::default)
+}
+
+/// Slack for coordinate comparisons, from the placement tuning.
+pub fn tolerance() -> f64 {
+    placement().tolerance
+}
+
+impl Config {
+    pub fn load() -> Result<Config, ConfigError> {
+        let (path, required) = match std::env::var("CONFIG_FILE") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+        };
+        let mut config = if required || path.exists() { Config::from_file(&path)? } else { Config::default() };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Config, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|source| ConfigError::Read { path: path.to_path_buf(), source })?;
+        toml::from_str(&text).map_err(|source| ConfigError::Parse { path: path.to_path_buf(), source })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        env_override("BIND_ADDRESS", &mut self.server.bind_address)?;
+        if let Ok(origins) = std::env::var("CORS_ALLOWED_ORIGINS") {
+            self.server.cors_allowed_origins = origins.split(',').map(|o| o.trim().to_string()).filter(|o| !o.is_empty()).collect();
+        }
+        env_override("MAX_BODY_BYTES", &mut self.server.max_body_bytes)?;
+        env_override("DATABASE_URL", &mut self.database.url)?;
+        env_override("DATABASE_MAX_CONNECTIONS", &mut self.database.max_connections)?;
+        if let Ok(v) = std::env::var("AUTH_DISABLED") {
+            self.auth.disabled = v == "1" || v.eq_ignore_ascii_case("true");
+        }
+        env_override_secret("ADMIN_TOKEN", &mut self.auth.admin_token);
+        env_override_secret("JWT_SECRET", &mut self.auth.jwt_secret);
+        env_override("IDEMPOTENCY_TTL_SECS", &mut self.idempotency.ttl_secs)?;
+
+        let p = &mut self.placement;
+        env_override("PLACEMENT_HIGH_PRIORITY_THRESHOLD", &mut p.high_priority_threshold)?;
+        env_override("PLACEMENT_GRID_DIVISIONS", &mut p.grid_divisions)?;
+        env_override("PLACEMENT_MIN_GRID_STEP", &mut p.min_grid_step)?;
+        env_override("PLACEMENT_FALLBACK_GRID_STEP", &mut p.fallback_grid_step)?;
+        env_override("PLACEMENT_TOLERANCE", &mut p.tolerance)?;
+        env_override("PLACEMENT_COMPACTION_MAX_PASSES", &mut p.compaction_max_passes)?;
+        env_override("PLACEMENT_ZONE_WEIGHT", &mut p.zone_weight)?;
+        env_override("PLACEMENT_RETRIEVAL_WEIGHT", &mut p.retrieval_weight)?;
+        env_override("PLACEMENT_UTILISATION_WEIGHT", &mut p.utilisation_weight)?;
+        env_override("PLACEMENT_STABILITY_WEIGHT", &mut p.stability_weight)?;
+        env_override("RUST_LOG", &mut self.logging.filter)?;
+        env_override("LOG_FORMAT", &mut self.logging.format)?;
+        Ok(())
+    }
+
+    /// Collects every problem at once so a bad deployment is fixed in one go.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: String| if !ok { problems.push(problem) };
+
+        let port = self.server.bind_address.rsplit_once(':').map(|(host, port)| (host, port.parse::<u16>()));
+        check(matches!(port, Some((host, Ok(_))) if !host.is_empty()),
+            format!("server.bind_address: '{}' is not host:port", self.server.bind_address));
+        // With auth off every caller is an admin, so nobody else may be able to connect
+        check(!self.auth.disabled || port.is_some_and(|(host, _)| is_loopback(host)),
+            format!("auth.disabled: only allowed when server.bind_address is a loopback address, not '{}'", self.server.bind_address));
+        for origin in &self.server.cors_allowed_origins {
+            check(origin.starts_with("http://") || origin.starts_with("https://"),
+                format!("server.cors_allowed_origins: '{}' must start with http:// or https://", origin));
+        }
+        check(self.server.max_body_bytes > 0, "server.max_body_bytes: must be positive".to_string());
+        check(self.database.url.starts_with("sqlite:"), format!("database.url: '{}' is not a sqlite: URL", self.database.url));
+        check(self.database.max_connections >= 1, "database.max_connections: must be at least 1".to_string());
+        check(self.idempotency.ttl_secs > 0, "idempotency.ttl_secs: must be positive".to_string());
+
+        let p = &self.placement;
+        check((0..=100).contains(&p.high_priority_threshold), "placement.high_priority_threshold: must be between 0 and 100".to_string());
+        check(p.grid_divisions >= 1, "placement.grid_divisions: must be at least 1".to_string());
+        check(p.min_grid_step > 0.0, "placement.min_grid_step: must be positive".to_string());
+        check(p.fallback_grid_step > 0.0, "placement.fallback_grid_step: must be positive".to_string());
+        check(p.tolerance > 0.0 && p.tolerance <= 1e-2, "placement.tolerance: must be in (0, 0.01]".to_string());
+        check(p.compaction_max_passes >= 1, "placement.compaction_max_passes: must be at least 1".to_string());
+        let weights = [p.zone_weight, p.retrieval_weight, p.utilisation_weight, p.stability_weight];
+        check(weights.iter().all(|w| *w >= 0.0), "placement: score weights must not be negative".to_string());
+        check((weights.iter().sum::<f64>() - 1.0).abs() < 1e-6, "placement: score weights must sum to 1".to_string());
+        if let Err(e) = tracing_subscriber::EnvFilter::try_new(&self.logging.filter) {
+            problems.push(format!("logging.filter: {}", e));
+        }
+
+        if problems.is_empty() { Ok(()) } else { Err(ConfigError::Invalid(problems)) }
+    }
+
+    /// Makes the placement tuning visible to [`placement`]; call once at startup.
+    pub fn install(&self) {
+        if PLACEMENT.set(self.placement.clone()).is_err() {
+            tracing::warn!("Placement tuning was already in use; keeping the earlier values");
+        }
+    }
+}
+
+fn env_override<T: FromStr>(var: &'static str, target: &mut T) -> Result<(), ConfigError>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Ok(raw) = std::env::var(var) {
+        *target = raw.trim().parse().map_err(|e: T::Err| ConfigError::Env { var, message: e.to_string() })?;
+    }
+    Ok(())
+}
+
+fn is_loopback(host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+fn env_override_secret(var: &'static str, target: &mut Option<String>) {
+    if let Ok(value) = std::env::var(var) {
+        *target = Some(value).filter(|v| !v.is_empty());
+    }
+}
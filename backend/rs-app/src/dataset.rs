@@ -0,0 +1,279 @@
+use crate::models::{Container, Coordinates, Item, PlacementResult, Position, RearrangementStep};
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+// Formats of the files in `generate-dataset/`. JSON files hold an object with
+// `items` and/or `containers` arrays in the API's shape; CSV files hold one of the two.
+
+#[derive(Debug, Error)]
+pub enum DatasetError {
+    #[error("Cannot read {}: {source}", path.display())]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("Invalid JSON in {}: {source}", path.display())]
+    Json { path: PathBuf, source: serde_json::Error },
+    #[error("Invalid CSV in {}: {source}", path.display())]
+    Csv { path: PathBuf, source: csv::Error },
+    #[error("Cannot write {}: {source}", path.display())]
+    Write { path: PathBuf, source: std::io::Error },
+    #[error("{}, row {row}: {message}", path.display())]
+    Row { path: PathBuf, row: usize, message: String },
+    #[error("Unsupported file type {}; expected .json or .csv", path.display())]
+    UnknownFormat { path: PathBuf },
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonDataset {
+    #[serde(default)]
+    items: Vec<JsonItem>,
+    #[serde(default)]
+    containers: Vec<Container>,
+}
+
+// Like `Item`, but some datasets leave usageLimit null
+#[derive(Debug, Deserialize)]
+struct JsonItem {
+    #[serde(rename = "itemId")]
+    item_id: String,
+    name: String,
+    width: f64,
+    depth: f64,
+    height: f64,
+    mass: Option<f64>,
+    priority: i32,
+    #[serde(rename = "expiryDate", default)]
+    expiry_date: Option<DateTime<Utc>>,
+    #[serde(rename = "usageLimit", default)]
+    usage_limit: Option<i32>,
+    #[serde(rename = "preferredZone")]
+    preferred_zone: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvItem {
+    item_id: String,
+    name: String,
+    width_cm: f64,
+    depth_cm: f64,
+    height_cm: f64,
+    mass_kg: Option<f64>,
+    priority: i32,
+    expiry_date: Option<String>,
+    usage_limit: Option<i32>,
+    preferred_zone: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CsvContainer {
+    zone: String,
+    container_id: String,
+    width_cm: f64,
+    depth_cm: f64,
+    height_cm: f64,
+}
+
+enum Format {
+    Json,
+    Csv,
+}
+
+fn format_of(path: &Path) -> Result<Format, DatasetError> {
+    match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+        Some("json") => Ok(Format::Json),
+        Some("csv") => Ok(Format::Csv),
+        _ => Err(DatasetError::UnknownFormat { path: path.to_path_buf() }),
+    }
+}
+
+fn read_json(path: &Path) -> Result<JsonDataset, DatasetError> {
+    let text = std::fs::read_to_string(path).map_err(|source| DatasetError::Read { path: path.to_path_buf(), source })?;
+    serde_json::from_str(&text).map_err(|source| DatasetError::Json { path: path.to_path_buf(), source })
+}
+
+fn read_csv<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<Vec<T>, DatasetError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_path(path)
+        .map_err(|source| DatasetError::Csv { path: path.to_path_buf(), source })?;
+    reader.deserialize().collect::<Result<Vec<T>, _>>()
+        .map_err(|source| DatasetError::Csv { path: path.to_path_buf(), source })
+}
+
+// Dates are written as "2025-10-23", full timestamps or "N/A"
+fn parse_expiry(value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty() && !v.eq_ignore_ascii_case("n/a")) else {
+        return Ok(None);
+    };
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).map(|d| d.and_utc()));
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| Some(d.with_timezone(&Utc)))
+        .map_err(|_| format!("expiry_date '{}' is not a date", value))
+}
+
+/// Reads the items of a dataset file, by extension.
+pub fn read_items(path: &Path) -> Result<Vec<Item>, DatasetError> {
+    match format_of(path)? {
+        Format::Json => Ok(read_json(path)?.items.into_iter().map(|i| Item {
+            item_id: i.item_id,
+            name: i.name,
+            width: i.width,
+            depth: i.depth,
+            height: i.height,
+            mass: i.mass,
+            priority: i.priority,
+            expiry_date: i.expiry_date,
+            usage_limit: i.usage_limit.unwrap_or(i32::MAX), // No recorded limit
+            current_uses: 0,
+            preferred_zone: i.preferred_zone,
+            status: None,
+        }).collect()),
+        Format::Csv => read_csv::<CsvItem>(path)?.into_iter().enumerate().map(|(i, row)| {
+            let expiry_date = parse_expiry(row.expiry_date.as_deref())
+                .map_err(|message| DatasetError::Row { path: path.to_path_buf(), row: i + 1, message })?;
+            Ok(Item {
+                item_id: row.item_id,
+                name: row.name,
+                width: row.width_cm,
+                depth: row.depth_cm,
+                height: row.height_cm,
+                mass: row.mass_kg,
+                priority: row.priority,
+                expiry_date,
+                usage_limit: row.usage_limit.unwrap_or(i32::MAX), // No recorded limit
+                current_uses: 0,
+                preferred_zone: row.preferred_zone,
+                status: None,
+            })
+        }).collect(),
+    }
+}
+
+/// Reads the containers of a dataset file, by extension.
+pub fn read_containers(path: &Path) -> Result<Vec<Container>, DatasetError> {
+    match format_of(path)? {
+        Format::Json => Ok(read_json(path)?.containers),
+        Format::Csv => Ok(read_csv::<CsvContainer>(path)?.into_iter().map(|row| Container {
+            container_id: row.container_id,
+            zone: row.zone,
+            width: row.width_cm,
+            depth: row.depth_cm,
+            height: row.height_cm,
+            is_waste_container: None,
+            max_weight_capacity: None,
+        }).collect()),
+    }
+}
+
+// --- Writing ---
+
+#[derive(Serialize)]
+struct JsonItems<'a> {
+    items: &'a [Item],
+}
+
+#[derive(Serialize)]
+struct JsonContainers<'a> {
+    containers: &'a [Container],
+}
+
+#[derive(Serialize)]
+struct JsonPlacements<'a> {
+    placements: &'a [PlacementResult],
+}
+
+#[derive(Serialize)]
+struct JsonRearrangements<'a> {
+    rearrangements: &'a [RearrangementStep],
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), DatasetError> {
+    let text = serde_json::to_string_pretty(value).map_err(|source| DatasetError::Json { path: path.to_path_buf(), source })?;
+    std::fs::write(path, text + "\n").map_err(|source| DatasetError::Write { path: path.to_path_buf(), source })
+}
+
+fn write_csv(path: &Path, header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> Result<(), DatasetError> {
+    let fail = |source: csv::Error| DatasetError::Csv { path: path.to_path_buf(), source };
+    let mut writer = csv::Writer::from_path(path).map_err(fail)?;
+    writer.write_record(header).map_err(fail)?;
+    for row in rows {
+        writer.write_record(&row).map_err(fail)?;
+    }
+    writer.flush().map_err(|source| DatasetError::Write { path: path.to_path_buf(), source })
+}
+
+// Same layout as the arrangement CSVs in backend/app
+fn coordinates(c: &Coordinates) -> String {
+    format!("({:?},{:?},{:?})", c.width, c.depth, c.height)
+}
+
+fn optional_coordinates(position: Option<&Position>, end: bool) -> String {
+    position.map(|p| coordinates(if end { &p.end_coordinates } else { &p.start_coordinates })).unwrap_or_default()
+}
+
+/// Writes items in the layout `read_items` accepts, by extension.
+pub fn write_items(path: &Path, items: &[Item]) -> Result<(), DatasetError> {
+    match format_of(path)? {
+        Format::Json => write_json(path, &JsonItems { items }),
+        Format::Csv => write_csv(
+            path,
+            &["item_id", "name", "width_cm", "depth_cm", "height_cm", "mass_kg", "priority", "expiry_date", "usage_limit", "preferred_zone"],
+            items.iter().map(|i| vec![
+                i.item_id.clone(), i.name.clone(), format!("{:?}", i.width), format!("{:?}", i.depth), format!("{:?}", i.height),
+                i.mass.map(|m| format!("{:?}", m)).unwrap_or_default(), i.priority.to_string(),
+                i.expiry_date.map_or("N/A".to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                i.usage_limit.to_string(), i.preferred_zone.clone(),
+            ]),
+        ),
+    }
+}
+
+/// Writes containers in the layout `read_containers` accepts, by extension.
+pub fn write_containers(path: &Path, containers: &[Container]) -> Result<(), DatasetError> {
+    match format_of(path)? {
+        Format::Json => write_json(path, &JsonContainers { containers }),
+        Format::Csv => write_csv(
+            path,
+            &["zone", "container_id", "width_cm", "depth_cm", "height_cm"],
+            containers.iter().map(|c| vec![
+                c.zone.clone(), c.container_id.clone(), format!("{:?}", c.width), format!("{:?}", c.depth), format!("{:?}", c.height),
+            ]),
+        ),
+    }
+}
+
+/// Writes an arrangement, by extension.
+pub fn write_placements(path: &Path, placements: &[PlacementResult]) -> Result<(), DatasetError> {
+    match format_of(path)? {
+        Format::Json => write_json(path, &JsonPlacements { placements }),
+        Format::Csv => write_csv(
+            path,
+            &["ItemID", "ContainerID", "Coordinates(W1,D1,H1)", "Coordinates(W2,D2,H2)"],
+            placements.iter().map(|p| vec![
+                p.item_id.clone(), p.container_id.clone(),
+                coordinates(&p.position.start_coordinates), coordinates(&p.position.end_coordinates),
+            ]),
+        ),
+    }
+}
+
+/// Writes rearrangement steps, by extension.
+pub fn write_rearrangements(path: &Path, steps: &[RearrangementStep]) -> Result<(), DatasetError> {
+    match format_of(path)? {
+        Format::Json => write_json(path, &JsonRearrangements { rearrangements: steps }),
+        Format::Csv => write_csv(
+            path,
+            &[
+                "Step", "Action", "ItemID", "FromContainer", "FromCoordinates(W1,D1,H1)", "FromCoordinates(W2,D2,H2)",
+                "ToContainer", "ToCoordinates(W1,D1,H1)", "ToCoordinates(W2,D2,H2)",
+            ],
+            steps.iter().map(|s| vec![
+                s.step.to_string(), s.action.clone(), s.item_id.clone(),
+                s.from_container.clone().unwrap_or_default(),
+                optional_coordinates(s.from_position.as_ref(), false), optional_coordinates(s.from_position.as_ref(), true),
+                s.to_container.clone().unwrap_or_default(),
+                optional_coordinates(s.to_position.as_ref(), false), optional_coordinates(s.to_position.as_ref(), true),
+            ]),
+        ),
+    }
+}
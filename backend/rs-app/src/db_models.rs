@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
 use serde::{Serialize, Deserialize}; // Keep serde if needed for API conversion
+use crate::models::{ActionType, ApiToken, Container, Item, ItemStatus, LogEntry, Role};
 
 // Match the table columns from migrations/20231120000001_create_tables.sql
 
@@ -39,6 +40,7 @@ pub struct DbContainer {
     pub is_waste_container: Option<bool>,
     #[sqlx(rename = "maxWeightCapacity")]
     pub max_weight_capacity: Option<f64>,
+    pub version: i64,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
@@ -54,4 +56,100 @@ pub struct DbPlacement {
     pub end_w: f64,
     pub end_d: f64,
     pub end_h: f64,
-} 
\ No newline at end of file
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct DbApiToken {
+    pub id: i64,
+    pub name: String,
+    #[sqlx(rename = "tokenHash")]
+    pub token_hash: String,
+    pub role: String, // "crew", "planner" or "admin"
+    #[sqlx(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[sqlx(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[sqlx(rename = "revokedAt")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize, Clone)]
+pub struct DbLogEntry {
+    pub id: i64,
+    pub timestamp: DateTime<Utc>,
+    #[sqlx(rename = "userId")]
+    pub user_id: String,
+    #[sqlx(rename = "actionType")]
+    pub action_type: String, // "placement" or "retrieval"
+    #[sqlx(rename = "itemId")]
+    pub item_id: String,
+    #[sqlx(rename = "containerId")]
+    pub container_id: Option<String>,
+}
+
+impl From<DbContainer> for Container {
+    fn from(c: DbContainer) -> Self {
+        Container {
+            container_id: c.container_id,
+            zone: c.zone,
+            width: c.width,
+            depth: c.depth,
+            height: c.height,
+            is_waste_container: c.is_waste_container,
+            max_weight_capacity: c.max_weight_capacity,
+        }
+    }
+}
+
+impl From<DbItem> for Item {
+    fn from(i: DbItem) -> Self {
+        let status = match i.status.as_str() {
+            "ACTIVE" => Some(ItemStatus::ACTIVE),
+            "WASTE_EXPIRED" => Some(ItemStatus::WASTE_EXPIRED),
+            "WASTE_DEPLETED" => Some(ItemStatus::WASTE_DEPLETED),
+            "DISPOSED" => Some(ItemStatus::DISPOSED),
+            _ => None,
+        };
+        Item {
+            item_id: i.item_id,
+            name: i.name,
+            width: i.width,
+            depth: i.depth,
+            height: i.height,
+            mass: i.mass,
+            priority: i.priority as i32,
+            expiry_date: i.expiry_date,
+            usage_limit: i.usage_limit.map_or(i32::MAX, |limit| limit as i32), // No recorded limit
+            current_uses: i.current_uses as i32,
+            preferred_zone: i.preferred_zone.unwrap_or_default(),
+            status,
+        }
+    }
+}
+
+impl From<DbApiToken> for ApiToken {
+    fn from(t: DbApiToken) -> Self {
+        ApiToken {
+            id: t.id,
+            name: t.name,
+            // The table's CHECK constraint only admits known roles
+            role: Role::parse(&t.role).unwrap_or(Role::Crew),
+            created_at: t.created_at,
+            last_used_at: t.last_used_at,
+            revoked_at: t.revoked_at,
+        }
+    }
+}
+
+impl From<DbLogEntry> for LogEntry {
+    fn from(l: DbLogEntry) -> Self {
+        LogEntry {
+            timestamp: l.timestamp,
+            user_id: l.user_id,
+            // The table's CHECK constraint only admits known actions
+            action_type: ActionType::parse(&l.action_type).unwrap_or(ActionType::Placement),
+            item_id: l.item_id,
+            container_id: l.container_id,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::rearrangement::PlanError;
+use actix_web::{error::{JsonPayloadError, PathError, QueryPayloadError}, http::StatusCode, HttpResponse, ResponseError};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::error;
+use utoipa::ToSchema;
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        FieldError { field: field.into(), message: message.into() }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ApiError {
+    #[error("Missing or invalid API token")]
+    Unauthorized,
+    #[error("This action requires the {0} role")]
+    Forbidden(&'static str),
+    #[error("API token {0} not found")]
+    TokenNotFound(i64),
+    #[error("Item '{0}' not found")]
+    ItemNotFound(String),
+    #[error("Container '{0}' not found")]
+    ContainerNotFound(String),
+    #[error("No containers in zone '{0}'")]
+    ZoneNotFound(String),
+    #[error("Item '{0}' already exists")]
+    ItemAlreadyExists(String),
+    #[error("Container '{0}' already exists")]
+    ContainerAlreadyExists(String),
+    #[error("Container '{container_id}' still holds {item_count} item(s); pass force=true to delete it anyway")]
+    ContainerNotEmpty { container_id: String, item_count: i64 },
+    #[error("Item '{0}' is already placed")]
+    ItemAlreadyPlaced(String),
+    #[error("Item '{0}' is not in any container")]
+    ItemNotPlaced(String),
+    #[error("Item '{item_id}' is {status} and can no longer be used")]
+    ItemNotUsable { item_id: String, status: String },
+    #[error("Container '{container_id}' would hold {total_mass} kg, above its capacity of {capacity} kg")]
+    ContainerOverweight { container_id: String, total_mass: f64, capacity: f64 },
+    #[error("Container '{container_id}' changed since it was read (version {}, now {}); re-plan and retry",
+        expected.map_or("none".to_string(), |v| v.to_string()), actual.map_or("none".to_string(), |v| v.to_string()))]
+    VersionConflict { container_id: String, expected: Option<i64>, actual: Option<i64> },
+    #[error("Position in container '{container_id}' overlaps item '{other_item_id}'")]
+    PositionOverlap { container_id: String, other_item_id: String },
+    #[error("Idempotency key '{0}' was already used for a different request")]
+    IdempotencyKeyMismatch(String),
+    #[error("A request with idempotency key '{0}' is still being processed")]
+    IdempotencyKeyInProgress(String),
+    #[error("Validation failed")]
+    Validation(Vec<FieldError>),
+    #[error("Rearrangement plan is not executable: {0}")]
+    PlanNotExecutable(#[from] PlanError),
+    #[error("{0} is not implemented")]
+    NotImplemented(String),
+    #[error("Database error: {0}")]
+    Db(#[from] sqlx::Error),
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    pub code: String,
+    pub message: String,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub details: Vec<FieldError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ErrorResponse {
+    pub success: bool,
+    pub error: ErrorBody,
+}
+
+impl ApiError {
+    /// Stable machine-readable code; clients branch on this, never on the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized => "UNAUTHORIZED",
+            ApiError::Forbidden(_) => "FORBIDDEN",
+            ApiError::TokenNotFound(_) => "TOKEN_NOT_FOUND",
+            ApiError::ItemNotFound(_) => "ITEM_NOT_FOUND",
+            ApiError::ContainerNotFound(_) => "CONTAINER_NOT_FOUND",
+            ApiError::ZoneNotFound(_) => "ZONE_NOT_FOUND",
+            ApiError::ItemAlreadyExists(_) => "ITEM_ALREADY_EXISTS",
+            ApiError::ContainerAlreadyExists(_) => "CONTAINER_ALREADY_EXISTS",
+            ApiError::ContainerNotEmpty { .. } => "CONTAINER_NOT_EMPTY",
+            ApiError::ItemAlreadyPlaced(_) => "ITEM_ALREADY_PLACED",
+            ApiError::ItemNotPlaced(_) => "ITEM_NOT_PLACED",
+            ApiError::ItemNotUsable { .. } => "ITEM_NOT_USABLE",
+            ApiError::ContainerOverweight { .. } => "CONTAINER_OVERWEIGHT",
+            ApiError::PositionOverlap { .. } => "POSITION_OVERLAP",
+            ApiError::VersionConflict { .. } => "VERSION_CONFLICT",
+            ApiError::IdempotencyKeyMismatch(_) => "IDEMPOTENCY_KEY_MISMATCH",
+            ApiError::IdempotencyKeyInProgress(_) => "IDEMPOTENCY_KEY_IN_PROGRESS",
+            ApiError::Validation(_) => "VALIDATION_FAILED",
+            ApiError::PlanNotExecutable(_) => "REARRANGEMENT_INFEASIBLE",
+            ApiError::NotImplemented(_) => "NOT_IMPLEMENTED",
+            ApiError::Db(_) => "DB_ERROR",
+        }
+    }
+
+    pub fn details(&self) -> Vec<FieldError> {
+        match self {
+            ApiError::Validation(details) => details.clone(),
+            _ => vec![],
+        }
+    }
+
+    pub fn validation(field: impl Into<String>, message: impl Into<String>) -> Self {
+        ApiError::Validation(vec![FieldError::new(field, message)])
+    }
+
+    pub fn from_json_error(err: JsonPayloadError) -> Self {
+        match &err {
+            JsonPayloadError::Deserialize(e) => {
+                let message = e.to_string();
+                ApiError::validation(serde_field(&message).unwrap_or("body").to_string(), message)
+            }
+            _ => ApiError::validation("body", err.to_string()),
+        }
+    }
+
+    pub fn from_query_error(err: QueryPayloadError) -> Self {
+        let message = err.to_string();
+        ApiError::validation(serde_field(&message).unwrap_or("query").to_string(), message)
+    }
+
+    pub fn from_path_error(err: PathError) -> Self {
+        ApiError::validation("path", err.to_string())
+    }
+}
+
+// serde reports missing/unknown fields as "... field `name` ..."
+fn serde_field(message: &str) -> Option<&str> {
+    let start = message.find("field `")? + "field `".len();
+    let len = message[start..].find('`')?;
+    Some(&message[start..start + len])
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::ItemNotFound(_) | ApiError::ContainerNotFound(_) | ApiError::ZoneNotFound(_)
+                | ApiError::TokenNotFound(_) | ApiError::ItemNotPlaced(_) => StatusCode::NOT_FOUND,
+            ApiError::ItemAlreadyExists(_) | ApiError::ContainerAlreadyExists(_) | ApiError::ContainerNotEmpty { .. }
+                | ApiError::ItemAlreadyPlaced(_) | ApiError::ItemNotUsable { .. } | ApiError::PositionOverlap { .. } | ApiError::VersionConflict { .. }
+                | ApiError::IdempotencyKeyInProgress(_) => StatusCode::CONFLICT,
+            ApiError::ContainerOverweight { .. } | ApiError::PlanNotExecutable(_)
+                | ApiError::IdempotencyKeyMismatch(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Validation(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotImplemented(_) => StatusCode::NOT_IMPLEMENTED,
+            ApiError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        // Database errors can carry SQL and schema details; those stay in the logs
+        let message = match self {
+            ApiError::Db(e) => {
+                error!("Database error: {}", e);
+                "Internal database error".to_string()
+            }
+            _ => self.to_string(),
+        };
+        HttpResponse::build(self.status_code()).json(ErrorResponse {
+            success: false,
+            error: ErrorBody {
+                code: self.code().to_string(),
+                message,
+                details: self.details(),
+            },
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use crate::models::Position;
+use actix_web::web::Bytes;
+use tracing::debug;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use utoipa::{IntoParams, ToSchema};
+
+// Events buffered per subscriber before it starts missing them
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Change notifications pushed to `/api/events` subscribers. Handlers publish
+/// them only after their transaction has committed. There is no "simulation day
+/// advanced" event yet: it waits on the simulate-day endpoint, which this service
+/// does not have.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum Event {
+    /// An item was placed, moved or lost its placement (`containerId` is null).
+    #[serde(rename_all = "camelCase")]
+    PlacementChanged {
+        item_id: String,
+        container_id: Option<String>,
+        zone: Option<String>,
+        position: Option<Position>,
+        previous_container_id: Option<String>,
+        previous_zone: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ItemUsed {
+        item_id: String,
+        container_id: Option<String>,
+        zone: Option<String>,
+        current_uses: i64,
+        usage_limit: Option<i64>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ItemBecameWaste {
+        item_id: String,
+        container_id: Option<String>,
+        zone: Option<String>,
+        status: String,
+    },
+    /// An item record was created, edited or deleted.
+    #[serde(rename_all = "camelCase")]
+    ItemChanged {
+        item_id: String,
+        container_id: Option<String>,
+        zone: Option<String>,
+        deleted: bool,
+    },
+    /// A container record was created, edited or deleted.
+    #[serde(rename_all = "camelCase")]
+    ContainerChanged {
+        container_id: String,
+        zone: String,
+        deleted: bool,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventFilter {
+    pub zone: Option<String>,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+}
+
+impl Event {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::PlacementChanged { .. } => "placementChanged",
+            Event::ItemUsed { .. } => "itemUsed",
+            Event::ItemBecameWaste { .. } => "itemBecameWaste",
+            Event::ItemChanged { .. } => "itemChanged",
+            Event::ContainerChanged { .. } => "containerChanged",
+        }
+    }
+
+    // Containers and zones the event concerns
+    fn scope(&self) -> Vec<(Option<&String>, Option<&String>)> {
+        match self {
+            Event::PlacementChanged { container_id, zone, previous_container_id, previous_zone, .. } => vec![
+                (container_id.as_ref(), zone.as_ref()),
+                (previous_container_id.as_ref(), previous_zone.as_ref()),
+            ],
+            Event::ItemUsed { container_id, zone, .. }
+            | Event::ItemBecameWaste { container_id, zone, .. }
+            | Event::ItemChanged { container_id, zone, .. } => vec![(container_id.as_ref(), zone.as_ref())],
+            Event::ContainerChanged { container_id, zone, .. } => vec![(Some(container_id), Some(zone))],
+        }
+    }
+
+    pub fn matches(&self, filter: &EventFilter) -> bool {
+        self.scope().iter().any(|(container_id, zone)| {
+            filter.container_id.as_ref().is_none_or(|wanted| *container_id == Some(wanted))
+                && filter.zone.as_ref().is_none_or(|wanted| *zone == Some(wanted))
+        })
+    }
+
+    /// The event as one Server-Sent Events frame.
+    pub fn to_sse(&self) -> Bytes {
+        let data = serde_json::to_string(self).unwrap_or_else(|_| "{}".to_string());
+        Bytes::from(format!("event: {}\ndata: {}\n\n", self.kind(), data))
+    }
+}
+
+/// Fan-out of committed changes to every connected event stream.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        EventBus { sender: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+
+    pub fn publish(&self, event: Event) {
+        // Sending only fails when nobody is listening, which is fine
+        if self.sender.send(event).is_err() {
+            debug!("No event stream subscribers");
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+use crate::config::tolerance;
+use crate::models::*;
+use crate::rearrangement::same_position;
+use crate::simulation::is_supported;
+
+pub fn volume(space: &Position) -> f64 {
+    (space.end_coordinates.width - space.start_coordinates.width)
+        * (space.end_coordinates.depth - space.start_coordinates.depth)
+        * (space.end_coordinates.height - space.start_coordinates.height)
+}
+
+fn intersects(a: &Position, b: &Position) -> bool {
+    a.start_coordinates.width + tolerance() < b.end_coordinates.width && b.start_coordinates.width + tolerance() < a.end_coordinates.width &&
+    a.start_coordinates.depth + tolerance() < b.end_coordinates.depth && b.start_coordinates.depth + tolerance() < a.end_coordinates.depth &&
+    a.start_coordinates.height + tolerance() < b.end_coordinates.height && b.start_coordinates.height + tolerance() < a.end_coordinates.height
+}
+
+fn contains(outer: &Position, inner: &Position) -> bool {
+    outer.start_coordinates.width <= inner.start_coordinates.width + tolerance() && inner.end_coordinates.width <= outer.end_coordinates.width + tolerance() &&
+    outer.start_coordinates.depth <= inner.start_coordinates.depth + tolerance() && inner.end_coordinates.depth <= outer.end_coordinates.depth + tolerance() &&
+    outer.start_coordinates.height <= inner.start_coordinates.height + tolerance() && inner.end_coordinates.height <= outer.end_coordinates.height + tolerance()
+}
+
+fn is_degenerate(space: &Position) -> bool {
+    space.end_coordinates.width - space.start_coordinates.width <= tolerance() ||
+    space.end_coordinates.depth - space.start_coordinates.depth <= tolerance() ||
+    space.end_coordinates.height - space.start_coordinates.height <= tolerance()
+}
+
+// Splits `space` around `occupied` into the (up to six) maximal boxes left on each side of it
+fn split(space: &Position, occupied: &Position, out: &mut Vec<Position>) {
+    let (s, e) = (&space.start_coordinates, &space.end_coordinates);
+    let (os, oe) = (&occupied.start_coordinates, &occupied.end_coordinates);
+    let sides = [
+        Position { start_coordinates: s.clone(), end_coordinates: Coordinates { width: os.width, ..e.clone() } },
+        Position { start_coordinates: Coordinates { width: oe.width, ..s.clone() }, end_coordinates: e.clone() },
+        Position { start_coordinates: s.clone(), end_coordinates: Coordinates { depth: os.depth, ..e.clone() } },
+        Position { start_coordinates: Coordinates { depth: oe.depth, ..s.clone() }, end_coordinates: e.clone() },
+        Position { start_coordinates: s.clone(), end_coordinates: Coordinates { height: os.height, ..e.clone() } },
+        Position { start_coordinates: Coordinates { height: oe.height, ..s.clone() }, end_coordinates: e.clone() },
+    ];
+    out.extend(sides.into_iter().filter(|side| !is_degenerate(side)));
+}
+
+/// Maximal empty axis-aligned boxes of `container` given its current placements,
+/// largest first. Every position an item could occupy without overlapping lies
+/// entirely inside at least one of these boxes.
+pub fn maximal_free_spaces(container: &Container, placements: &[(String, Position)]) -> Vec<Position> {
+    let mut spaces = vec![Position {
+        start_coordinates: Coordinates { width: 0.0, depth: 0.0, height: 0.0 },
+        end_coordinates: Coordinates { width: container.width, depth: container.depth, height: container.height },
+    }];
+
+    for (_, occupied) in placements {
+        let mut next: Vec<Position> = Vec::with_capacity(spaces.len());
+        for space in &spaces {
+            if intersects(space, occupied) {
+                split(space, occupied, &mut next);
+            } else {
+                next.push(space.clone());
+            }
+        }
+
+        // Keep only maximal boxes: drop any box contained in another (and duplicates)
+        let mut maximal: Vec<Position> = Vec::with_capacity(next.len());
+        for (i, space) in next.iter().enumerate() {
+            let dominated = next.iter().enumerate().any(|(j, other)| {
+                i != j && contains(other, space) && (!contains(space, other) || j < i)
+            });
+            if !dominated { maximal.push(space.clone()); }
+        }
+        spaces = maximal;
+    }
+
+    spaces.sort_by(|a, b| volume(b).partial_cmp(&volume(a)).unwrap_or(std::cmp::Ordering::Equal));
+    spaces
+}
+
+/// Every orientation of an item with the given dimensions that fits in `space`.
+pub fn fitting_orientations(space: &Position, (w, d, h): (f64, f64, f64)) -> impl Iterator<Item = (f64, f64, f64)> {
+    let sw = space.end_coordinates.width - space.start_coordinates.width;
+    let sd = space.end_coordinates.depth - space.start_coordinates.depth;
+    let sh = space.end_coordinates.height - space.start_coordinates.height;
+    [(w, d, h), (w, h, d), (d, w, h), (d, h, w), (h, w, d), (h, d, w)]
+        .into_iter()
+        .filter(move |&(ow, od, oh)| ow <= sw + tolerance() && od <= sd + tolerance() && oh <= sh + tolerance())
+}
+
+/// Front-most spot in `space` for a box of size `(w, d, h)` that rests on the floor
+/// or on an item. Maximal spaces above the floor can overhang the items they sit
+/// on, so besides the space's own corner this tries the nearest corner of each item
+/// whose top forms the space's floor.
+pub fn supported_spot(space: &Position, (w, d, h): (f64, f64, f64), placements: &[(String, Position)]) -> Option<Position> {
+    let (s, e) = (&space.start_coordinates, &space.end_coordinates);
+    let corners = std::iter::once((s.width, s.depth)).chain(placements.iter()
+        .filter(|(_, p)| (p.end_coordinates.height - s.height).abs() < tolerance())
+        .map(|(_, p)| (p.start_coordinates.width.max(s.width), p.start_coordinates.depth.max(s.depth))));
+    corners
+        .map(|(width, depth)| Position {
+            start_coordinates: Coordinates { width, depth, height: s.height },
+            end_coordinates: Coordinates { width: width + w, depth: depth + d, height: s.height + h },
+        })
+        .filter(|c| c.end_coordinates.width <= e.width + tolerance() && c.end_coordinates.depth <= e.depth + tolerance())
+        .filter(|c| is_supported(c, placements))
+        .min_by(|a, b| a.start_coordinates.depth.partial_cmp(&b.start_coordinates.depth).unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.start_coordinates.width.partial_cmp(&b.start_coordinates.width).unwrap_or(std::cmp::Ordering::Equal)))
+}
+
+/// Spots where an item of size `dims` can go in `container`, in any orientation:
+/// the front-most supported spot of each maximal free space, per orientation that
+/// fits it, without duplicates.
+pub fn supported_spots(container: &Container, placements: &[(String, Position)], dims: (f64, f64, f64)) -> Vec<Position> {
+    let mut spots: Vec<Position> = Vec::new();
+    for space in maximal_free_spaces(container, placements) {
+        for orientation in fitting_orientations(&space, dims) {
+            let Some(spot) = supported_spot(&space, orientation, placements) else { continue };
+            if !spots.iter().any(|s| same_position(s, &spot)) {
+                spots.push(spot);
+            }
+        }
+    }
+    spots
+}
@@ -0,0 +1,197 @@
+use crate::models::{Container, Item, PlacementResult};
+use crate::planner;
+use crate::simulation::ContainerState;
+use chrono::{Days, NaiveDate};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::RangeInclusive;
+
+// Port of generate-dataset/generate_item.py and generate_container.py: the same
+// catalogue and distributions, but seeded so a dataset can be reproduced.
+
+// Item kinds and the zones they are usually kept in (sample_data.py)
+const CATALOGUE: &[(&str, &[&str])] = &[
+    ("Food Packet", &["Crew_Quarters", "Storage_Bay"]),
+    ("Oxygen Cylinder", &["Airlock", "Crew_Quarters", "Medical_Bay"]),
+    ("First Aid Kit", &["Medical_Bay", "Crew_Quarters"]),
+    ("Water Bottle", &["Crew_Quarters", "Storage_Bay"]),
+    ("Space Suit", &["Storage_Bay", "Airlock"]),
+    ("Tool Kit", &["Maintenance_Bay", "Storage_Bay"]),
+    ("Radiation Shield", &["Storage_Bay", "Engineering_Bay"]),
+    ("Emergency Beacon", &["Command_Center", "Cockpit"]),
+    ("Battery Pack", &["Power_Bay", "External_Storage"]),
+    ("Solar Panel", &["External_Storage", "Power_Bay"]),
+    ("Navigation Module", &["Cockpit", "Command_Center"]),
+    ("Communication Device", &["Command_Center", "Crew_Quarters"]),
+    ("Research Samples", &["Lab", "Storage_Bay"]),
+    ("Fire Extinguisher", &["Crew_Quarters", "Engineering_Bay"]),
+    ("Thruster Fuel", &["Engine_Bay", "Storage_Bay"]),
+    ("Microgravity Lab Kit", &["Lab", "Crew_Quarters"]),
+    ("Pressure Regulator", &["Airlock", "Engineering_Bay"]),
+    ("Cooling System", &["Engineering_Bay", "Power_Bay"]),
+    ("Waste Management Kit", &["Sanitation_Bay", "Engineering_Bay"]),
+    ("Asteroid Sample Container", &["Lab", "Storage_Bay"]),
+    ("3D Printer", &["Engineering_Bay", "Lab"]),
+    ("Laptop", &["Crew_Quarters", "Command_Center"]),
+    ("Scientific Sensor", &["Lab", "Cockpit"]),
+    ("Medical Scanner", &["Medical_Bay", "Lab"]),
+    ("Vacuum Sealed Tools", &["Storage_Bay", "Maintenance_Bay"]),
+    ("EV Suit Battery", &["Airlock", "Storage_Bay"]),
+    ("Tether Reel", &["External_Storage", "Airlock"]),
+    ("CO2 Scrubber", &["Life_Support", "Engineering_Bay"]),
+    ("Water Purification Unit", &["Life_Support", "Crew_Quarters"]),
+    ("Seed Packets", &["Greenhouse", "Lab"]),
+    ("Lab Microscope", &["Lab", "Medical_Bay"]),
+    ("Protein Bars", &["Crew_Quarters", "Storage_Bay"]),
+    ("Antibiotic Supply", &["Medical_Bay", "Lab"]),
+    ("Gyroscope Module", &["Cockpit", "Engineering_Bay"]),
+    ("Circuit Board", &["Engineering_Bay", "Storage_Bay"]),
+    ("Helmet Visor", &["Storage_Bay", "Crew_Quarters"]),
+    ("Emergency Oxygen Mask", &["Crew_Quarters", "Medical_Bay"]),
+    ("LED Work Light", &["Maintenance_Bay", "Engineering_Bay"]),
+    ("Handheld Spectrometer", &["Lab", "Engineering_Bay"]),
+];
+
+/// What to generate. The defaults match the Python scripts.
+#[derive(Debug, Clone)]
+pub struct GeneratorOptions {
+    pub seed: u64,
+    pub items: usize,
+    pub containers_per_zone: RangeInclusive<usize>,
+    // The first `placed` items are planned into the containers up front
+    pub placed: usize,
+    // Expiry dates are drawn relative to this day
+    pub today: NaiveDate,
+}
+
+impl Default for GeneratorOptions {
+    fn default() -> Self {
+        GeneratorOptions {
+            seed: 0,
+            items: 2000,
+            containers_per_zone: 2..=5,
+            placed: 0,
+            today: chrono::Utc::now().date_naive(),
+        }
+    }
+}
+
+/// A generated manifest; `placements` is empty unless pre-placed items were asked for.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub items: Vec<Item>,
+    pub containers: Vec<Container>,
+    pub placements: Vec<PlacementResult>,
+}
+
+pub fn generate(options: &GeneratorOptions) -> Dataset {
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let containers = containers(&mut rng, options.containers_per_zone.clone());
+    let items: Vec<Item> = (1..=options.items).map(|n| item(&mut rng, n, options.today)).collect();
+
+    let mut placements = Vec::new();
+    if options.placed > 0 {
+        let by_id: HashMap<String, Container> = containers.iter().map(|c| (c.container_id.clone(), c.clone())).collect();
+        let preplaced = &items[..options.placed.min(items.len())];
+        // Planning into empty containers cannot fail on stale state, and any item
+        // that does not fit is simply left unplaced
+        if let Ok(plan) = planner::plan(&by_id, &ContainerState::new(), &[], preplaced) {
+            placements = plan.into_results(false).0;
+            placements.sort_by(|a, b| a.item_id.cmp(&b.item_id));
+        }
+    }
+    Dataset { items, containers, placements }
+}
+
+fn round(value: f64, places: i32) -> f64 {
+    let scale = 10f64.powi(places);
+    (value * scale).round() / scale
+}
+
+// Initials of the words in the zone name; on a clash, later letters of each word
+fn zone_prefix(zone: &str, taken: &HashMap<String, &str>) -> String {
+    let words: Vec<Vec<char>> = zone.split('_').map(|w| w.chars().collect()).collect();
+    let mut prefix: String = words.iter().filter_map(|w| w.first()).collect();
+    let mut n = 0;
+    while taken.contains_key(&prefix) {
+        n += 1;
+        prefix = words.iter().filter(|w| !w.is_empty()).map(|w| w[n.min(w.len() - 1)].to_ascii_uppercase()).collect();
+    }
+    prefix
+}
+
+fn containers(rng: &mut StdRng, per_zone: RangeInclusive<usize>) -> Vec<Container> {
+    let zones: BTreeSet<&str> = CATALOGUE.iter().flat_map(|(_, zones)| zones.iter().copied()).collect();
+    let mut prefixes: HashMap<String, &str> = HashMap::new();
+    let mut containers = Vec::new();
+    for zone in zones {
+        let prefix = zone_prefix(zone, &prefixes);
+        prefixes.insert(prefix.clone(), zone);
+        for n in 1..=rng.gen_range(per_zone.clone()) {
+            // Standard rack size, sometimes halved, quartered or doubled along an axis
+            let mut scale = || if rng.gen_bool(0.5) { 1.0 } else { 2f64.powi(rng.gen_range(-2..=1)) };
+            containers.push(Container {
+                container_id: format!("{}{:02}", prefix, n),
+                zone: zone.to_string(),
+                width: 100.0 * scale(),
+                depth: 85.0 * scale(),
+                height: 200.0 * scale(),
+                is_waste_container: None,
+                max_weight_capacity: None,
+            });
+        }
+    }
+    containers
+}
+
+fn item(rng: &mut StdRng, n: usize, today: NaiveDate) -> Item {
+    let (kind, zones) = CATALOGUE[rng.gen_range(0..CATALOGUE.len())];
+    let preferred_zone = zones.choose(rng).copied().unwrap_or_default();
+    let is = |words: &[&str]| words.iter().any(|w| kind.contains(w));
+
+    let mut dim = |lo: f64, hi: f64| round(rng.gen_range(lo..=hi), 1);
+    let (width, depth, height, mass) = if is(&["Cylinder", "Tank"]) {
+        (dim(15.0, 30.0), dim(15.0, 30.0), dim(40.0, 100.0), dim(10.0, 50.0))
+    } else if is(&["Panel"]) {
+        (dim(40.0, 100.0), dim(40.0, 100.0), dim(1.0, 5.0), dim(5.0, 20.0))
+    } else if is(&["Kit", "Packet", "Sample"]) {
+        (dim(10.0, 30.0), dim(10.0, 30.0), dim(10.0, 30.0), dim(1.0, 10.0))
+    } else {
+        let (w, d, h) = (dim(10.0, 50.0), dim(10.0, 50.0), dim(10.0, 50.0));
+        (w, d, h, round(w * d * h / 2000.0, 2))
+    };
+
+    let priority = rng.gen_range(1..=100);
+    // Only perishables and medicine expire
+    let expiry_date = if is(&["Food", "Medical", "Antibiotic"]) {
+        today.checked_add_days(Days::new(rng.gen_range(30..=730)))
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc())
+    } else {
+        None
+    };
+    let usage_limit = if is(&["Kit", "Packet", "Food", "Medical"]) {
+        rng.gen_range(1..=100)
+    } else if is(&["Battery", "Filter", "Scrubber"]) {
+        rng.gen_range(100..=1000)
+    } else {
+        rng.gen_range(1..=5000)
+    };
+
+    Item {
+        item_id: format!("{:06}", n),
+        name: kind.replace(' ', "_"),
+        width,
+        depth,
+        height,
+        mass: Some(mass),
+        priority,
+        expiry_date,
+        usage_limit,
+        current_uses: 0,
+        preferred_zone: preferred_zone.to_string(),
+        status: None,
+    }
+}
@@ -1,162 +1,100 @@
-use actix_web::{web, HttpResponse, Result, http::StatusCode};
+use actix_web::{web, HttpResponse, http::StatusCode};
 use crate::models::*;
-use crate::db_models::{DbItem, DbPlacement}; // Remove DbContainer
+use crate::db_models::{DbApiToken, DbContainer, DbItem, DbLogEntry, DbPlacement};
+use crate::errors::{ApiError, ErrorResponse, FieldError};
+use crate::validation::{self, Validate, Validated, ValidatedQuery};
+use crate::listing::{self, SortColumn};
+use crate::events::{Event, EventBus, EventFilter};
+use crate::metrics::Metrics;
+use crate::config::{self, Config};
+use crate::{auth, compaction, free_space, integrity, planner, rearrangement, suggestion};
+use crate::simulation::{boxes_overlap, exceeds_capacity, obstructs, ContainerState};
 // Remove PlacementService import
-use log::{info, warn, error, debug};
-use serde_json::json;
-use sqlx::SqlitePool; // Remove Row, Transaction, Sqlite
+use tracing::{field, info, info_span, warn, error, debug, Instrument, Span};
+use chrono::Utc;
+use sqlx::{FromRow, QueryBuilder, Row, Sqlite, SqliteConnection, SqlitePool};
 use std::collections::{HashMap, HashSet};
+use futures::StreamExt;
+use tokio::sync::broadcast::error::RecvError;
 // Remove anyhow::anyhow import
 
 
-// ==============================================================================
-// == Helper Functions (Moved from PlacementService or adapted from Python) =====
-// ==============================================================================
-
-fn boxes_overlap(
-    start1: &Coordinates, end1: &Coordinates, start2: &Coordinates, end2: &Coordinates
-) -> bool {
-    let tol = 1e-6; // Tolerance
-    let no_overlap_w = end1.width <= start2.width + tol || end2.width <= start1.width + tol;
-    let no_overlap_d = end1.depth <= start2.depth + tol || end2.depth <= start1.depth + tol;
-    let no_overlap_h = end1.height <= start2.height + tol || end2.height <= start1.height + tol;
-    ! (no_overlap_w || no_overlap_d || no_overlap_h)
-}
-
-// Find spot function adapted for Rust, using API models for simulation
-fn find_spot_in_container(
-    item_dims: (f64, f64, f64),    // Width, Depth, Height of the item being placed
-    container: &Container,         // Container dimensions (API model)
-    current_placements_in_container: &[(String, Position)], // Current simulation state (itemId, Position)
-    is_high_priority: bool
-) -> Option<(Position, (f64, f64, f64))> { // Returns (Position, orientation_used)
-
-    let item_w = item_dims.0;
-    let item_d = item_dims.1;
-    let item_h = item_dims.2;
-
-    let orientations = [
-        (item_w, item_d, item_h), (item_w, item_h, item_d),
-        (item_d, item_w, item_h), (item_d, item_h, item_w),
-        (item_h, item_w, item_d), (item_h, item_d, item_w),
-    ];
-    let precision = 3; // Decimal places
-
-    for (w, d, h) in orientations {
-        if w > container.width + 1e-6 || d > container.depth + 1e-6 || h > container.height + 1e-6 {
-            continue;
-        }
-
-        // Simplified grid search strategy
-        let width_increment = (container.width / 10.0).max(0.01);
-        let depth_increment = (container.depth / 10.0).max(0.01);
-        let height_increment = (container.height / 10.0).max(0.01);
-
-        let mut search_depths: Vec<f64> = (0..=( (container.depth / depth_increment).floor() as i32 + 1))
-                                            .map(|i| (i as f64 * depth_increment).min(container.depth - d).max(0.0))
-                                            .collect();
-        search_depths.dedup_by(|a, b| ((*a) - (*b)).abs() < 1e-9); // Fix by dereferencing
-        if !is_high_priority { search_depths.reverse(); } // Low prio tries deep spots first
-
-
-        let mut possible_base_heights = vec![0.0];
-        possible_base_heights.extend(current_placements_in_container.iter().map(|(_, p)| p.end_coordinates.height));
-        possible_base_heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-        possible_base_heights.dedup_by(|a, b| ((*a) - (*b)).abs() < 1e-9); // Fix by dereferencing
-
-
-        for start_h_base in possible_base_heights {
-            let start_h = start_h_base; // Precision applied later if needed
-            if start_h + h > container.height + 1e-6 { continue; }
-
-            for start_d in &search_depths {
-                let start_d = *start_d;
-                if start_d + d > container.depth + 1e-6 { continue; }
-
-                 let mut search_widths: Vec<f64> = (0..=( (container.width / width_increment).floor() as i32 + 1))
-                                            .map(|i| (i as f64 * width_increment).min(container.width - w).max(0.0))
-                                            .collect();
-                search_widths.dedup_by(|a, b| ((*a) - (*b)).abs() < 1e-9); // Fix by dereferencing
-
-                for start_w in search_widths {
-                     if start_w + w > container.width + 1e-6 { continue; }
-
-                    let start_coords = Coordinates { width: start_w, depth: start_d, height: start_h };
-                    let end_coords = Coordinates {
-                        width: start_w + w,
-                        depth: start_d + d,
-                        height: start_h + h,
-                    };
-                    let candidate_position = Position { start_coordinates: start_coords.clone(), end_coordinates: end_coords.clone() };
-
-                    // 1. Boundary Check (redundant if start calc is correct, but good safety)
-                     if end_coords.width > container.width + 1e-6 || end_coords.depth > container.depth + 1e-6 || end_coords.height > container.height + 1e-6 {
-                         continue;
-                     }
-
-                    // 2. Overlap Check
-                    let mut overlaps = false;
-                    for (_, existing_pos) in current_placements_in_container {
-                        if boxes_overlap(&start_coords, &end_coords, &existing_pos.start_coordinates, &existing_pos.end_coordinates) {
-                            overlaps = true;
-                            break;
-                        }
-                    }
-                    if overlaps { continue; }
-
-                    // 3. Stability Check (Simplified)
-                    let is_on_floor = start_h.abs() < 1e-6;
-                    let mut is_supported = false;
-                    if !is_on_floor {
-                        for (_, p_pos) in current_placements_in_container {
-                            if (p_pos.end_coordinates.height - start_h).abs() < 1e-6 { // Is this item below?
-                                // Check horizontal overlap
-                                let base_start_w = start_w; let base_end_w = end_coords.width;
-                                let base_start_d = start_d; let base_end_d = end_coords.depth;
-                                let support_start_w = p_pos.start_coordinates.width; let support_end_w = p_pos.end_coordinates.width;
-                                let support_start_d = p_pos.start_coordinates.depth; let support_end_d = p_pos.end_coordinates.depth;
-                                if !(base_end_w <= support_start_w + 1e-6 || support_end_w <= base_start_w + 1e-6 ||
-                                     base_end_d <= support_start_d + 1e-6 || support_end_d <= base_start_d + 1e-6) {
-                                    is_supported = true;
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                     if !is_on_floor && !is_supported { continue; } // Skip floating positions
-
-                    // All checks passed
-                    return Some((candidate_position, (w, d, h)));
-                }
-            }
-        }
-    }
-    None // No spot found
-}
-
-
 // ==============================================================================
 // == Main Placement Handler ====================================================
 // ==============================================================================
 
+#[utoipa::path(
+    post,
+    path = "/api/placement",
+    tag = "placement",
+    request_body = PlacementRequest,
+    responses(
+        (status = 200, description = "Every item placed", body = PlacementResponse),
+        (status = 207, description = "Some items could not be placed", body = PlacementResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 409, description = "Item already placed", body = ErrorResponse),
+    )
+)]
+#[tracing::instrument(name = "placement", skip_all, fields(items = req.items.len(), placed = field::Empty, failed = field::Empty, rearranged = field::Empty))]
 pub async fn optimize_placement(
-    req: web::Json<PlacementRequest>,
+    req: Validated<PlacementRequest>,
     db_pool: web::Data<SqlitePool>, // Use DB pool from app state
-) -> Result<HttpResponse> {
+    events: web::Data<EventBus>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, ApiError> {
     info!("Received placement request for {} items", req.items.len());
+    let started = std::time::Instant::now();
 
     // --- Phase 0: Data Loading & Initial Setup ---
-    let mut tx = match db_pool.begin().await {
-        Ok(tx) => tx,
+    // Reads happen outside the write transaction; the container versions read here
+    // are checked again at commit time, so a plan built on stale state is rejected.
+    let load_span = info_span!("phase", phase = "load");
+
+    let req_item_ids: HashSet<String> = req.items.iter().map(|i| i.item_id.clone()).collect();
+    let mut all_container_ids_map: HashMap<String, Container> = HashMap::new();
+
+    // Read once: the stored rows seed the planning set and give the versions the plan is based on
+    let stored_containers: HashMap<String, DbContainer> = match sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers"#)
+        .fetch_all(db_pool.get_ref())
+        .instrument(load_span.clone())
+        .await
+    {
+        Ok(rows) => rows.into_iter().map(|c| (c.container_id.clone(), c)).collect(),
         Err(e) => {
-            error!("Failed to begin database transaction: {}", e);
-            return Ok(HttpResponse::InternalServerError().json(PlacementResponse::error(e.to_string())));
+            error!("Failed to load stored containers: {}", e);
+            return Err(e.into());
         }
     };
 
-    let req_container_ids: Vec<String> = req.containers.iter().map(|c| c.container_id.clone()).collect();
-    let req_item_ids: HashSet<String> = req.items.iter().map(|i| i.item_id.clone()).collect();
-    let all_container_ids_map: HashMap<String, Container> = req.containers.iter().cloned().map(|c| (c.container_id.clone(), c)).collect();
+    // Stored containers first, so request-supplied containers act as additions/updates
+    if req.use_stored_containers {
+        for db_container in stored_containers.values() {
+            if req.zones.as_ref().is_some_and(|zones| !zones.contains(&db_container.zone)) { continue; }
+            all_container_ids_map.insert(db_container.container_id.clone(), db_container.clone().into());
+        }
+        load_span.in_scope(|| debug!(containers = all_container_ids_map.len(), "loaded stored containers for planning"));
+    }
+    for container in &req.containers {
+        all_container_ids_map.insert(container.container_id.clone(), container.clone());
+    }
+    if req.use_stored_containers {
+        let zones: HashSet<&String> = all_container_ids_map.values().map(|c| &c.zone).collect();
+        let mut violations = Vec::new();
+        validation::check_preferred_zones(&req.items, &zones, "", &mut violations);
+        if !violations.is_empty() {
+            return Err(ApiError::Validation(violations));
+        }
+    }
+    let req_container_ids: Vec<String> = all_container_ids_map.keys().cloned().collect();
+
+    // Versions the plan is based on; None for containers not stored yet
+    let read_versions: HashMap<String, Option<i64>> = req_container_ids.iter()
+        .map(|c_id| (c_id.clone(), stored_containers.get(c_id).map(|c| c.version)))
+        .collect();
+    // Request-supplied containers that are new or differ from their stored row
+    let changed_containers: Vec<&Container> = req.containers.iter()
+        .filter(|c| stored_containers.get(&c.container_id).is_none_or(|stored| container_differs(stored, c)))
+        .collect();
 
 
     // Load existing placements for relevant containers from DB
@@ -167,47 +105,32 @@ pub async fn optimize_placement(
             r#"SELECT * FROM placements WHERE "containerId_fk" = ?"#
         )
         .bind(container_id)
-        .fetch_all(&mut *tx)
+        .fetch_all(db_pool.get_ref())
+        .instrument(load_span.clone())
         .await
         {
             Ok(placements) => existing_placements_db.extend(placements),
             Err(e) => {
                 error!("Failed to fetch existing placements for container {}: {}", container_id, e);
-                // Continue rather than failing completely - container might not exist yet
+                return Err(e.into());
             }
         }
     }
 
 
     // Build initial simulation state & check for duplicates in request vs DB
-    // sim_placements: HashMap<ContainerId, Vec<(ItemId, Position)>>
-    let mut sim_placements: HashMap<String, Vec<(String, Position)>> = HashMap::new();
-    // final_placements_for_response: HashMap<ItemId, PlacementResult> // Stores final intended state
-    let mut final_placements_for_response: HashMap<String, PlacementResult> = HashMap::new();
+    // initial_placements: HashMap<ContainerId, Vec<(ItemId, Position)>>
+    let mut initial_placements: ContainerState = HashMap::new();
     let mut existing_item_ids_in_db: HashSet<String> = HashSet::new();
 
     for p_db in &existing_placements_db {
         // ** DUPLICATE CHECK **
         if req_item_ids.contains(&p_db.item_id_fk) {
-            let err_msg = format!("Item '{}' is already placed in the database.", p_db.item_id_fk);
-            error!("{}", err_msg);
-            tx.rollback().await.ok();
-            return Ok(HttpResponse::Conflict().json(PlacementResponse::error(err_msg)));
+            error!("Item '{}' is already placed in the database.", p_db.item_id_fk);
+            return Err(ApiError::ItemAlreadyPlaced(p_db.item_id_fk.clone()));
         }
         existing_item_ids_in_db.insert(p_db.item_id_fk.clone());
-        let position = Position {
-            start_coordinates: Coordinates { width: p_db.start_w, depth: p_db.start_d, height: p_db.start_h },
-            end_coordinates: Coordinates { width: p_db.end_w, depth: p_db.end_d, height: p_db.end_h },
-        };
-        sim_placements.entry(p_db.container_id_fk.clone()).or_default().push((p_db.item_id_fk.clone(), position.clone()));
-        // Pre-populate final response with existing state (will be updated if item moves)
-        final_placements_for_response.insert(p_db.item_id_fk.clone(), PlacementResult {
-            item_id: p_db.item_id_fk.clone(),
-            container_id: p_db.container_id_fk.clone(),
-            position,
-            retrieval_steps: 0, // Not used in response, placeholder
-            is_preferred_zone: false, // Placeholder
-        });
+        initial_placements.entry(p_db.container_id_fk.clone()).or_default().push((p_db.item_id_fk.clone(), db_position(p_db)));
     }
 
      // Load properties of existing items from DB
@@ -218,7 +141,8 @@ pub async fn optimize_placement(
                 r#"SELECT * FROM items WHERE "itemId" = ?"#
             )
             .bind(item_id)
-            .fetch_optional(&mut *tx)
+            .fetch_optional(db_pool.get_ref())
+            .instrument(load_span.clone())
             .await {
                 Ok(Some(item)) => existing_items_props_db.push(item),
                 Ok(None) => warn!("Item {} referenced in placement but not found in items table", item_id),
@@ -230,491 +154,1874 @@ pub async fn optimize_placement(
         }
     }
 
-    // Combine item properties (priority, dimensions) for simulation lookup
-    // HashMap<ItemId, (Priority, Width, Depth, Height)>
-    let mut all_item_props: HashMap<String, (i32, f64, f64, f64)> = HashMap::new();
-    for db_item in existing_items_props_db {
-        all_item_props.insert(db_item.item_id, (db_item.priority as i32, db_item.width, db_item.depth, db_item.height));
+    // --- Phases 1-3: Planning ---
+    let stored_items: Vec<Item> = existing_items_props_db.into_iter().map(Item::from).collect();
+    let plan = match planner::plan(&all_container_ids_map, &initial_placements, &stored_items, &req.items) {
+        Ok(plan) => plan,
+        Err(e) => {
+            error!("Rearrangement plan is not executable: {}", e);
+            return Err(e.into());
+        }
+    };
+
+    // Items that end up somewhere new, with the container they left (if any)
+    let mut changed_placements: Vec<(&String, Option<&String>)> = Vec::new();
+    for (item_id, result) in &plan.placements {
+        let previous = initial_placements.iter()
+            .find_map(|(c_id, placed)| placed.iter().find(|(id, _)| id == item_id).map(|(_, pos)| (c_id, pos)));
+        if previous.is_some_and(|(c_id, pos)| *c_id == result.container_id && rearrangement::same_position(pos, &result.position)) {
+            continue;
+        }
+        changed_placements.push((item_id, previous.map(|(c_id, _)| c_id)));
     }
-    for req_item in &req.items {
-        // Use request data for incoming items (overwrites if ID somehow existed but wasn't placed)
-        all_item_props.insert(req_item.item_id.clone(), (req_item.priority, req_item.width, req_item.depth, req_item.height));
+
+    // --- Phase 4: Persistence ---
+    let container_versions = async {
+        // Use the final state from `plan.placements`
+        let mut tx = match db_pool.begin().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                error!("Failed to begin database transaction: {}", e);
+                return Err(e.into());
+            }
+        };
+
+        // 4.0 Containers this request writes get their version bumped, the others must be unchanged.
+        // Bumps go first so the transaction takes the write lock before reading.
+        let mut written: HashSet<&String> = changed_containers.iter().map(|c| &c.container_id).collect();
+        for (item_id, previous) in &changed_placements {
+            written.insert(&plan.placements[*item_id].container_id);
+            written.extend(*previous);
+        }
+        let mut container_versions: HashMap<String, i64> = HashMap::new();
+        for (c_id, read_version) in &read_versions {
+            let outcome = match read_version {
+                Some(version) if written.contains(c_id) => bump_container_version(&mut tx, c_id, Some(*version)).await.map(Some),
+                _ => check_container_version(&mut tx, c_id, *read_version).await.map(|_| *read_version),
+            };
+            match outcome {
+                Ok(Some(version)) => { container_versions.insert(c_id.clone(), version); }
+                Ok(None) => {}
+                Err(e) => {
+                    warn!("Placement plan is stale: {}", e);
+                    tx.rollback().await.ok();
+                    return Err(e);
+                }
+            }
+        }
+
+         // 4.1 Upsert request-supplied Containers that changed (stored ones are unchanged)
+         for c_data in &changed_containers {
+              let c_id = &c_data.container_id;
+              match sqlx::query(
+                  r#"INSERT INTO containers ("containerId", zone, width, depth, height, "isWasteContainer", "maxWeightCapacity")
+                     VALUES (?, ?, ?, ?, ?, ?, ?)
+                     ON CONFLICT("containerId") DO UPDATE SET
+                        zone=excluded.zone,
+                        width=excluded.width,
+                        depth=excluded.depth,
+                        height=excluded.height,
+                        "isWasteContainer"=excluded."isWasteContainer",
+                        "maxWeightCapacity"=excluded."maxWeightCapacity"
+                  "#)
+                  .bind(c_id)
+                  .bind(&c_data.zone)
+                  .bind(c_data.width)
+                  .bind(c_data.depth)
+                  .bind(c_data.height)
+                  .bind(c_data.is_waste_container)
+                  .bind(c_data.max_weight_capacity)
+                  .execute(&mut *tx).await {
+                  Ok(_) => debug!("Upserted container {}", c_id),
+                  Err(e) => {
+                      error!("Failed to upsert container {}: {}", c_id, e);
+                      tx.rollback().await.ok();
+                      return Err(e.into());
+                  }
+              }
+         }
+
+         // Containers created by this request start at version 0 and take their first bump here
+         for (c_id, _) in read_versions.iter().filter(|(c_id, v)| v.is_none() && written.contains(c_id)) {
+              match bump_container_version(&mut tx, c_id, None).await {
+                  Ok(version) => { container_versions.insert(c_id.clone(), version); }
+                  Err(e) => {
+                      error!("Failed to bump version of container {}: {}", c_id, e);
+                      tx.rollback().await.ok();
+                      return Err(e);
+                  }
+              }
+         }
+
+        // 4.2 Upsert Items and Placements, only for items that ended up somewhere new
+         for &(item_id, _) in &changed_placements {
+             let final_placement = &plan.placements[item_id];
+             // Find corresponding request item data
+             let req_item_data = req.items.iter().find(|i| i.item_id == *item_id);
+
+             // Upsert Item (items already in the database keep their stored properties)
+             if req_item_data.is_some() {
+                 match sqlx::query(
+                    r#"
+                    INSERT INTO items ("itemId", name, width, depth, height, mass, priority, "expiryDate", "usageLimit", "currentUses", "preferredZone", status)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'ACTIVE')
+                    ON CONFLICT("itemId") DO UPDATE SET
+                        name=excluded.name,
+                        width=excluded.width,
+                        depth=excluded.depth,
+                        height=excluded.height,
+                        mass=excluded.mass,
+                        priority=excluded.priority,
+                        "expiryDate"=excluded."expiryDate",
+                        "usageLimit"=excluded."usageLimit",
+                        -- currentUses = currentUses, -- Don't reset uses on placement update
+                        "preferredZone"=excluded."preferredZone",
+                        status='ACTIVE' -- Ensure item is marked active on placement/move
+                    "#)
+                    .bind(item_id)
+                    .bind(req_item_data.map_or("Unknown", |i| &i.name))
+                    .bind(req_item_data.map_or(0.0, |i| i.width))
+                    .bind(req_item_data.map_or(0.0, |i| i.depth))
+                    .bind(req_item_data.map_or(0.0, |i| i.height))
+                    .bind(req_item_data.and_then(|i| i.mass))
+                    .bind(req_item_data.map_or(0, |i| i.priority))
+                    .bind(req_item_data.and_then(|i| i.expiry_date))
+                    .bind(req_item_data.map(|i| i.usage_limit as i64))
+                    .bind(req_item_data.map_or(0, |i| i.current_uses as i64))
+                    .bind(req_item_data.map(|i| &i.preferred_zone))
+                    .execute(&mut *tx).await {
+                     Ok(_) => debug!("Upserted item {}", item_id),
+                     Err(e) => {
+                          error!("Failed to upsert item {}: {}", item_id, e);
+                          tx.rollback().await.ok();
+                          return Err(e.into());
+                     }
+                 }
+             }
+
+             // Upsert Placement (delete old, insert new conceptually)
+             match sqlx::query(r#"DELETE FROM placements WHERE "itemId_fk" = ?"#)
+                     .bind(item_id)
+                     .execute(&mut *tx).await {
+                 Ok(_) => debug!("Deleted old placement for {}", item_id),
+                 Err(e) => error!("Failed to delete old placement for {}: {} (may not exist)", item_id, e), // Log error but proceed
+             }
+
+             match sqlx::query(
+                 r#"INSERT INTO placements ("itemId_fk", "containerId_fk", start_w, start_d, start_h, end_w, end_d, end_h)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#)
+                 .bind(item_id)
+                 .bind(&final_placement.container_id)
+                 .bind(final_placement.position.start_coordinates.width)
+                 .bind(final_placement.position.start_coordinates.depth)
+                 .bind(final_placement.position.start_coordinates.height)
+                 .bind(final_placement.position.end_coordinates.width)
+                 .bind(final_placement.position.end_coordinates.depth)
+                 .bind(final_placement.position.end_coordinates.height)
+                 .execute(&mut *tx).await {
+                  Ok(_) => debug!("Inserted new placement for {}", item_id),
+                 Err(e) => {
+                      error!("Failed to insert placement for {}: {}", item_id, e);
+                      tx.rollback().await.ok();
+                      return Err(e.into());
+                 }
+             }
+         }
+
+        // 4.3 Handle failed items (just log for now, item records might exist from above)
+        if !plan.failed.is_empty() {
+            warn!("The following items could not be placed: {:?}", plan.failed);
+            // Potentially log these failures to a dedicated log table in the future
+        }
+
+        // Commit transaction
+        if let Err(e) = tx.commit().await {
+            error!("Database transaction commit failed: {}", e);
+            // No need to rollback, commit failed
+            return Err(e.into());
+        }
+        Ok::<_, ApiError>(container_versions)
+    }
+    .instrument(info_span!("phase", phase = "persist"))
+    .await?;
+
+    let rearranged_items: HashSet<&String> = plan.rearrangements.iter().map(|s| &s.item_id).collect();
+    let placed_count = plan.placements.keys().filter(|id| req_item_ids.contains(*id)).count();
+    metrics.observe_placement_run(started.elapsed(), placed_count, plan.failed.len(), rearranged_items.len());
+    let run_span = Span::current();
+    run_span.record("placed", placed_count);
+    run_span.record("failed", plan.failed.len());
+    run_span.record("rearranged", rearranged_items.len());
+    info!("placement run committed");
+
+    // Announce every item that ended up somewhere new
+    for (item_id, previous) in &changed_placements {
+        let result = &plan.placements[*item_id];
+        events.publish(Event::PlacementChanged {
+            item_id: (*item_id).clone(),
+            container_id: Some(result.container_id.clone()),
+            zone: all_container_ids_map.get(&result.container_id).map(|c| c.zone.clone()),
+            position: Some(result.position.clone()),
+            previous_container_id: previous.cloned(),
+            previous_zone: previous.and_then(|c_id| all_container_ids_map.get(c_id)).map(|c| c.zone.clone()),
+        });
     }
 
 
-    // --- Simulation Phases ---
-    let mut rearrangements_result: Vec<RearrangementStep> = Vec::new();
-    let mut processed_item_ids_in_request: HashSet<String> = HashSet::new();
-    let mut items_failed_completely: Vec<String> = Vec::new();
-    let mut sorted_incoming_items = req.items.clone(); // Clone request items for processing
-    sorted_incoming_items.sort_by(|a, b| b.priority.cmp(&a.priority)); // Descending priority
+    // --- Phase 5: Format Response ---
+    let success = plan.failed.is_empty();
+    let error_msg = if success { None } else { Some(format!("Placement incomplete. Could not place items: {:?}", plan.failed)) };
+    let rearrangements = plan.rearrangements.clone();
+    let (placements, unplaced) = plan.into_results(req.explain);
 
-    // --- Phase 1: Initial Placement Attempt (Preferred Zones First) ---
-    debug!("--- Phase 1: Attempting Preferred Zone Placements ---");
-    let mut items_requiring_placement_pass_2: Vec<Item> = Vec::new(); // Items for next phase
+    let response_data = PlacementResponse {
+        success,
+        placements,
+        rearrangements,
+        container_versions,
+        unplaced,
+        error: error_msg,
+    };
 
-    for item_req in &sorted_incoming_items {
-        if processed_item_ids_in_request.contains(&item_req.item_id) { continue; } // Already handled?
+    let status_code = if success { StatusCode::OK } else { StatusCode::MULTI_STATUS }; // Use 207 if partially successful
+    Ok(HttpResponse::build(status_code).json(response_data))
+}
 
-        debug!("Processing item: {} (Prio: {}, PrefZone: {})", item_req.item_id, item_req.priority, item_req.preferred_zone);
-        let mut placed = false;
-        let is_high_prio = item_req.priority >= 50; // Example threshold
 
-        let preferred_container_ids: Vec<&String> = all_container_ids_map.keys()
-            .filter(|cid| all_container_ids_map[*cid].zone == item_req.preferred_zone)
-            .collect();
+// ==============================================================================
+// == Single Item Suggestions ===================================================
+// ==============================================================================
 
-        if !preferred_container_ids.is_empty() {
-            for container_id in preferred_container_ids {
-                let container = &all_container_ids_map[container_id];
-                let current_sim_placements_in_cont = sim_placements.get(container_id).map_or(vec![], |v| v.clone()); // Clone for find_spot
-
-                if let Some((position, _orientation)) = find_spot_in_container(
-                    (item_req.width, item_req.depth, item_req.height),
-                    container,
-                    &current_sim_placements_in_cont,
-                    is_high_prio
-                ) {
-                    // Update Simulation State
-                    sim_placements.entry(container_id.clone()).or_default().push((item_req.item_id.clone(), position.clone()));
-                    // Update final results
-                    final_placements_for_response.insert(item_req.item_id.clone(), PlacementResult {
-                        item_id: item_req.item_id.clone(), container_id: container_id.clone(), position,
-                        retrieval_steps: 0, is_preferred_zone: true
-                    });
-                    processed_item_ids_in_request.insert(item_req.item_id.clone());
-                    debug!("    SUCCESS (Phase 1): Placed {} in preferred {}", item_req.item_id, container_id);
-                    placed = true;
-                    break;
-                }
+#[utoipa::path(
+    post,
+    path = "/api/placement/suggest",
+    tag = "placement",
+    request_body = Item,
+    params(SuggestionQuery),
+    responses(
+        (status = 200, description = "Ranked spots, best first; nothing is stored", body = SuggestionResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn suggest_placement(
+    req: Validated<Item>,
+    query: ValidatedQuery<SuggestionQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Suggesting top {} spots for item {}", query.top, req.item_id);
+
+    let (containers, state, masses) = tokio::try_join!(load_containers(&db_pool, None), load_container_state(&db_pool), load_placed_masses(&db_pool))?;
+    let containers: Vec<Container> = containers.into_iter().map(Container::from).collect();
+
+    let suggestions = suggestion::suggest_placements(&req, &containers, &state, &masses, query.top);
+    if suggestions.is_empty() {
+        info!("No container can currently take item {}", req.item_id);
+    }
+    Ok(HttpResponse::Ok().json(SuggestionResponse { success: !suggestions.is_empty(), suggestions }))
+}
+
+
+// ==============================================================================
+// == Rearrangement Plan Validation =============================================
+// ==============================================================================
+
+async fn load_container_state(db_pool: &SqlitePool) -> Result<ContainerState, sqlx::Error> {
+    let placements = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements"#)
+        .fetch_all(db_pool)
+        .await?;
+    let mut state: ContainerState = HashMap::new();
+    for p_db in placements {
+        let position = Position {
+            start_coordinates: Coordinates { width: p_db.start_w, depth: p_db.start_d, height: p_db.start_h },
+            end_coordinates: Coordinates { width: p_db.end_w, depth: p_db.end_d, height: p_db.end_h },
+        };
+        state.entry(p_db.container_id_fk).or_default().push((p_db.item_id_fk, position));
+    }
+    Ok(state)
+}
+
+// Masses of the placed items that have one, for the containers' weight capacity
+async fn load_placed_masses(db_pool: &SqlitePool) -> Result<HashMap<String, f64>, sqlx::Error> {
+    sqlx::query_as::<_, (String, f64)>(
+        r#"SELECT i."itemId", i.mass FROM placements p JOIN items i ON i."itemId" = p."itemId_fk" WHERE i.mass IS NOT NULL"#)
+        .fetch_all(db_pool)
+        .await
+        .map(|rows| rows.into_iter().collect())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/rearrangement/validate",
+    tag = "rearrangement",
+    request_body = RearrangementPlanRequest,
+    responses(
+        (status = 200, description = "Executable order of the plan", body = RearrangementPlanResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 422, description = "Plan cannot be executed", body = ErrorResponse),
+    )
+)]
+pub async fn validate_rearrangement_plan(
+    req: Validated<RearrangementPlanRequest>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Validating rearrangement plan with {} steps", req.steps.len());
+
+    let state = load_container_state(&db_pool).await?;
+
+    match rearrangement::sequence_plan(&state, &req.steps) {
+        Ok(steps) => Ok(HttpResponse::Ok().json(RearrangementPlanResponse { success: true, steps })),
+        Err(e) => {
+            warn!("Rejected rearrangement plan: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+
+// ==============================================================================
+// == Arrangement Validation ====================================================
+// ==============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/validate",
+    tag = "rearrangement",
+    responses(
+        (status = 200, description = "Violations found in the stored arrangement", body = ArrangementReport),
+    )
+)]
+pub async fn validate_arrangement(db_pool: web::Data<SqlitePool>) -> Result<HttpResponse, ApiError> {
+    info!("Validating stored arrangement");
+
+    let (containers, items, state) = tokio::try_join!(
+        load_containers(&db_pool, None),
+        sqlx::query_as::<_, DbItem>(r#"SELECT * FROM items ORDER BY "itemId""#).fetch_all(db_pool.get_ref()),
+        load_container_state(&db_pool),
+    )?;
+    let containers: Vec<Container> = containers.into_iter().map(Container::from).collect();
+    let items: Vec<Item> = items.into_iter().map(Item::from).collect();
+
+    let violations = integrity::check(&containers, &items, &state);
+    if !violations.is_empty() {
+        warn!("Stored arrangement has {} violations", violations.len());
+    }
+    Ok(HttpResponse::Ok().json(ArrangementReport {
+        success: true,
+        valid: violations.is_empty(),
+        containers: containers.len(),
+        items: items.len(),
+        placements: state.values().map(Vec::len).sum(),
+        violations,
+    }))
+}
+
+
+// ==============================================================================
+// == Container Compaction ======================================================
+// ==============================================================================
+
+async fn compact_containers(
+    db_pool: &SqlitePool,
+    containers: Vec<DbContainer>,
+    max_moves: usize,
+) -> Result<HttpResponse, ApiError> {
+    let state = load_container_state(db_pool).await?;
+    let priorities: HashMap<String, i32> = sqlx::query_as::<_, (String, i64)>(
+        r#"SELECT i."itemId", i.priority FROM placements p JOIN items i ON i."itemId" = p."itemId_fk""#)
+        .fetch_all(db_pool)
+        .await?
+        .into_iter()
+        .map(|(item_id, priority)| (item_id, priority as i32))
+        .collect();
+
+    let mut remaining_moves = max_moves;
+    let mut results: Vec<ContainerCompaction> = Vec::new();
+    for db_container in containers {
+        let container: Container = db_container.into();
+        let placements = state.get(&container.container_id).cloned().unwrap_or_default();
+
+        match compaction::plan_compaction(&container, &placements, &priorities, remaining_moves) {
+            Ok(plan) => {
+                remaining_moves -= plan.moves;
+                debug!("Compaction of {}: {} moves ({} steps)", container.container_id, plan.moves, plan.steps.len());
+                results.push(ContainerCompaction {
+                    container_id: container.container_id,
+                    move_count: plan.moves,
+                    truncated: plan.truncated,
+                    rearrangements: plan.steps,
+                });
+            }
+            Err(e) => {
+                error!("Compaction plan for {} is not executable: {}", container.container_id, e);
+                return Err(e.into());
             }
         }
+    }
 
-        if !placed {
-            debug!("    INFO (Phase 1): Could not place {} in preferred zone.", item_req.item_id);
-            items_requiring_placement_pass_2.push(item_req.clone());
+    Ok(HttpResponse::Ok().json(CompactionResponse { success: true, containers: results }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/containers/{id}/compact",
+    tag = "rearrangement",
+    params(("id" = String, Path, description = "containerId"), CompactionQuery),
+    responses(
+        (status = 200, description = "Compaction plan; nothing is moved", body = CompactionResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+    )
+)]
+pub async fn compact_container(
+    path: web::Path<String>,
+    query: ValidatedQuery<CompactionQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let container_id = path.into_inner();
+    info!("Planning compaction for container {}", container_id);
+
+    match sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers WHERE "containerId" = ?"#)
+        .bind(&container_id)
+        .fetch_optional(db_pool.get_ref())
+        .await
+    {
+        Ok(Some(container)) => compact_containers(&db_pool, vec![container], query.max_moves).await,
+        Ok(None) => Err(ApiError::ContainerNotFound(container_id)),
+        Err(e) => {
+            error!("Failed to fetch container {}: {}", container_id, e);
+            Err(e.into())
         }
     }
+}
 
+#[utoipa::path(
+    post,
+    path = "/api/zones/{zone}/compact",
+    tag = "rearrangement",
+    params(("zone" = String, Path), CompactionQuery),
+    responses(
+        (status = 200, description = "Compaction plan per container; nothing is moved", body = CompactionResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Zone not found", body = ErrorResponse),
+    )
+)]
+pub async fn compact_zone(
+    path: web::Path<String>,
+    query: ValidatedQuery<CompactionQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let zone = path.into_inner();
+    info!("Planning compaction for zone {}", zone);
+
+    match sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers WHERE zone = ? ORDER BY "containerId""#)
+        .bind(&zone)
+        .fetch_all(db_pool.get_ref())
+        .await
+    {
+        Ok(containers) if containers.is_empty() => Err(ApiError::ZoneNotFound(zone)),
+        Ok(containers) => compact_containers(&db_pool, containers, query.max_moves).await,
+        Err(e) => {
+            error!("Failed to fetch containers for zone {}: {}", zone, e);
+            Err(e.into())
+        }
+    }
+}
 
-    // --- Phase 2: Rearrangement Simulation ---
-    debug!("--- Phase 2: Evaluating Rearrangements ---");
-    let mut items_requiring_placement_pass_3 = items_requiring_placement_pass_2; // Start with items needing placement
-    let mut rearrangement_step_counter = 0;
-    let mut made_rearrangement_in_iteration = true; // Loop control
 
-    while made_rearrangement_in_iteration {
-        made_rearrangement_in_iteration = false;
-        let mut items_still_needing_placement_after_iter: Vec<Item> = Vec::new();
+// ==============================================================================
+// == Free Space Queries ========================================================
+// ==============================================================================
 
-        // Evaluate highest priority items first
-        items_requiring_placement_pass_3.sort_by(|a, b| b.priority.cmp(&a.priority));
+async fn load_containers(db_pool: &SqlitePool, zone: Option<&String>) -> Result<Vec<DbContainer>, sqlx::Error> {
+    match zone {
+        Some(zone) => sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers WHERE zone = ? ORDER BY "containerId""#)
+            .bind(zone)
+            .fetch_all(db_pool)
+            .await,
+        None => sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers ORDER BY "containerId""#)
+            .fetch_all(db_pool)
+            .await,
+    }
+}
 
-        for high_prio_item in &items_requiring_placement_pass_3 {
-            if processed_item_ids_in_request.contains(&high_prio_item.item_id) { continue; }
+#[utoipa::path(
+    get,
+    path = "/api/free-space",
+    tag = "free-space",
+    params(FreeSpaceQuery),
+    responses(
+        (status = 200, description = "Largest free boxes per container", body = FreeSpaceResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn get_free_space(
+    query: ValidatedQuery<FreeSpaceQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Computing free space (zone: {:?}, top: {})", query.zone, query.top);
+
+    let (containers, state) = tokio::try_join!(load_containers(&db_pool, query.zone.as_ref()), load_container_state(&db_pool))?;
+
+    let results: Vec<ContainerFreeSpace> = containers.into_iter().map(|db_container| {
+        let container: Container = db_container.into();
+        let placements = state.get(&container.container_id).map_or(&[][..], |v| &v[..]);
+        let spaces = free_space::maximal_free_spaces(&container, placements);
+        ContainerFreeSpace {
+            largest_free_volume: spaces.first().map_or(0.0, free_space::volume),
+            free_spaces: spaces.into_iter().take(query.top)
+                .map(|position| FreeSpace { volume: free_space::volume(&position), position })
+                .collect(),
+            container_id: container.container_id,
+            zone: container.zone,
+        }
+    }).collect();
 
-            debug!("Reviewing rearrangement for: {} (Prio: {})", high_prio_item.item_id, high_prio_item.priority);
-            let mut rearrangement_successful_for_this_item = false;
+    Ok(HttpResponse::Ok().json(FreeSpaceResponse { success: true, containers: results }))
+}
 
-            let preferred_container_ids: Vec<&String> = all_container_ids_map.keys()
-                .filter(|cid| all_container_ids_map[*cid].zone == high_prio_item.preferred_zone)
-                .collect();
+#[utoipa::path(
+    get,
+    path = "/api/free-space/fit",
+    tag = "free-space",
+    params(FitQuery),
+    responses(
+        (status = 200, description = "Containers with room for the given box", body = FitResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn get_fitting_containers(
+    query: ValidatedQuery<FitQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Finding containers that fit {}x{}x{} (zone: {:?})", query.width, query.depth, query.height, query.zone);
+
+    let (containers, state, masses) = tokio::try_join!(
+        load_containers(&db_pool, query.zone.as_ref()),
+        load_container_state(&db_pool),
+        load_placed_masses(&db_pool),
+    )?;
+
+    let dims = (query.width, query.depth, query.height);
+    let mut fits: Vec<ContainerFit> = Vec::new();
+    for db_container in containers {
+        let container: Container = db_container.into();
+        let placements = state.get(&container.container_id).map_or(&[][..], |v| &v[..]);
+        // Waste containers only take items being disposed of
+        if container.is_waste_container == Some(true) || exceeds_capacity(&container, placements, &masses, query.mass.unwrap_or(0.0)) {
+            continue;
+        }
+        // Front-most supported spot keeps the item easy to retrieve
+        let best = free_space::supported_spots(&container, placements, dims).into_iter()
+            .min_by(|a, b| {
+                a.start_coordinates.depth.partial_cmp(&b.start_coordinates.depth).unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.start_coordinates.height.partial_cmp(&b.start_coordinates.height).unwrap_or(std::cmp::Ordering::Equal))
+            });
+        if let Some(position) = best {
+            fits.push(ContainerFit {
+                position,
+                container_id: container.container_id,
+                zone: container.zone,
+            });
+        }
+    }
 
-             if preferred_container_ids.is_empty() {
-                 debug!("    No preferred zone for {}. Moving to next stage.", high_prio_item.item_id);
-                 items_still_needing_placement_after_iter.push(high_prio_item.clone());
-                 continue;
-             }
+    Ok(HttpResponse::Ok().json(FitResponse { success: true, containers: fits }))
+}
 
-            // Try direct placement again first
-            let mut placed_directly_in_phase2 = false;
-             for container_id in &preferred_container_ids {
-                 let container = &all_container_ids_map[*container_id];
-                 let current_sim_placements_in_cont = sim_placements.get(*container_id).map_or(vec![], |v| v.clone());
-                 if let Some((position, _)) = find_spot_in_container(
-                     (high_prio_item.width, high_prio_item.depth, high_prio_item.height),
-                     container, &current_sim_placements_in_cont, true)
-                 {
-                     sim_placements.entry((*container_id).clone()).or_default().push((high_prio_item.item_id.clone(), position.clone()));
-                     final_placements_for_response.insert(high_prio_item.item_id.clone(), PlacementResult {
-                         item_id: high_prio_item.item_id.clone(), container_id: (*container_id).clone(), position,
-                         retrieval_steps: 0, is_preferred_zone: true
-                     });
-                     processed_item_ids_in_request.insert(high_prio_item.item_id.clone());
-                     debug!("    SUCCESS (Phase 2 Direct): Placed {} in preferred {}", high_prio_item.item_id, container_id);
-                     placed_directly_in_phase2 = true;
-                     made_rearrangement_in_iteration = true; // State changed
-                     break;
-                 }
-             }
-             if placed_directly_in_phase2 { continue; } // Go to next high_prio_item
-
-
-            // Identify potential items to displace in preferred containers
-            let mut potential_displacees = vec![];
-            for container_id in &preferred_container_ids {
-                if let Some(current_sim_placements_in_cont) = sim_placements.get(*container_id) {
-                    for (existing_item_id, existing_pos) in current_sim_placements_in_cont {
-                        let existing_prio = all_item_props.get(existing_item_id).map_or(-1, |props| props.0);
-                        if existing_prio >= 0 && existing_prio < high_prio_item.priority {
-                            potential_displacees.push((existing_item_id.clone(), existing_prio, (*container_id).clone(), existing_pos.clone()));
-                        }
-                    }
-                }
-            }
-            potential_displacees.sort_by_key(|&(_, prio, _, _)| prio); // Sort by priority ASC
 
-            if potential_displacees.is_empty() {
-                debug!("    No lower-priority items to displace for {}", high_prio_item.item_id);
-                items_still_needing_placement_after_iter.push(high_prio_item.clone());
-                continue;
-            }
+// ==============================================================================
+// == Item & Container Resources ================================================
+// ==============================================================================
 
-            // Try displacing items one by one
-            for (displacee_id, _, source_container_id, source_position) in potential_displacees {
-                debug!("    Attempting to displace: {} from {}", displacee_id, source_container_id);
-
-                // 1. Simulate removal
-                let mut temp_sim_placements_in_source = sim_placements.get(&source_container_id).map_or(vec![], |v| v.clone());
-                temp_sim_placements_in_source.retain(|(id, _)| id != &displacee_id);
-
-                // 2. Check if high-prio item fits now
-                let source_container = &all_container_ids_map[&source_container_id];
-                if let Some(spot_for_high_prio) = find_spot_in_container(
-                    (high_prio_item.width, high_prio_item.depth, high_prio_item.height),
-                    source_container, &temp_sim_placements_in_source, true)
-                {
-                     // 3. Try to find NEW home for displacee
-                    let displacee_props = all_item_props.get(&displacee_id);
-                    if displacee_props.is_none() {
-                        error!("Cannot find props for displacee {}. Skipping displacement.", displacee_id); continue;
-                    }
-                    let displacee_dims = (displacee_props.unwrap().1, displacee_props.unwrap().2, displacee_props.unwrap().3);
-                    let mut relocated = false;
-
-                    for target_container_id in all_container_ids_map.keys() {
-                        if target_container_id == &source_container_id { continue; } // Don't try same container
-
-                        let target_container = &all_container_ids_map[target_container_id];
-                        let current_sim_placements_in_target = sim_placements.get(target_container_id).map_or(vec![], |v| v.clone());
-
-                        if let Some(spot_for_displacee) = find_spot_in_container(
-                            displacee_dims, target_container, &current_sim_placements_in_target, false) // Low prio placement
-                        {
-                            let (new_position_displacee, _) = spot_for_displacee;
-                            debug!("      SUCCESS: Found new spot for displaced {} in {}", displacee_id, target_container_id);
-
-                            // 4. Commit simulation changes
-                            rearrangement_step_counter += 1;
-                            rearrangements_result.push(RearrangementStep {
-                                step: rearrangement_step_counter, action: "move".to_string(), item_id: displacee_id.clone(),
-                                from_container: Some(source_container_id.clone()), from_position: Some(source_position.clone()),
-                                to_container: Some(target_container_id.clone()), to_position: Some(new_position_displacee.clone())
-                            });
-
-                            // Update sim_placements: remove displacee from old, add to new
-                            sim_placements.get_mut(&source_container_id).unwrap().retain(|(id, _)| id != &displacee_id);
-                            sim_placements.entry(target_container_id.clone()).or_default().push((displacee_id.clone(), new_position_displacee.clone()));
-
-                            // Update final response for displaced item
-                            final_placements_for_response.insert(displacee_id.clone(), PlacementResult {
-                                item_id: displacee_id.clone(), container_id: target_container_id.clone(), position: new_position_displacee,
-                                retrieval_steps: 0, is_preferred_zone: false // Zone check needed if API requires it
-                            });
-
-                            // Place high-prio item in freed spot
-                            let (position_high_prio, _) = spot_for_high_prio;
-                            sim_placements.get_mut(&source_container_id).unwrap().push((high_prio_item.item_id.clone(), position_high_prio.clone()));
-
-                             // Update final response for high-prio item
-                             final_placements_for_response.insert(high_prio_item.item_id.clone(), PlacementResult {
-                                item_id: high_prio_item.item_id.clone(), container_id: source_container_id.clone(), position: position_high_prio,
-                                retrieval_steps: 0, is_preferred_zone: true
-                            });
-
-                            processed_item_ids_in_request.insert(high_prio_item.item_id.clone());
-                            rearrangement_successful_for_this_item = true;
-                            made_rearrangement_in_iteration = true; // State changed
-                            debug!("    SUCCESS (Phase 2): Displaced {}, Placed {} in {}", displacee_id, high_prio_item.item_id, source_container_id);
-                            relocated = true;
-                            break; // Stop trying to relocate this specific displacee
-                        }
-                    }
-                    if !relocated {
-                        debug!("      Failed to relocate displaced item {}. Trying next displacee.", displacee_id);
-                        // Don't commit simulation changes, loop continues to next displacee
-                    }
-
-                } else {
-                     debug!("      Removing {} does not free space for {}. Trying next displacee.", displacee_id, high_prio_item.item_id);
-                }
+async fn fetch_item_record(conn: &mut SqliteConnection, item_id: &str) -> Result<ItemRecord, ApiError> {
+    let item = sqlx::query_as::<_, DbItem>(r#"SELECT * FROM items WHERE "itemId" = ?"#)
+        .bind(item_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::ItemNotFound(item_id.to_string()))?;
+    let placement = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements WHERE "itemId_fk" = ?"#)
+        .bind(item_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(ItemRecord {
+        item: item.into(),
+        container_id: placement.as_ref().map(|p_db| p_db.container_id_fk.clone()),
+        position: placement.as_ref().map(db_position),
+    })
+}
 
-                if rearrangement_successful_for_this_item {
-                    break; // Stop trying to displace other items for this high_prio_item
-                }
-            } // End loop through potential displacees
+async fn fetch_container_record(conn: &mut SqliteConnection, container_id: &str) -> Result<ContainerRecord, ApiError> {
+    let container = sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers WHERE "containerId" = ?"#)
+        .bind(container_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| ApiError::ContainerNotFound(container_id.to_string()))?;
+    let (item_count,): (i64,) = sqlx::query_as(r#"SELECT COUNT(*) FROM placements WHERE "containerId_fk" = ?"#)
+        .bind(container_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    Ok(ContainerRecord { version: container.version, container: container.into(), item_count })
+}
 
-            // If no successful rearrangement occurred for this item in this iteration
-            if !rearrangement_successful_for_this_item {
-                 debug!("    All displacement attempts failed for {} in this iteration.", high_prio_item.item_id);
-                 items_still_needing_placement_after_iter.push(high_prio_item.clone());
-            }
-        } // End loop through items needing placement in this iteration
+// A container's placements and the masses of the items in it that have one
+async fn load_container_contents(
+    conn: &mut SqliteConnection,
+    container_id: &str,
+) -> Result<(Vec<(String, Position)>, HashMap<String, f64>), sqlx::Error> {
+    let placements = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements WHERE "containerId_fk" = ?"#)
+        .bind(container_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let masses = sqlx::query_as::<_, (String, f64)>(
+        r#"SELECT i."itemId", i.mass FROM placements p JOIN items i ON i."itemId" = p."itemId_fk"
+           WHERE p."containerId_fk" = ? AND i.mass IS NOT NULL"#)
+        .bind(container_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let placements = placements.iter().map(|p_db| (p_db.item_id_fk.clone(), db_position(p_db))).collect();
+    Ok((placements, masses.into_iter().collect()))
+}
 
-        items_requiring_placement_pass_3 = items_still_needing_placement_after_iter;
-    } // End while made_rearrangement_in_iteration
+/// Fails with `ContainerOverweight` if `item_mass` on top of `placed` would take
+/// `container` past its weight capacity.
+fn check_capacity(container: &Container, placed: &[(String, Position)], masses: &HashMap<String, f64>, item_mass: f64) -> Result<(), ApiError> {
+    if !exceeds_capacity(container, placed, masses, item_mass) {
+        return Ok(());
+    }
+    let held: f64 = placed.iter().filter_map(|(id, _)| masses.get(id)).sum();
+    Err(ApiError::ContainerOverweight {
+        container_id: container.container_id.clone(),
+        total_mass: held + item_mass,
+        capacity: container.max_weight_capacity.unwrap_or_default(),
+    })
+}
 
+async fn write_item<'e>(
+    executor: impl sqlx::Executor<'e, Database = Sqlite>,
+    item: &Item,
+    insert: bool,
+) -> Result<(), sqlx::Error> {
+    let sql = if insert {
+        r#"INSERT INTO items (name, width, depth, height, mass, priority, "expiryDate", "usageLimit", "currentUses", "preferredZone", status, "itemId")
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#
+    } else {
+        r#"UPDATE items SET name = ?, width = ?, depth = ?, height = ?, mass = ?, priority = ?, "expiryDate" = ?,
+           "usageLimit" = ?, "currentUses" = ?, "preferredZone" = ?, status = ? WHERE "itemId" = ?"#
+    };
+    sqlx::query(sql)
+        .bind(&item.name)
+        .bind(item.width)
+        .bind(item.depth)
+        .bind(item.height)
+        .bind(item.mass)
+        .bind(item.priority)
+        .bind(item.expiry_date)
+        .bind(item.usage_limit as i64)
+        .bind(item.current_uses as i64)
+        .bind(&item.preferred_zone)
+        .bind(item.status.as_ref().map_or("ACTIVE", ItemStatus::as_str))
+        .bind(&item.item_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
 
-    // --- Phase 3: Final Placement Attempt (Anywhere) ---
-    debug!("--- Phase 3: Final Placement Attempt (Anywhere) ---");
-    for item_req in &items_requiring_placement_pass_3 { // Use items remaining after Phase 2
-        if processed_item_ids_in_request.contains(&item_req.item_id) { continue; }
+async fn write_container<'e>(
+    executor: impl sqlx::Executor<'e, Database = Sqlite>,
+    container: &Container,
+    insert: bool,
+) -> Result<(), sqlx::Error> {
+    let sql = if insert {
+        r#"INSERT INTO containers (zone, width, depth, height, "isWasteContainer", "maxWeightCapacity", "containerId")
+           VALUES (?, ?, ?, ?, ?, ?, ?)"#
+    } else {
+        r#"UPDATE containers SET zone = ?, width = ?, depth = ?, height = ?, "isWasteContainer" = ?, "maxWeightCapacity" = ?
+           WHERE "containerId" = ?"#
+    };
+    sqlx::query(sql)
+        .bind(&container.zone)
+        .bind(container.width)
+        .bind(container.depth)
+        .bind(container.height)
+        .bind(container.is_waste_container)
+        .bind(container.max_weight_capacity)
+        .bind(&container.container_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
 
-        debug!("Attempting final placement for: {}", item_req.item_id);
-        let mut placed = false;
-        let is_high_prio = item_req.priority >= 50;
+const ITEM_SORT_COLUMNS: &[SortColumn] = &[
+    SortColumn { name: "itemId", expr: r#"i."itemId""# },
+    SortColumn { name: "name", expr: "i.name" },
+    SortColumn { name: "width", expr: "i.width" },
+    SortColumn { name: "depth", expr: "i.depth" },
+    SortColumn { name: "height", expr: "i.height" },
+    SortColumn { name: "mass", expr: "IFNULL(i.mass, 0)" },
+    SortColumn { name: "priority", expr: "i.priority" },
+    SortColumn { name: "expiryDate", expr: r#"IFNULL(i."expiryDate", '')"# },
+    SortColumn { name: "usageLimit", expr: r#"IFNULL(i."usageLimit", 9223372036854775807)"# },
+    SortColumn { name: "currentUses", expr: r#"i."currentUses""# },
+    SortColumn { name: "preferredZone", expr: r#"IFNULL(i."preferredZone", '')"# },
+    SortColumn { name: "status", expr: "i.status" },
+    SortColumn { name: "containerId", expr: r#"IFNULL((SELECT "containerId_fk" FROM placements WHERE "itemId_fk" = i."itemId"), '')"# },
+];
+
+fn push_item_filters<'a>(qb: &mut QueryBuilder<'a, Sqlite>, query: &'a ItemListQuery) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(search) = &query.search {
+        // instr rather than LIKE, so % and _ in the search text match literally
+        qb.push(r#" AND (instr(lower(i."itemId"), lower("#).push_bind(search)
+            .push(")) > 0 OR instr(lower(i.name), lower(").push_bind(search).push(")) > 0)");
+    }
+    if let Some(zone) = &query.zone {
+        qb.push(r#" AND i."preferredZone" = "#).push_bind(zone);
+    }
+    if let Some(status) = &query.status {
+        qb.push(" AND i.status = ").push_bind(status.as_str());
+    }
+    if let Some(min) = query.min_priority {
+        qb.push(" AND i.priority >= ").push_bind(min);
+    }
+    if let Some(max) = query.max_priority {
+        qb.push(" AND i.priority <= ").push_bind(max);
+    }
+    if let Some(after) = query.expires_after {
+        qb.push(r#" AND i."expiryDate" >= "#).push_bind(after);
+    }
+    if let Some(before) = query.expires_before {
+        qb.push(r#" AND i."expiryDate" <= "#).push_bind(before);
+    }
+    if let Some(container_id) = &query.container_id {
+        qb.push(r#" AND i."itemId" IN (SELECT "itemId_fk" FROM placements WHERE "containerId_fk" = "#)
+            .push_bind(container_id)
+            .push(")");
+    }
+}
 
-        for container_id in all_container_ids_map.keys() {
-            let container = &all_container_ids_map[container_id];
-            let current_sim_placements_in_cont = sim_placements.get(container_id).map_or(vec![], |v| v.clone());
+#[utoipa::path(
+    get,
+    path = "/api/items",
+    tag = "items",
+    params(ItemListQuery),
+    responses(
+        (status = 200, description = "One page of items", body = ItemListResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn list_items(
+    query: ValidatedQuery<ItemListQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let sort = listing::parse_sort(query.sort.as_deref(), ITEM_SORT_COLUMNS)?;
+    let cursor = listing::parse_cursor(query.cursor.as_deref())?;
+    if let Some(cursor) = cursor {
+        listing::check_cursor(&db_pool, "items", cursor).await?;
+    }
 
-             if let Some((position, _)) = find_spot_in_container(
-                (item_req.width, item_req.depth, item_req.height),
-                container, &current_sim_placements_in_cont, is_high_prio)
-            {
-                sim_placements.entry(container_id.clone()).or_default().push((item_req.item_id.clone(), position.clone()));
-                final_placements_for_response.insert(item_req.item_id.clone(), PlacementResult {
-                    item_id: item_req.item_id.clone(), container_id: container_id.clone(), position,
-                    retrieval_steps: 0, is_preferred_zone: container.zone == item_req.preferred_zone
-                });
-                processed_item_ids_in_request.insert(item_req.item_id.clone());
-                debug!("    SUCCESS (Phase 3): Placed {} in NON-PREFERRED {}", item_req.item_id, container_id);
-                placed = true;
-                break;
-            }
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM items i");
+    push_item_filters(&mut count, &query);
+    let (total,): (i64,) = count.build_query_as().fetch_one(db_pool.get_ref()).await?;
+
+    let mut page = QueryBuilder::new("SELECT i.* FROM items i");
+    push_item_filters(&mut page, &query);
+    if let Some(cursor) = cursor {
+        listing::push_after_cursor(&mut page, &sort, "items", "i", cursor);
+    }
+    listing::push_order(&mut page, &sort, "i", query.limit);
+    let mut items: Vec<DbItem> = page.build_query_as().fetch_all(db_pool.get_ref()).await?;
+    let next_cursor = listing::next_cursor(&mut items, query.limit, |item| item.id);
+
+    let records = item_records(&db_pool, items).await?;
+    Ok(HttpResponse::Ok().json(ItemListResponse { success: true, items: records, total, next_cursor }))
+}
+
+// Pairs one page of items with their placements, in one query
+async fn item_records(db_pool: &SqlitePool, items: Vec<DbItem>) -> Result<Vec<ItemRecord>, sqlx::Error> {
+    let mut placements: HashMap<String, DbPlacement> = HashMap::new();
+    if !items.is_empty() {
+        let mut qb = QueryBuilder::new(r#"SELECT * FROM placements WHERE "itemId_fk" IN ("#);
+        let mut ids = qb.separated(", ");
+        for item in &items {
+            ids.push_bind(&item.item_id);
         }
+        qb.push(")");
+        placements = qb.build_query_as::<DbPlacement>()
+            .fetch_all(db_pool)
+            .await?
+            .into_iter()
+            .map(|p_db| (p_db.item_id_fk.clone(), p_db))
+            .collect();
+    }
 
-        if !placed {
-            warn!("    !!! PLACEMENT FAILED COMPLETELY for item {} !!!", item_req.item_id);
-            items_failed_completely.push(item_req.item_id.clone());
-            processed_item_ids_in_request.insert(item_req.item_id.clone()); // Mark as processed (failed)
-            // Remove from final response map if it was somehow added
-            final_placements_for_response.remove(&item_req.item_id);
+    Ok(items.into_iter().map(|item| {
+        let placement = placements.get(&item.item_id);
+        ItemRecord {
+            container_id: placement.map(|p_db| p_db.container_id_fk.clone()),
+            position: placement.map(db_position),
+            item: item.into(),
         }
+    }).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/items/{id}",
+    tag = "items",
+    params(("id" = String, Path, description = "itemId")),
+    responses(
+        (status = 200, body = ItemResponse),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_item(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> Result<HttpResponse, ApiError> {
+    let item = fetch_item_record(&mut *db_pool.acquire().await?, &path).await?;
+    Ok(HttpResponse::Ok().json(ItemResponse { success: true, item }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/items",
+    tag = "items",
+    request_body = Item,
+    responses(
+        (status = 201, body = ItemResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 409, description = "Item already exists", body = ErrorResponse),
+    )
+)]
+pub async fn create_item(
+    req: Validated<Item>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Creating item {}", req.item_id);
+    match write_item(db_pool.get_ref(), &req, true).await {
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(ApiError::ItemAlreadyExists(req.item_id.clone())),
+        result => result?,
     }
+    let item = fetch_item_record(&mut *db_pool.acquire().await?, &req.item_id).await?;
+    events.publish(Event::ItemChanged { item_id: req.item_id.clone(), container_id: None, zone: None, deleted: false });
+    Ok(HttpResponse::Created().json(ItemResponse { success: true, item }))
+}
 
-    debug!("--- End Simulation Phases --- Failed items: {:?}", items_failed_completely);
+#[utoipa::path(
+    patch,
+    path = "/api/items/{id}",
+    tag = "items",
+    request_body = ItemPatch,
+    params(("id" = String, Path, description = "itemId")),
+    responses(
+        (status = 200, body = ItemResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+        (status = 422, description = "Container weight capacity exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn update_item(
+    path: web::Path<String>,
+    req: web::Json<ItemPatch>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    let item_id = path.into_inner();
+    info!("Updating item {}", item_id);
+
+    // The resize check only holds if nobody places the item between reading and writing it
+    let mut tx = db_pool.begin().await?;
+    let current = fetch_item_record(&mut tx, &item_id).await?;
+    let mut item = current.item.clone();
+    req.into_inner().apply(&mut item);
+    item.validate()?;
+
+    // A placed item's box is stored with the placement; resizing it would silently invalidate it
+    let tol = config::placement().tolerance;
+    let resized = (item.width - current.item.width).abs() > tol
+        || (item.depth - current.item.depth).abs() > tol
+        || (item.height - current.item.height).abs() > tol;
+    if resized && current.container_id.is_some() {
+        return Err(ApiError::validation("width", "dimensions of a placed item cannot change; remove its placement first"));
+    }
 
-    // --- Phase 4: Persistence ---
-    debug!("--- Phase 4: Persisting Changes to Database ---");
-    // Use the final state from `final_placements_for_response`
-
-     // 4.1 Upsert Containers (ensure they exist, maybe update dims if needed)
-     for (c_id, c_data) in &all_container_ids_map {
-          match sqlx::query(
-              r#"INSERT INTO containers ("containerId", zone, width, depth, height, "isWasteContainer", "maxWeightCapacity")
-                 VALUES (?, ?, ?, ?, ?, ?, ?)
-                 ON CONFLICT("containerId") DO UPDATE SET
-                    zone=excluded.zone,
-                    width=excluded.width,
-                    depth=excluded.depth,
-                    height=excluded.height,
-                    "isWasteContainer"=excluded."isWasteContainer",
-                    "maxWeightCapacity"=excluded."maxWeightCapacity"
-              "#)
-              .bind(c_id)
-              .bind(&c_data.zone)
-              .bind(c_data.width)
-              .bind(c_data.depth)
-              .bind(c_data.height)
-              .bind(c_data.is_waste_container)
-              .bind(c_data.max_weight_capacity)
-              .execute(&mut *tx).await {
-              Ok(_) => debug!("Upserted container {}", c_id),
-              Err(e) => {
-                  error!("Failed to upsert container {}: {}", c_id, e);
-                  tx.rollback().await.ok();
-                   return Ok(HttpResponse::InternalServerError().json(PlacementResponse::error(format!("DB error upserting container {}: {}", c_id, e))));
-              }
-          }
-     }
-
-    // 4.2 Process final placements: Upsert Items and Placements
-     for (item_id, final_placement) in &final_placements_for_response {
-         // Find corresponding request item data
-         let req_item_data = req.items.iter().find(|i| i.item_id == *item_id);
-
-         // Upsert Item
-         match sqlx::query(
-            r#"
-            INSERT INTO items ("itemId", name, width, depth, height, mass, priority, "expiryDate", "usageLimit", "currentUses", "preferredZone", status)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'ACTIVE')
-            ON CONFLICT("itemId") DO UPDATE SET
-                name=excluded.name,
-                width=excluded.width,
-                depth=excluded.depth,
-                height=excluded.height,
-                mass=excluded.mass,
-                priority=excluded.priority,
-                "expiryDate"=excluded."expiryDate",
-                "usageLimit"=excluded."usageLimit",
-                -- currentUses = currentUses, -- Don't reset uses on placement update
-                "preferredZone"=excluded."preferredZone",
-                status='ACTIVE' -- Ensure item is marked active on placement/move
-            "#)
-            .bind(item_id)
-            .bind(req_item_data.map_or("Unknown", |i| &i.name))
-            .bind(req_item_data.map_or(0.0, |i| i.width))
-            .bind(req_item_data.map_or(0.0, |i| i.depth))
-            .bind(req_item_data.map_or(0.0, |i| i.height))
-            .bind(req_item_data.and_then(|i| i.mass))
-            .bind(req_item_data.map_or(0, |i| i.priority))
-            .bind(req_item_data.and_then(|i| i.expiry_date))
-            .bind(req_item_data.map(|i| i.usage_limit as i64))
-            .bind(req_item_data.map_or(0, |i| i.current_uses as i64))
-            .bind(req_item_data.map(|i| &i.preferred_zone))
-            .execute(&mut *tx).await {
-             Ok(_) => debug!("Upserted item {}", item_id),
-             Err(e) => {
-                  error!("Failed to upsert item {}: {}", item_id, e);
-                  tx.rollback().await.ok();
-                   return Ok(HttpResponse::InternalServerError().json(PlacementResponse::error(format!("DB error upserting item {}: {}", item_id, e))));
-             }
-         }
+    // A heavier item may take its container past its weight capacity
+    let mass_changed = match (item.mass, current.item.mass) {
+        (Some(new), Some(old)) => (new - old).abs() > tol,
+        (new, old) => new.is_some() != old.is_some(),
+    };
+    if let (true, Some(container_id)) = (mass_changed, &current.container_id) {
+        let container = fetch_container_record(&mut tx, container_id).await?.container;
+        let (mut placements, masses) = load_container_contents(&mut tx, container_id).await?;
+        placements.retain(|(id, _)| id != &item_id);
+        check_capacity(&container, &placements, &masses, item.mass.unwrap_or(0.0))?;
+        bump_container_version(&mut tx, container_id, None).await?;
+    }
 
-         // Upsert Placement (delete old, insert new conceptually)
-         match sqlx::query(r#"DELETE FROM placements WHERE "itemId_fk" = ?"#)
-                 .bind(item_id)
-                 .execute(&mut *tx).await {
-             Ok(_) => debug!("Deleted old placement for {}", item_id),
-             Err(e) => error!("Failed to delete old placement for {}: {} (may not exist)", item_id, e), // Log error but proceed
-         }
+    write_item(&mut *tx, &item, false).await?;
+    let record = fetch_item_record(&mut tx, &item_id).await?;
+    let (container_id, zone) = item_location(&mut *tx, &item_id).await?.unzip();
+    tx.commit().await?;
 
-         match sqlx::query(
-             r#"INSERT INTO placements ("itemId_fk", "containerId_fk", start_w, start_d, start_h, end_w, end_d, end_h)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#)
-             .bind(item_id)
-             .bind(&final_placement.container_id)
-             .bind(final_placement.position.start_coordinates.width)
-             .bind(final_placement.position.start_coordinates.depth)
-             .bind(final_placement.position.start_coordinates.height)
-             .bind(final_placement.position.end_coordinates.width)
-             .bind(final_placement.position.end_coordinates.depth)
-             .bind(final_placement.position.end_coordinates.height)
-             .execute(&mut *tx).await {
-              Ok(_) => debug!("Inserted new placement for {}", item_id),
-             Err(e) => {
-                  error!("Failed to insert placement for {}: {}", item_id, e);
-                  tx.rollback().await.ok();
-                   return Ok(HttpResponse::InternalServerError().json(PlacementResponse::error(format!("DB error inserting placement {}: {}", item_id, e))));
-             }
-         }
-     }
+    events.publish(Event::ItemChanged { item_id: item_id.clone(), container_id: container_id.clone(), zone: zone.clone(), deleted: false });
+    let is_waste = |status: &Option<ItemStatus>| matches!(status, Some(ItemStatus::WASTE_EXPIRED | ItemStatus::WASTE_DEPLETED));
+    if is_waste(&item.status) && !is_waste(&current.item.status) {
+        let status = item.status.as_ref().map_or("", ItemStatus::as_str).to_string();
+        events.publish(Event::ItemBecameWaste { item_id, container_id, zone, status });
+    }
+    Ok(HttpResponse::Ok().json(ItemResponse { success: true, item: record }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/items/{id}",
+    tag = "items",
+    params(("id" = String, Path, description = "itemId")),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, description = "Item not found", body = ErrorResponse),
+    )
+)]
+pub async fn delete_item(
+    path: web::Path<String>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    let item_id = path.into_inner();
+    info!("Deleting item {}", item_id);
+
+    let mut tx = db_pool.begin().await?;
+    let previous = item_location(&mut *tx, &item_id).await?;
+    if let Some((container_id, _)) = &previous {
+        bump_container_version(&mut tx, container_id, None).await?;
+    }
+    let removed_placements = sqlx::query(r#"DELETE FROM placements WHERE "itemId_fk" = ?"#)
+        .bind(&item_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    let deleted = sqlx::query(r#"DELETE FROM items WHERE "itemId" = ?"#)
+        .bind(&item_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    if deleted == 0 {
+        tx.rollback().await.ok();
+        return Err(ApiError::ItemNotFound(item_id));
+    }
+    tx.commit().await?;
+
+    let (container_id, zone) = previous.unzip();
+    if container_id.is_some() {
+        events.publish(Event::PlacementChanged {
+            item_id: item_id.clone(),
+            container_id: None,
+            zone: None,
+            position: None,
+            previous_container_id: container_id.clone(),
+            previous_zone: zone.clone(),
+        });
+    }
+    events.publish(Event::ItemChanged { item_id, container_id, zone, deleted: true });
+
+    Ok(HttpResponse::Ok().json(DeleteResponse { success: true, removed_placements }))
+}
+
+const CONTAINER_ITEM_COUNT: &str = r#"(SELECT COUNT(*) FROM placements WHERE "containerId_fk" = c."containerId")"#;
+
+const CONTAINER_SORT_COLUMNS: &[SortColumn] = &[
+    SortColumn { name: "containerId", expr: r#"c."containerId""# },
+    SortColumn { name: "zone", expr: "c.zone" },
+    SortColumn { name: "width", expr: "c.width" },
+    SortColumn { name: "depth", expr: "c.depth" },
+    SortColumn { name: "height", expr: "c.height" },
+    SortColumn { name: "isWasteContainer", expr: r#"IFNULL(c."isWasteContainer", 0)"# },
+    SortColumn { name: "maxWeightCapacity", expr: r#"IFNULL(c."maxWeightCapacity", 0)"# },
+    SortColumn { name: "itemCount", expr: CONTAINER_ITEM_COUNT },
+];
+
+fn push_container_filters<'a>(qb: &mut QueryBuilder<'a, Sqlite>, query: &'a ContainerListQuery) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(search) = &query.search {
+        qb.push(r#" AND (instr(lower(c."containerId"), lower("#).push_bind(search)
+            .push(")) > 0 OR instr(lower(c.zone), lower(").push_bind(search).push(")) > 0)");
+    }
+    if let Some(zone) = &query.zone {
+        qb.push(" AND c.zone = ").push_bind(zone);
+    }
+    if let Some(container_id) = &query.container_id {
+        qb.push(r#" AND c."containerId" = "#).push_bind(container_id);
+    }
+    if let Some(is_waste) = query.is_waste_container {
+        qb.push(r#" AND IFNULL(c."isWasteContainer", 0) = "#).push_bind(is_waste);
+    }
+}
 
-    // 4.3 Handle failed items (just log for now, item records might exist from above)
-    if !items_failed_completely.is_empty() {
-        warn!("The following items could not be placed: {:?}", items_failed_completely);
-        // Potentially log these failures to a dedicated log table in the future
+#[utoipa::path(
+    get,
+    path = "/api/containers",
+    tag = "containers",
+    params(ContainerListQuery),
+    responses(
+        (status = 200, description = "One page of containers", body = ContainerListResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn list_containers(
+    query: ValidatedQuery<ContainerListQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let sort = listing::parse_sort(query.sort.as_deref(), CONTAINER_SORT_COLUMNS)?;
+    let cursor = listing::parse_cursor(query.cursor.as_deref())?;
+    if let Some(cursor) = cursor {
+        listing::check_cursor(&db_pool, "containers", cursor).await?;
     }
 
-    // Commit transaction
-    if let Err(e) = tx.commit().await {
-        error!("Database transaction commit failed: {}", e);
-        // No need to rollback, commit failed
-        return Ok(HttpResponse::InternalServerError().json(PlacementResponse::error(format!("Database commit failed: {}", e))));
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM containers c");
+    push_container_filters(&mut count, &query);
+    let (total,): (i64,) = count.build_query_as().fetch_one(db_pool.get_ref()).await?;
+
+    let mut page = QueryBuilder::new(format!("SELECT c.*, {} AS item_count FROM containers c", CONTAINER_ITEM_COUNT));
+    push_container_filters(&mut page, &query);
+    if let Some(cursor) = cursor {
+        listing::push_after_cursor(&mut page, &sort, "containers", "c", cursor);
     }
-    debug!("--- DB Commit Successful ---");
+    listing::push_order(&mut page, &sort, "c", query.limit);
+    let mut containers: Vec<(DbContainer, i64)> = Vec::new();
+    for row in page.build().fetch_all(db_pool.get_ref()).await? {
+        let container = DbContainer::from_row(&row)?;
+        containers.push((container, row.try_get("item_count")?));
+    }
+    let next_cursor = listing::next_cursor(&mut containers, query.limit, |(container, _)| container.id);
+
+    let records = containers.into_iter().map(|(container, item_count)| ContainerRecord {
+        version: container.version,
+        container: container.into(),
+        item_count,
+    }).collect();
+    Ok(HttpResponse::Ok().json(ContainerListResponse { success: true, containers: records, total, next_cursor }))
+}
 
+#[utoipa::path(
+    get,
+    path = "/api/containers/{id}",
+    tag = "containers",
+    params(("id" = String, Path, description = "containerId")),
+    responses(
+        (status = 200, body = ContainerResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_container(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> Result<HttpResponse, ApiError> {
+    let container = fetch_container_record(&mut *db_pool.acquire().await?, &path).await?;
+    Ok(HttpResponse::Ok().json(ContainerResponse { success: true, container }))
+}
 
-    // --- Phase 5: Format Response ---
-    debug!("--- Phase 5: Formatting Response ---");
-    let final_placements_list: Vec<PlacementResult> = final_placements_for_response.values().cloned().collect();
-    let success = items_failed_completely.is_empty();
-    let error_msg = if success { None } else { Some(format!("Placement incomplete. Could not place items: {:?}", items_failed_completely)) };
+#[utoipa::path(
+    post,
+    path = "/api/containers",
+    tag = "containers",
+    request_body = Container,
+    responses(
+        (status = 201, body = ContainerResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 409, description = "Container already exists", body = ErrorResponse),
+    )
+)]
+pub async fn create_container(
+    req: Validated<Container>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Creating container {}", req.container_id);
+    match write_container(db_pool.get_ref(), &req, true).await {
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Err(ApiError::ContainerAlreadyExists(req.container_id.clone())),
+        result => result?,
+    }
+    let container = fetch_container_record(&mut *db_pool.acquire().await?, &req.container_id).await?;
+    events.publish(Event::ContainerChanged { container_id: req.container_id.clone(), zone: req.zone.clone(), deleted: false });
+    Ok(HttpResponse::Created().json(ContainerResponse { success: true, container }))
+}
 
-    let response_data = PlacementResponse {
-        success,
-        placements: final_placements_list,
-        rearrangements: rearrangements_result,
-        error: error_msg,
+#[utoipa::path(
+    patch,
+    path = "/api/containers/{id}",
+    tag = "containers",
+    request_body = ContainerPatch,
+    params(("id" = String, Path, description = "containerId")),
+    responses(
+        (status = 200, body = ContainerResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 422, description = "Container weight capacity exceeded", body = ErrorResponse),
+    )
+)]
+pub async fn update_container(
+    path: web::Path<String>,
+    req: web::Json<ContainerPatch>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    let container_id = path.into_inner();
+    info!("Updating container {}", container_id);
+
+    // Same transaction as the shrink check below, so nothing can be placed in between
+    let mut tx = db_pool.begin().await?;
+    let mut container = fetch_container_record(&mut tx, &container_id).await?.container;
+    req.into_inner().apply(&mut container);
+    container.validate()?;
+
+    // Shrinking must not cut through items already inside
+    let (placements, masses) = load_container_contents(&mut tx, &container_id).await?;
+    let mut violations = Vec::new();
+    for (axis, limit, extent) in [
+        ("width", container.width, placements.iter().map(|(_, p)| p.end_coordinates.width).fold(0.0, f64::max)),
+        ("depth", container.depth, placements.iter().map(|(_, p)| p.end_coordinates.depth).fold(0.0, f64::max)),
+        ("height", container.height, placements.iter().map(|(_, p)| p.end_coordinates.height).fold(0.0, f64::max)),
+    ] {
+        if extent > limit + config::placement().tolerance {
+            violations.push(FieldError::new(axis, format!("placed items extend to {} which exceeds the new {} of {}", extent, axis, limit)));
+        }
+    }
+    if !violations.is_empty() {
+        return Err(ApiError::Validation(violations));
+    }
+    // Nor may a lower weight capacity leave it holding more than it is allowed to
+    check_capacity(&container, &placements, &masses, 0.0)?;
+
+    bump_container_version(&mut tx, &container_id, None).await?;
+    write_container(&mut *tx, &container, false).await?;
+    let record = fetch_container_record(&mut tx, &container_id).await?;
+    tx.commit().await?;
+    events.publish(Event::ContainerChanged { container_id: container_id.clone(), zone: container.zone.clone(), deleted: false });
+    Ok(HttpResponse::Ok().json(ContainerResponse { success: true, container: record }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/containers/{id}",
+    tag = "containers",
+    params(("id" = String, Path, description = "containerId"), DeleteContainerQuery),
+    responses(
+        (status = 200, body = DeleteResponse),
+        (status = 404, description = "Container not found", body = ErrorResponse),
+        (status = 409, description = "Container still holds items", body = ErrorResponse),
+    )
+)]
+pub async fn delete_container(
+    path: web::Path<String>,
+    query: web::Query<DeleteContainerQuery>,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    let container_id = path.into_inner();
+    info!("Deleting container {} (force: {})", container_id, query.force);
+
+    let mut tx = db_pool.begin().await?;
+    let Some((zone,)) = sqlx::query_as::<_, (String,)>(r#"SELECT zone FROM containers WHERE "containerId" = ?"#)
+        .bind(&container_id)
+        .fetch_optional(&mut *tx)
+        .await?
+    else {
+        tx.rollback().await.ok();
+        return Err(ApiError::ContainerNotFound(container_id));
     };
+    let displaced: Vec<(String,)> = sqlx::query_as(r#"SELECT "itemId_fk" FROM placements WHERE "containerId_fk" = ?"#)
+        .bind(&container_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    if !displaced.is_empty() && !query.force {
+        tx.rollback().await.ok();
+        return Err(ApiError::ContainerNotEmpty { container_id, item_count: displaced.len() as i64 });
+    }
 
-    let status_code = if success { StatusCode::OK } else { StatusCode::MULTI_STATUS }; // Use 207 if partially successful
-    Ok(HttpResponse::build(status_code).json(response_data))
+    // Items stay in the inventory, they just lose their placement
+    let removed_placements = sqlx::query(r#"DELETE FROM placements WHERE "containerId_fk" = ?"#)
+        .bind(&container_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    sqlx::query(r#"DELETE FROM containers WHERE "containerId" = ?"#)
+        .bind(&container_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    for (item_id,) in displaced {
+        events.publish(Event::PlacementChanged {
+            item_id,
+            container_id: None,
+            zone: None,
+            position: None,
+            previous_container_id: Some(container_id.clone()),
+            previous_zone: Some(zone.clone()),
+        });
+    }
+    events.publish(Event::ContainerChanged { container_id, zone, deleted: true });
+
+    Ok(HttpResponse::Ok().json(DeleteResponse { success: true, removed_placements }))
+}
+
+
+// ==============================================================================
+// == API Tokens ================================================================
+// ==============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/api/tokens",
+    tag = "tokens",
+    responses(
+        (status = 200, body = TokenListResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+    )
+)]
+pub async fn list_tokens(db_pool: web::Data<SqlitePool>) -> Result<HttpResponse, ApiError> {
+    let tokens = sqlx::query_as::<_, DbApiToken>("SELECT * FROM api_tokens ORDER BY id")
+        .fetch_all(db_pool.get_ref())
+        .await?;
+    Ok(HttpResponse::Ok().json(TokenListResponse { success: true, tokens: tokens.into_iter().map(ApiToken::from).collect() }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tokens",
+    tag = "tokens",
+    request_body = CreateTokenRequest,
+    responses(
+        (status = 201, body = CreatedTokenResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+    )
+)]
+pub async fn create_token(
+    req: Validated<CreateTokenRequest>,
+    identity: auth::Identity,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Issuing {} token '{}' (requested by {})", req.role.as_str(), req.name, identity.name);
+    let token = auth::generate_token();
+    let api_token = sqlx::query_as::<_, DbApiToken>(
+        r#"INSERT INTO api_tokens (name, "tokenHash", role, "createdAt") VALUES (?, ?, ?, ?) RETURNING *"#)
+        .bind(req.name.trim())
+        .bind(auth::hash_token(&token))
+        .bind(req.role.as_str())
+        .bind(Utc::now())
+        .fetch_one(db_pool.get_ref())
+        .await?;
+    Ok(HttpResponse::Created().json(CreatedTokenResponse { success: true, token, api_token: api_token.into() }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/tokens/{id}",
+    tag = "tokens",
+    params(("id" = i64, Path, description = "Token id")),
+    responses(
+        (status = 200, body = TokenResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+    )
+)]
+pub async fn revoke_token(
+    path: web::Path<i64>,
+    identity: auth::Identity,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    info!("Revoking API token {} (requested by {})", id, identity.name);
+    // Revoking twice keeps the original revocation time
+    let api_token = sqlx::query_as::<_, DbApiToken>(
+        r#"UPDATE api_tokens SET "revokedAt" = IFNULL("revokedAt", ?) WHERE id = ? RETURNING *"#)
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_optional(db_pool.get_ref())
+        .await?
+        .ok_or(ApiError::TokenNotFound(id))?;
+    Ok(HttpResponse::Ok().json(TokenResponse { success: true, api_token: api_token.into() }))
+}
+
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Effective configuration after environment overrides; secrets are masked", body = ConfigResponse),
+        (status = 403, description = "Requires the admin role", body = ErrorResponse),
+    )
+)]
+pub async fn get_config(config: web::Data<Config>) -> HttpResponse {
+    HttpResponse::Ok().json(ConfigResponse { success: true, config: config.get_ref().clone() })
+}
+
+
+// ==============================================================================
+// == Live Events ===============================================================
+// ==============================================================================
+
+// Idle streams get a comment this often so proxies don't drop them
+const EVENT_KEEP_ALIVE: std::time::Duration = std::time::Duration::from_secs(15);
+
+#[utoipa::path(
+    get,
+    path = "/api/events",
+    tag = "events",
+    params(EventFilter),
+    responses(
+        (status = 200, description = "Server-Sent Events stream; each `data` line is one Event", content_type = "text/event-stream", body = Event),
+    )
+)]
+pub async fn stream_events(
+    query: web::Query<EventFilter>,
+    events: web::Data<EventBus>,
+) -> HttpResponse {
+    let filter = query.into_inner();
+    info!("Event stream opened (zone: {:?}, container: {:?})", filter.zone, filter.container_id);
+
+    let receiver = events.subscribe();
+    let updates = futures::stream::unfold((receiver, filter), |(mut receiver, filter)| async move {
+        loop {
+            let frame = match tokio::time::timeout(EVENT_KEEP_ALIVE, receiver.recv()).await {
+                Ok(Ok(event)) if event.matches(&filter) => event.to_sse(),
+                Ok(Ok(_)) => continue,
+                // The client fell behind and missed events; it should refetch
+                Ok(Err(RecvError::Lagged(missed))) => web::Bytes::from(format!("event: lagged\ndata: {{\"missed\":{}}}\n\n", missed)),
+                Ok(Err(RecvError::Closed)) => return None,
+                Err(_) => web::Bytes::from_static(b": keep-alive\n\n"),
+            };
+            return Some((Ok::<_, actix_web::Error>(frame), (receiver, filter)));
+        }
+    });
+    let opened = futures::stream::once(async { Ok(web::Bytes::from_static(b": connected\n\n")) });
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        // Keeps the compression middleware from buffering the stream
+        .insert_header(("Content-Encoding", "identity"))
+        .streaming(opened.chain(updates))
 }
 
 
 // --- Other Handlers (Place, Retrieve, Waste, Cargo) ---
-// These would also need to be updated to use the db_pool if they need DB access
 
+/// Bumps a container's version, but only if it still is `expected` (when given).
+/// Returns the new version.
+async fn bump_container_version(conn: &mut SqliteConnection, container_id: &str, expected: Option<i64>) -> Result<i64, ApiError> {
+    let bumped: Option<(i64,)> = sqlx::query_as(
+        r#"UPDATE containers SET version = version + 1 WHERE "containerId" = ? AND version = IFNULL(?, version) RETURNING version"#)
+        .bind(container_id)
+        .bind(expected)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match bumped {
+        Some((version,)) => Ok(version),
+        None => Err(match check_container_version(conn, container_id, expected).await {
+            Err(e) => e,
+            Ok(()) => ApiError::ContainerNotFound(container_id.to_string()),
+        }),
+    }
+}
+
+/// Fails with a conflict unless the container's version is `expected`
+/// (None meaning the container must not exist).
+async fn check_container_version(conn: &mut SqliteConnection, container_id: &str, expected: Option<i64>) -> Result<(), ApiError> {
+    let actual: Option<(i64,)> = sqlx::query_as(r#"SELECT version FROM containers WHERE "containerId" = ?"#)
+        .bind(container_id)
+        .fetch_optional(conn)
+        .await?;
+    let actual = actual.map(|(version,)| version);
+    match (expected, actual) {
+        (Some(_), None) => Err(ApiError::ContainerNotFound(container_id.to_string())),
+        (expected, actual) if expected != actual => Err(ApiError::VersionConflict { container_id: container_id.to_string(), expected, actual }),
+        _ => Ok(()),
+    }
+}
+
+// Container and zone an item is currently placed in
+async fn item_location<'e>(
+    executor: impl sqlx::Executor<'e, Database = Sqlite>,
+    item_id: &str,
+) -> Result<Option<(String, String)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"SELECT p."containerId_fk", c.zone FROM placements p JOIN containers c ON c."containerId" = p."containerId_fk"
+           WHERE p."itemId_fk" = ?"#)
+        .bind(item_id)
+        .fetch_optional(executor)
+        .await
+}
+
+fn container_differs(stored: &DbContainer, container: &Container) -> bool {
+    stored.zone != container.zone
+        || stored.width != container.width
+        || stored.depth != container.depth
+        || stored.height != container.height
+        || stored.is_waste_container != container.is_waste_container
+        || stored.max_weight_capacity != container.max_weight_capacity
+}
+
+async fn write_log(
+    conn: &mut SqliteConnection,
+    identity: &auth::Identity,
+    action: ActionType,
+    item_id: &str,
+    container_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"INSERT INTO logs (timestamp, "userId", "actionType", "itemId", "containerId") VALUES (?, ?, ?, ?, ?)"#)
+        .bind(Utc::now())
+        .bind(&identity.name)
+        .bind(action.as_str())
+        .bind(item_id)
+        .bind(container_id)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+fn db_position(p_db: &DbPlacement) -> Position {
+    Position {
+        start_coordinates: Coordinates { width: p_db.start_w, depth: p_db.start_d, height: p_db.start_h },
+        end_coordinates: Coordinates { width: p_db.end_w, depth: p_db.end_d, height: p_db.end_h },
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/place",
+    tag = "placement",
+    request_body = PlaceRequest,
+    responses(
+        (status = 200, body = PlaceResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+        (status = 404, description = "Item or container not found", body = ErrorResponse),
+        (status = 409, description = "Position overlaps another item", body = ErrorResponse),
+        (status = 422, description = "Container weight capacity exceeded", body = ErrorResponse),
+    )
+)]
 pub async fn place_item(
-    _req: web::Json<PlaceRequest>,
-    _db_pool: web::Data<SqlitePool>, // Add pool if needed
-    // _placement_service: web::Data<PlacementService>, // Remove if not used
-) -> Result<HttpResponse> {
-    info!("Recording placement for item {}", _req.item_id);
-    // TODO: Implement database update logic for manual placement recording
-    Ok(HttpResponse::NotImplemented().json(PlaceResponse {
-        success: false,
-        error: Some("Manual placement recording not implemented.".to_string()),
-    }))
+    req: Validated<PlaceRequest>,
+    identity: auth::Identity,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Recording placement of item {} in {} by {}", req.item_id, req.container_id, identity.name);
+
+    let mut tx = db_pool.begin().await?;
+
+    let item = sqlx::query_as::<_, DbItem>(r#"SELECT * FROM items WHERE "itemId" = ?"#)
+        .bind(&req.item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::ItemNotFound(req.item_id.clone()))?;
+    let container = sqlx::query_as::<_, DbContainer>(r#"SELECT * FROM containers WHERE "containerId" = ?"#)
+        .bind(&req.container_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::ContainerNotFound(req.container_id.clone()))?;
+
+    // Position must lie inside the container and match the item in some orientation
+    let (start, end) = (&req.position.start_coordinates, &req.position.end_coordinates);
+    let tol = config::placement().tolerance;
+    let mut violations = Vec::new();
+    for (axis, s, e, limit) in [
+        ("width", start.width, end.width, container.width),
+        ("depth", start.depth, end.depth, container.depth),
+        ("height", start.height, end.height, container.height),
+    ] {
+        if s < -tol {
+            violations.push(FieldError::new(format!("position.startCoordinates.{}", axis), "must not be negative"));
+        }
+        if e > limit + tol {
+            violations.push(FieldError::new(format!("position.endCoordinates.{}", axis), format!("exceeds container {} of {}", axis, limit)));
+        }
+    }
+    let mut placed_dims = [end.width - start.width, end.depth - start.depth, end.height - start.height];
+    let mut item_dims = [item.width, item.depth, item.height];
+    placed_dims.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    item_dims.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    if placed_dims.iter().zip(item_dims.iter()).any(|(p, i)| (p - i).abs() > tol) {
+        violations.push(FieldError::new("position", format!(
+            "does not match the dimensions of item '{}' ({} x {} x {}) in any orientation", item.item_id, item.width, item.depth, item.height)));
+    }
+    if !violations.is_empty() {
+        return Err(ApiError::Validation(violations));
+    }
+
+    let others = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements WHERE "containerId_fk" = ? AND "itemId_fk" != ?"#)
+        .bind(&req.container_id)
+        .bind(&req.item_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    if let Some(other) = others.iter().find(|p_db| {
+        let pos = db_position(p_db);
+        boxes_overlap(start, end, &pos.start_coordinates, &pos.end_coordinates)
+    }) {
+        return Err(ApiError::PositionOverlap { container_id: req.container_id.clone(), other_item_id: other.item_id_fk.clone() });
+    }
+
+    if let Some(capacity) = container.max_weight_capacity {
+        let (held_mass,): (Option<f64>,) = sqlx::query_as(
+            r#"SELECT SUM(i.mass) FROM placements p JOIN items i ON i."itemId" = p."itemId_fk"
+               WHERE p."containerId_fk" = ? AND p."itemId_fk" != ?"#)
+            .bind(&req.container_id)
+            .bind(&req.item_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let total_mass = held_mass.unwrap_or(0.0) + item.mass.unwrap_or(0.0);
+        if total_mass > capacity + tol {
+            return Err(ApiError::ContainerOverweight { container_id: req.container_id.clone(), total_mass, capacity });
+        }
+    }
+
+    // Checks that nothing changed since the container was read above (or by the client)
+    let container_version = bump_container_version(&mut tx, &req.container_id, Some(req.expected_version.unwrap_or(container.version))).await?;
+    let previous = item_location(&mut *tx, &req.item_id).await?;
+    if let Some((previous_container_id, _)) = previous.as_ref().filter(|(c_id, _)| *c_id != req.container_id) {
+        bump_container_version(&mut tx, previous_container_id, None).await?;
+    }
+    sqlx::query(r#"DELETE FROM placements WHERE "itemId_fk" = ?"#)
+        .bind(&req.item_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"INSERT INTO placements ("itemId_fk", "containerId_fk", start_w, start_d, start_h, end_w, end_d, end_h)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#)
+        .bind(&req.item_id)
+        .bind(&req.container_id)
+        .bind(start.width)
+        .bind(start.depth)
+        .bind(start.height)
+        .bind(end.width)
+        .bind(end.depth)
+        .bind(end.height)
+        .execute(&mut *tx)
+        .await?;
+    write_log(&mut tx, &identity, ActionType::Placement, &req.item_id, Some(&req.container_id)).await?;
+    tx.commit().await?;
+
+    let (previous_container_id, previous_zone) = previous.unzip();
+    events.publish(Event::PlacementChanged {
+        item_id: req.item_id.clone(),
+        container_id: Some(req.container_id.clone()),
+        zone: Some(container.zone),
+        position: Some(req.position.clone()),
+        previous_container_id,
+        previous_zone,
+    });
+
+    Ok(HttpResponse::Ok().json(PlaceResponse { success: true, container_version: Some(container_version), error: None }))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/retrieve",
+    tag = "retrieval",
+    request_body = RetrievalRequest,
+    responses(
+        (status = 200, body = RetrievalResponse),
+        (status = 404, description = "Item not found or not placed", body = ErrorResponse),
+        (status = 409, description = "Item is waste or disposed", body = ErrorResponse),
+    )
+)]
 pub async fn retrieve_item(
-    _req: web::Json<RetrievalRequest>,
-     _db_pool: web::Data<SqlitePool>, // Add pool if needed
-    // _placement_service: web::Data<PlacementService>, // Remove if not used
-) -> Result<HttpResponse> {
-    info!("Processing retrieval request for item {}", _req.item_id);
-     // TODO: Implement database logic to find item, calculate steps, update status/uses
-    Ok(HttpResponse::NotImplemented().json(RetrievalResponse {
-        success: false, item: None, container_id: None, position: None,
-        steps_required: 0, items_to_move: vec![],
-        error: Some("Item retrieval not implemented.".to_string()),
+    req: Validated<RetrievalRequest>,
+    identity: auth::Identity,
+    db_pool: web::Data<SqlitePool>,
+    events: web::Data<EventBus>,
+) -> Result<HttpResponse, ApiError> {
+    info!("Processing retrieval request for item {} by {}", req.item_id, identity.name);
+
+    let mut tx = db_pool.begin().await?;
+
+    let mut item = sqlx::query_as::<_, DbItem>(r#"SELECT * FROM items WHERE "itemId" = ?"#)
+        .bind(&req.item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::ItemNotFound(req.item_id.clone()))?;
+    // Waste and disposed items are not handed out, and their use count stays put
+    if item.status != "ACTIVE" {
+        return Err(ApiError::ItemNotUsable { item_id: req.item_id.clone(), status: item.status });
+    }
+    let placement = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements WHERE "itemId_fk" = ?"#)
+        .bind(&req.item_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| ApiError::ItemNotPlaced(req.item_id.clone()))?;
+
+    // Items between the open face and the target have to come out first
+    let target = db_position(&placement);
+    let neighbours = sqlx::query_as::<_, DbPlacement>(r#"SELECT * FROM placements WHERE "containerId_fk" = ? AND "itemId_fk" != ?"#)
+        .bind(&placement.container_id_fk)
+        .bind(&req.item_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    let mut blocking: Vec<&DbPlacement> = neighbours.iter().filter(|n| obstructs(&db_position(n), &target)).collect();
+    blocking.sort_by(|a, b| a.start_d.partial_cmp(&b.start_d).unwrap_or(std::cmp::Ordering::Equal));
+    let items_to_move: Vec<String> = blocking.into_iter().map(|n| n.item_id_fk.clone()).collect();
+
+    item.current_uses += 1;
+    let depleted = item.usage_limit.is_some_and(|limit| item.current_uses >= limit);
+    if depleted {
+        item.status = "WASTE_DEPLETED".to_string();
+    }
+    sqlx::query(r#"UPDATE items SET "currentUses" = ?, status = ? WHERE "itemId" = ?"#)
+        .bind(item.current_uses)
+        .bind(&item.status)
+        .bind(&req.item_id)
+        .execute(&mut *tx)
+        .await?;
+    write_log(&mut tx, &identity, ActionType::Retrieval, &req.item_id, Some(&placement.container_id_fk)).await?;
+    let location = item_location(&mut *tx, &req.item_id).await?;
+    tx.commit().await?;
+
+    let (container_id, zone) = location.unzip();
+    events.publish(Event::ItemUsed {
+        item_id: req.item_id.clone(),
+        container_id: container_id.clone(),
+        zone: zone.clone(),
+        current_uses: item.current_uses,
+        usage_limit: item.usage_limit,
+    });
+    if depleted {
+        events.publish(Event::ItemBecameWaste { item_id: req.item_id.clone(), container_id, zone, status: item.status.clone() });
+    }
+
+    Ok(HttpResponse::Ok().json(RetrievalResponse {
+        success: true,
+        container_id: Some(placement.container_id_fk.clone()),
+        position: Some(target),
+        steps_required: items_to_move.len() as i32,
+        items_to_move,
+        item: Some(item.into()),
+        error: None,
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/waste-management",
+    tag = "waste",
+    responses(
+        (status = 501, description = "Not implemented yet", body = ErrorResponse),
+    )
+)]
 pub async fn get_waste_management(
-     _db_pool: web::Data<SqlitePool>, // Add pool if needed
-    // _placement_service: web::Data<PlacementService>, // Remove if not used
-) -> Result<HttpResponse> {
+    _db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
     info!("Processing waste management request");
-    // TODO: Implement database logic to find expired/depleted items
-    Ok(HttpResponse::NotImplemented().json(WasteManagementResponse {
-        expired_items: vec![], fully_used_items: vec![],
-        suggested_waste_container: "N/A".to_string(), total_waste_mass: 0.0,
-        //error: Some("Waste management not implemented.".to_string()),
-    }))
+    Err(ApiError::NotImplemented("Waste management".to_string()))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/cargo-return",
+    tag = "waste",
+    responses(
+        (status = 501, description = "Not implemented yet", body = ErrorResponse),
+    )
+)]
 pub async fn get_cargo_return_plan(
-     _db_pool: web::Data<SqlitePool>, // Add pool if needed
-    // _placement_service: web::Data<PlacementService>, // Remove if not used
-) -> Result<HttpResponse> {
+    _db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
     info!("Processing cargo return plan request");
-     // TODO: Implement database logic for cargo return plan
-    Ok(HttpResponse::NotImplemented().json(json!({
-        "success": false,
-        "error": "Cargo return plan not implemented.",
-        "wasteItems": [], "loadingSequence": [], "totalReturnMass": 0.0, "spaceReclaimed": 0.0
-    })))
-}
-
-// Keep helper if needed elsewhere, otherwise remove
-// fn validate_coordinates(coords: &Coordinates, container: &Container) -> bool {
-//     coords.width >= 0.0 && coords.width <= container.width &&
-//     coords.depth >= 0.0 && coords.depth <= container.depth &&
-//     coords.height >= 0.0 && coords.height <= container.height
-// }
-
-// Add a helper to create error responses consistently
-impl PlacementResponse {
-    fn error(msg: String) -> Self {
-        PlacementResponse {
-            success: false,
-            placements: vec![],
-            rearrangements: vec![],
-            error: Some(msg),
-        }
+    Err(ApiError::NotImplemented("Cargo return plan".to_string()))
+}
+
+fn push_waste_filters<'a>(qb: &mut QueryBuilder<'a, Sqlite>, query: &'a WasteListQuery) {
+    match &query.status {
+        Some(status) => qb.push(" WHERE i.status = ").push_bind(status.as_str()),
+        None => qb.push(" WHERE i.status IN ('WASTE_EXPIRED', 'WASTE_DEPLETED')"),
+    };
+    if let Some(zone) = &query.zone {
+        qb.push(r#" AND i."itemId" IN (SELECT p."itemId_fk" FROM placements p JOIN containers c ON c."containerId" = p."containerId_fk" WHERE c.zone = "#)
+            .push_bind(zone)
+            .push(")");
     }
-} 
\ No newline at end of file
+    if let Some(container_id) = &query.container_id {
+        qb.push(r#" AND i."itemId" IN (SELECT "itemId_fk" FROM placements WHERE "containerId_fk" = "#)
+            .push_bind(container_id)
+            .push(")");
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/waste",
+    tag = "waste",
+    params(WasteListQuery),
+    responses(
+        (status = 200, description = "One page of expired and depleted items", body = ItemListResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn list_waste(
+    query: ValidatedQuery<WasteListQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    // Waste pages sort and resume exactly like the item list they are a subset of
+    let sort = listing::parse_sort(query.sort.as_deref(), ITEM_SORT_COLUMNS)?;
+    let cursor = listing::parse_cursor(query.cursor.as_deref())?;
+    if let Some(cursor) = cursor {
+        listing::check_cursor(&db_pool, "items", cursor).await?;
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM items i");
+    push_waste_filters(&mut count, &query);
+    let (total,): (i64,) = count.build_query_as().fetch_one(db_pool.get_ref()).await?;
+
+    let mut page = QueryBuilder::new("SELECT i.* FROM items i");
+    push_waste_filters(&mut page, &query);
+    if let Some(cursor) = cursor {
+        listing::push_after_cursor(&mut page, &sort, "items", "i", cursor);
+    }
+    listing::push_order(&mut page, &sort, "i", query.limit);
+    let mut items: Vec<DbItem> = page.build_query_as().fetch_all(db_pool.get_ref()).await?;
+    let next_cursor = listing::next_cursor(&mut items, query.limit, |item| item.id);
+
+    let records = item_records(&db_pool, items).await?;
+    Ok(HttpResponse::Ok().json(ItemListResponse { success: true, items: records, total, next_cursor }))
+}
+
+
+// ==============================================================================
+// == Action Logs ===============================================================
+// ==============================================================================
+
+const LOG_SORT_COLUMNS: &[SortColumn] = &[
+    SortColumn { name: "timestamp", expr: "l.timestamp" },
+    SortColumn { name: "userId", expr: r#"l."userId""# },
+    SortColumn { name: "actionType", expr: r#"l."actionType""# },
+    SortColumn { name: "itemId", expr: r#"l."itemId""# },
+    SortColumn { name: "containerId", expr: r#"IFNULL(l."containerId", '')"# },
+];
+
+fn push_log_filters<'a>(qb: &mut QueryBuilder<'a, Sqlite>, query: &'a LogListQuery) {
+    qb.push(" WHERE 1 = 1");
+    if let Some(item_id) = &query.item_id {
+        qb.push(r#" AND l."itemId" = "#).push_bind(item_id);
+    }
+    if let Some(user_id) = &query.user_id {
+        qb.push(r#" AND l."userId" = "#).push_bind(user_id);
+    }
+    if let Some(action) = query.action_type {
+        qb.push(r#" AND l."actionType" = "#).push_bind(action.as_str());
+    }
+    if let Some(container_id) = &query.container_id {
+        qb.push(r#" AND l."containerId" = "#).push_bind(container_id);
+    }
+    if let Some(start) = query.start_date {
+        qb.push(" AND l.timestamp >= ").push_bind(start);
+    }
+    if let Some(end) = query.end_date {
+        qb.push(" AND l.timestamp <= ").push_bind(end);
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/logs",
+    tag = "logs",
+    params(LogListQuery),
+    responses(
+        (status = 200, description = "One page of log entries", body = LogListResponse),
+        (status = 400, description = "Validation failed", body = ErrorResponse),
+    )
+)]
+pub async fn list_logs(
+    query: ValidatedQuery<LogListQuery>,
+    db_pool: web::Data<SqlitePool>,
+) -> Result<HttpResponse, ApiError> {
+    let sort = listing::parse_sort(query.sort.as_deref(), LOG_SORT_COLUMNS)?;
+    let cursor = listing::parse_cursor(query.cursor.as_deref())?;
+    if let Some(cursor) = cursor {
+        listing::check_cursor(&db_pool, "logs", cursor).await?;
+    }
+
+    let mut count = QueryBuilder::new("SELECT COUNT(*) FROM logs l");
+    push_log_filters(&mut count, &query);
+    let (total,): (i64,) = count.build_query_as().fetch_one(db_pool.get_ref()).await?;
+
+    let mut page = QueryBuilder::new("SELECT l.* FROM logs l");
+    push_log_filters(&mut page, &query);
+    if let Some(cursor) = cursor {
+        listing::push_after_cursor(&mut page, &sort, "logs", "l", cursor);
+    }
+    listing::push_order(&mut page, &sort, "l", query.limit);
+    let mut entries: Vec<DbLogEntry> = page.build_query_as().fetch_all(db_pool.get_ref()).await?;
+    let next_cursor = listing::next_cursor(&mut entries, query.limit, |entry| entry.id);
+
+    let logs = entries.into_iter().map(LogEntry::from).collect();
+    Ok(HttpResponse::Ok().json(LogListResponse { success: true, logs, total, next_cursor }))
+}
+
+
+// ==============================================================================
+// == Health & Metrics ==========================================================
+// ==============================================================================
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "The process is up", body = HealthResponse))
+)]
+pub async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(HealthResponse { success: true, status: "ok".to_string(), checks: Vec::new() })
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Database reachable and fully migrated", body = HealthResponse),
+        (status = 503, description = "Not ready to serve requests", body = HealthResponse),
+    )
+)]
+pub async fn readyz(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let database = match sqlx::query("SELECT 1").execute(db_pool.get_ref()).await {
+        Ok(_) => HealthCheck { name: "database".to_string(), ok: true, message: None },
+        Err(e) => HealthCheck { name: "database".to_string(), ok: false, message: Some(e.to_string()) },
+    };
+
+    // Every migration compiled into the binary must have been applied successfully
+    let applied: Result<Vec<(i64,)>, sqlx::Error> = sqlx::query_as("SELECT version FROM _sqlx_migrations WHERE success = 1")
+        .fetch_all(db_pool.get_ref())
+        .await;
+    let migrations = match applied {
+        Ok(applied) => {
+            let applied: HashSet<i64> = applied.into_iter().map(|(v,)| v).collect();
+            let pending: Vec<String> = sqlx::migrate!("./migrations").iter()
+                .filter(|m| !applied.contains(&m.version))
+                .map(|m| format!("{} {}", m.version, m.description))
+                .collect();
+            if pending.is_empty() {
+                HealthCheck { name: "migrations".to_string(), ok: true, message: None }
+            } else {
+                HealthCheck { name: "migrations".to_string(), ok: false, message: Some(format!("pending: {}", pending.join(", "))) }
+            }
+        }
+        Err(e) => HealthCheck { name: "migrations".to_string(), ok: false, message: Some(e.to_string()) },
+    };
+
+    let ready = database.ok && migrations.ok;
+    let status = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    HttpResponse::build(status).json(HealthResponse {
+        success: ready,
+        status: if ready { "ready" } else { "not ready" }.to_string(),
+        checks: vec![database, migrations],
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "Prometheus text exposition format", content_type = "text/plain", body = String))
+)]
+pub async fn prometheus_metrics(metrics: web::Data<Metrics>, db_pool: web::Data<SqlitePool>) -> Result<HttpResponse, ApiError> {
+    let body = metrics.render(&db_pool).await?;
+    Ok(HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(body))
+}
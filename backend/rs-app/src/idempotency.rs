@@ -0,0 +1,192 @@
+use crate::auth::Identity;
+use crate::errors::ApiError;
+use actix_web::body::{to_bytes, BoxBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, HttpMessage, HttpResponse, ResponseError};
+use chrono::{Duration, Utc};
+use tracing::{debug, warn};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+
+pub const IDEMPOTENCY_KEY: &str = "Idempotency-Key";
+const REPLAYED: &str = "Idempotent-Replayed";
+const MAX_KEY_LEN: usize = 255;
+// A first attempt still unanswered after this long is assumed to have died with the server
+const PENDING_TIMEOUT_SECS: i64 = 300;
+
+/// How long stored responses are replayed for.
+#[derive(Clone)]
+pub struct IdempotencyConfig {
+    pub ttl: Duration,
+}
+
+/// Middleware for mutating requests that carry an `Idempotency-Key` header: the
+/// first request runs normally and its response is stored, retries with the same
+/// key get the stored response back without the handler running again.
+pub async fn idempotency(mut req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    let mutating = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH | Method::DELETE);
+    let key = req.headers().get(IDEMPOTENCY_KEY).map(|v| v.to_str().map(str::to_string));
+    let (true, Some(key)) = (mutating, key) else {
+        return next.call(req).await;
+    };
+    let key = match key {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => key,
+        _ => {
+            let err = ApiError::validation(IDEMPOTENCY_KEY, format!("must be 1 to {} visible ASCII characters", MAX_KEY_LEN));
+            return Ok(req.into_response(err.error_response()));
+        }
+    };
+    let (Some(db_pool), Some(config)) = (
+        req.app_data::<web::Data<SqlitePool>>().cloned(),
+        req.app_data::<web::Data<IdempotencyConfig>>().cloned(),
+    ) else {
+        warn!("Idempotency middleware is missing its database pool or config; handling request normally");
+        return next.call(req).await;
+    };
+
+    // Keys are scoped by caller, so another caller reusing one never sees this response
+    let owner = req.extensions().get::<Identity>().map(|identity| identity.name.clone()).unwrap_or_default();
+    let key = ScopedKey { owner, key };
+
+    // The body is part of the fingerprint, so read it and hand it back to the handler
+    let body = req.extract::<web::Bytes>().await?;
+    let fingerprint = fingerprint(&req, &body);
+    req.set_payload(Payload::from(body));
+
+    match claim(&db_pool, &config, &key, &fingerprint).await {
+        Ok(Claim::Owned) => {}
+        Ok(Claim::Replay { status, content_type, body }) => {
+            debug!("Replaying stored response for idempotency key {}", key.key);
+            let mut res = HttpResponse::build(StatusCode::from_u16(status).unwrap_or(StatusCode::OK));
+            if let Some(content_type) = content_type {
+                res.insert_header((header::CONTENT_TYPE, content_type));
+            }
+            res.insert_header((REPLAYED, "true"));
+            return Ok(req.into_response(res.body(body)));
+        }
+        Ok(Claim::Rejected(err)) => return Ok(req.into_response(err.error_response())),
+        Err(e) => return Ok(req.into_response(ApiError::from(e).error_response())),
+    }
+
+    let res = next.call(req).await?;
+    let (req, res) = res.into_parts();
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body = match to_bytes(res.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            release(&db_pool, &key).await;
+            return Err(actix_web::error::ErrorInternalServerError(e.to_string()));
+        }
+    };
+
+    // Only successes are stored: a rejected request (a stale version, a conflict, a
+    // server error) must be able to run again once the client has fixed the cause
+    if !status.is_success() {
+        release(&db_pool, &key).await;
+    } else if let Err(e) = store(&db_pool, &key, status, headers.get(header::CONTENT_TYPE), &body).await {
+        warn!("Failed to store response for idempotency key {}: {}", key.key, e);
+        release(&db_pool, &key).await;
+    }
+
+    let mut rebuilt = HttpResponse::build(status);
+    for (name, value) in headers.iter() {
+        rebuilt.append_header((name.clone(), value.clone()));
+    }
+    Ok(ServiceResponse::new(req, rebuilt.body(body)))
+}
+
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b" ");
+    hasher.update(req.uri().to_string());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredResponse {
+    fingerprint: String,
+    status: Option<i64>,
+    #[sqlx(rename = "contentType")]
+    content_type: Option<String>,
+    body: Option<Vec<u8>>,
+}
+
+/// A client-chosen key together with the caller that sent it.
+struct ScopedKey {
+    owner: String,
+    key: String,
+}
+
+enum Claim {
+    Owned,
+    Replay { status: u16, content_type: Option<String>, body: Vec<u8> },
+    Rejected(ApiError),
+}
+
+async fn claim(db_pool: &SqlitePool, config: &IdempotencyConfig, key: &ScopedKey, fingerprint: &str) -> Result<Claim, sqlx::Error> {
+    let now = Utc::now();
+    sqlx::query(r#"DELETE FROM idempotency_keys WHERE "createdAt" < ? OR (status IS NULL AND "createdAt" < ?)"#)
+        .bind(now - config.ttl)
+        .bind(now - Duration::seconds(PENDING_TIMEOUT_SECS))
+        .execute(db_pool)
+        .await?;
+
+    let inserted = sqlx::query(r#"INSERT INTO idempotency_keys (owner, "key", fingerprint, "createdAt") VALUES (?, ?, ?, ?) ON CONFLICT(owner, "key") DO NOTHING"#)
+        .bind(&key.owner)
+        .bind(&key.key)
+        .bind(fingerprint)
+        .bind(now)
+        .execute(db_pool)
+        .await?
+        .rows_affected();
+    if inserted == 1 {
+        return Ok(Claim::Owned);
+    }
+
+    let stored: Option<StoredResponse> = sqlx::query_as(
+        r#"SELECT fingerprint, status, "contentType", body FROM idempotency_keys WHERE owner = ? AND "key" = ?"#)
+        .bind(&key.owner)
+        .bind(&key.key)
+        .fetch_optional(db_pool)
+        .await?;
+    Ok(match stored {
+        // Expired between the insert and the select; the retry is safe to run
+        None => Claim::Owned,
+        Some(stored) if stored.fingerprint != fingerprint => Claim::Rejected(ApiError::IdempotencyKeyMismatch(key.key.clone())),
+        Some(StoredResponse { status: None, .. }) => Claim::Rejected(ApiError::IdempotencyKeyInProgress(key.key.clone())),
+        Some(StoredResponse { status: Some(status), content_type, body, .. }) => Claim::Replay {
+            status: status as u16,
+            content_type,
+            body: body.unwrap_or_default(),
+        },
+    })
+}
+
+async fn store(db_pool: &SqlitePool, key: &ScopedKey, status: StatusCode, content_type: Option<&header::HeaderValue>, body: &[u8]) -> Result<(), sqlx::Error> {
+    sqlx::query(r#"UPDATE idempotency_keys SET status = ?, "contentType" = ?, body = ? WHERE owner = ? AND "key" = ?"#)
+        .bind(status.as_u16() as i64)
+        .bind(content_type.and_then(|v| v.to_str().ok()))
+        .bind(body)
+        .bind(&key.owner)
+        .bind(&key.key)
+        .execute(db_pool)
+        .await?;
+    Ok(())
+}
+
+async fn release(db_pool: &SqlitePool, key: &ScopedKey) {
+    let deleted = sqlx::query(r#"DELETE FROM idempotency_keys WHERE owner = ? AND "key" = ?"#)
+        .bind(&key.owner)
+        .bind(&key.key)
+        .execute(db_pool)
+        .await;
+    if let Err(e) = deleted {
+        warn!("Failed to release idempotency key {}: {}", key.key, e);
+    }
+}
@@ -0,0 +1,155 @@
+use crate::config;
+use crate::models::{Container, Item, ItemStatus, Position};
+use crate::simulation::{boxes_overlap, is_supported, ContainerState};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+/// A way in which an arrangement is physically impossible, or disagrees with the item records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Violation {
+    Overlap {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+        #[serde(rename = "otherItemId")]
+        other_item_id: String,
+    },
+    OutOfBounds {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+    // Neither on the floor nor resting on another item
+    Unsupported {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+    Overweight {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        mass: f64,
+        capacity: f64,
+    },
+    UnknownContainer {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+    // Placement of an item that has no record
+    UnknownItem {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+    // Disposed items have left the station and should no longer occupy space
+    DisposedItemPlaced {
+        #[serde(rename = "containerId")]
+        container_id: String,
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+    ActiveItemUnplaced {
+        #[serde(rename = "itemId")]
+        item_id: String,
+    },
+}
+
+fn out_of_bounds(position: &Position, container: &Container) -> bool {
+    let tol = config::placement().tolerance;
+    let (s, e) = (&position.start_coordinates, &position.end_coordinates);
+    s.width < -tol || s.depth < -tol || s.height < -tol ||
+    e.width > container.width + tol || e.depth > container.depth + tol || e.height > container.height + tol
+}
+
+/// Checks the geometry and load of an arrangement. Items without a known mass
+/// count as weightless. Each overlapping pair is reported once.
+pub fn check_arrangement(containers: &[Container], state: &ContainerState, masses: &HashMap<String, f64>) -> Vec<Violation> {
+    let by_id: HashMap<&String, &Container> = containers.iter().map(|c| (&c.container_id, c)).collect();
+    let mut container_ids: Vec<&String> = state.keys().collect();
+    container_ids.sort();
+
+    let mut violations = Vec::new();
+    for container_id in container_ids {
+        let placed = &state[container_id];
+        let Some(container) = by_id.get(container_id) else {
+            violations.extend(placed.iter().map(|(item_id, _)| Violation::UnknownContainer {
+                container_id: container_id.clone(),
+                item_id: item_id.clone(),
+            }));
+            continue;
+        };
+
+        for (i, (item_id, position)) in placed.iter().enumerate() {
+            if out_of_bounds(position, container) {
+                violations.push(Violation::OutOfBounds { container_id: container_id.clone(), item_id: item_id.clone() });
+            }
+            if !is_supported(position, placed) {
+                violations.push(Violation::Unsupported { container_id: container_id.clone(), item_id: item_id.clone() });
+            }
+            for (other_id, other) in &placed[i + 1..] {
+                if boxes_overlap(&position.start_coordinates, &position.end_coordinates, &other.start_coordinates, &other.end_coordinates) {
+                    violations.push(Violation::Overlap {
+                        container_id: container_id.clone(),
+                        item_id: item_id.clone(),
+                        other_item_id: other_id.clone(),
+                    });
+                }
+            }
+        }
+
+        if let Some(capacity) = container.max_weight_capacity {
+            let mass: f64 = placed.iter().filter_map(|(id, _)| masses.get(id)).sum();
+            if mass > capacity + config::placement().tolerance {
+                violations.push(Violation::Overweight { container_id: container_id.clone(), mass, capacity });
+            }
+        }
+    }
+    violations
+}
+
+/// Checks the placements against the item records: every placed item must exist
+/// and not be disposed, and every active item must be placed somewhere.
+pub fn check_records(items: &[Item], state: &ContainerState) -> Vec<Violation> {
+    let by_id: HashMap<&String, &Item> = items.iter().map(|i| (&i.item_id, i)).collect();
+    let mut container_ids: Vec<&String> = state.keys().collect();
+    container_ids.sort();
+
+    let mut violations = Vec::new();
+    let mut placed: HashSet<&String> = HashSet::new();
+    for container_id in container_ids {
+        for (item_id, _) in &state[container_id] {
+            placed.insert(item_id);
+            match by_id.get(item_id) {
+                None => violations.push(Violation::UnknownItem { container_id: container_id.clone(), item_id: item_id.clone() }),
+                Some(item) if item.status == Some(ItemStatus::DISPOSED) => {
+                    violations.push(Violation::DisposedItemPlaced { container_id: container_id.clone(), item_id: item_id.clone() });
+                }
+                Some(_) => {}
+            }
+        }
+    }
+
+    let mut unplaced: Vec<&String> = items.iter()
+        .filter(|i| i.status == Some(ItemStatus::ACTIVE) && !placed.contains(&i.item_id))
+        .map(|i| &i.item_id)
+        .collect();
+    unplaced.sort();
+    violations.extend(unplaced.into_iter().map(|item_id| Violation::ActiveItemUnplaced { item_id: item_id.clone() }));
+    violations
+}
+
+/// Every check on a stored arrangement: geometry and load, then the item records.
+pub fn check(containers: &[Container], items: &[Item], state: &ContainerState) -> Vec<Violation> {
+    let masses: HashMap<String, f64> = items.iter().filter_map(|i| i.mass.map(|m| (i.item_id.clone(), m))).collect();
+    let mut violations = check_arrangement(containers, state, &masses);
+    violations.extend(check_records(items, state));
+    violations
+}
@@ -0,0 +1,27 @@
+//! Cargo placement engine and API for the ISS. The `placement_service` binary
+//! serves the HTTP API; `placement-cli` plans offline from dataset files.
+
+pub mod auth;
+pub mod compaction;
+pub mod config;
+pub mod dataset;
+pub mod db_models;
+pub mod errors;
+pub mod events;
+pub mod free_space;
+pub mod generator;
+pub mod handlers;
+pub mod idempotency;
+pub mod integrity;
+pub mod listing;
+pub mod metrics;
+pub mod models;
+pub mod openapi;
+pub mod placement_service;
+pub mod planner;
+pub mod rearrangement;
+pub mod report;
+pub mod simulation;
+pub mod strategy;
+pub mod suggestion;
+pub mod validation;
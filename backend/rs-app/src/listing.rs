@@ -0,0 +1,81 @@
+use crate::errors::ApiError;
+use sqlx::{QueryBuilder, Sqlite};
+
+pub const MAX_PAGE_SIZE: i64 = 500;
+
+pub fn default_page_size() -> i64 {
+    50
+}
+
+/// A sortable column: its API name and the SQL expression it orders by.
+/// Expressions must never be NULL (row-value comparisons with NULL match
+/// nothing), so nullable columns wrap themselves in `IFNULL`.
+pub struct SortColumn {
+    pub name: &'static str,
+    pub expr: &'static str,
+}
+
+pub struct Sort {
+    pub expr: &'static str,
+    pub descending: bool,
+}
+
+/// Parses `sort=column` / `sort=-column` against the columns a list supports.
+pub fn parse_sort(sort: Option<&str>, columns: &[SortColumn]) -> Result<Sort, ApiError> {
+    let raw = sort.unwrap_or(columns[0].name);
+    let (name, descending) = match raw.strip_prefix('-') {
+        Some(name) => (name, true),
+        None => (raw, false),
+    };
+    columns.iter()
+        .find(|c| c.name == name)
+        .map(|c| Sort { expr: c.expr, descending })
+        .ok_or_else(|| {
+            let names: Vec<&str> = columns.iter().map(|c| c.name).collect();
+            ApiError::validation("sort", format!("unknown sort column '{}'; expected one of {}", name, names.join(", ")))
+        })
+}
+
+/// Cursors are the opaque row id of the last entry of the previous page.
+pub fn parse_cursor(cursor: Option<&str>) -> Result<Option<i64>, ApiError> {
+    cursor
+        .map(|c| c.parse::<i64>().map_err(|_| ApiError::validation("cursor", "is not a cursor returned by this endpoint")))
+        .transpose()
+}
+
+/// Appends the keyset condition that resumes after the row with id `cursor`.
+/// `table` and `alias` must match the outer query so the sort expression
+/// resolves the same way in the subquery. Ties are broken by id.
+pub fn push_after_cursor(qb: &mut QueryBuilder<'_, Sqlite>, sort: &Sort, table: &str, alias: &str, cursor: i64) {
+    let op = if sort.descending { "<" } else { ">" };
+    qb.push(format!(
+        " AND ({expr}, {alias}.id) {op} (SELECT {expr}, {alias}.id FROM {table} {alias} WHERE {alias}.id = ",
+        expr = sort.expr, alias = alias, op = op, table = table,
+    ));
+    qb.push_bind(cursor);
+    qb.push(")");
+}
+
+pub fn push_order(qb: &mut QueryBuilder<'_, Sqlite>, sort: &Sort, alias: &str, limit: i64) {
+    let dir = if sort.descending { "DESC" } else { "ASC" };
+    qb.push(format!(" ORDER BY {} {dir}, {}.id {dir} LIMIT ", sort.expr, alias, dir = dir));
+    // One extra row tells us whether another page follows
+    qb.push_bind(limit + 1);
+}
+
+/// Trims the look-ahead row off `rows` and returns the cursor for the next page.
+pub fn next_cursor<T>(rows: &mut Vec<T>, limit: i64, id: impl Fn(&T) -> i64) -> Option<String> {
+    if rows.len() as i64 <= limit { return None; }
+    rows.truncate(limit as usize);
+    rows.last().map(|row| id(row).to_string())
+}
+
+/// A cursor whose row has since been deleted can't be resumed from; say so
+/// instead of silently returning an empty page.
+pub async fn check_cursor(db_pool: &sqlx::SqlitePool, table: &str, cursor: i64) -> Result<(), ApiError> {
+    let (exists,): (bool,) = sqlx::query_as(&format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?)", table))
+        .bind(cursor)
+        .fetch_one(db_pool)
+        .await?;
+    if exists { Ok(()) } else { Err(ApiError::validation("cursor", "refers to an entry that no longer exists; restart from the first page")) }
+}
@@ -1,30 +1,32 @@
-mod models;
-mod handlers;
-mod placement_service;
-mod db_models;
-
-use actix_web::{web, App, HttpServer, middleware};
+use actix_web::{web, App, HttpServer, middleware::{self, from_fn}};
 use actix_cors::Cors;
-use placement_service::PlacementService;
-use env_logger::Env;
-use sqlx::{SqlitePool, migrate::MigrateDatabase, Sqlite};
+use placement_service::{auth, config, errors, events, handlers, idempotency, metrics, openapi};
+use placement_service::placement_service::PlacementService;
+use sqlx::{sqlite::SqlitePoolOptions, migrate::MigrateDatabase, Sqlite};
 use dotenv::dotenv;
-use std::env;
-use log::{info, error};
+use tracing::{info, warn, error};
+use tracing_actix_web::TracingLogger;
+use tracing_subscriber::EnvFilter;
+use std::io::IsTerminal;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    // Initialize logging with timestamp
-    env_logger::Builder::from_env(Env::default().default_filter_or("info"))
-        .format_timestamp_millis()
-        .init();
-
     // Load environment variables from .env file
     dotenv().ok();
 
-    // Get database URL from environment variable
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set in .env file");
+    // Logging is configured by the config itself, so problems with it go to stderr
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()));
+        }
+    };
+    init_tracing(&config.logging);
+    config.install();
+    let database_url = config.database.url.clone();
 
     // Create database file if it doesn't exist
     if !Sqlite::database_exists(&database_url).await.unwrap_or(false) {
@@ -36,7 +38,9 @@ async fn main() -> std::io::Result<()> {
     }
 
     // Establish database connection pool
-    let db_pool = SqlitePool::connect(&database_url)
+    let db_pool = SqlitePoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .connect(&database_url)
         .await
         .expect("Failed to connect to database");
 
@@ -53,33 +57,109 @@ async fn main() -> std::io::Result<()> {
     // Create PlacementService (if it now uses the db pool, modify its constructor)
     // For now, assume it might still contain some logic, but handlers will use db_pool primarily.
     let placement_service = web::Data::new(PlacementService::new());
+    let event_bus = web::Data::new(events::EventBus::new());
+    let metrics = web::Data::new(metrics::Metrics::new());
+
+    let idempotency_config = web::Data::new(idempotency::IdempotencyConfig { ttl: chrono::Duration::seconds(config.idempotency.ttl_secs) });
+    let auth_config = web::Data::new(config.auth.clone());
+    if auth_config.disabled {
+        warn!("Authentication is disabled: every request is treated as an admin");
+    } else if auth_config.admin_token.is_none() {
+        info!("No admin token configured; only tokens already in the database (or JWTs) will be accepted");
+    }
+    if config.server.cors_allowed_origins.is_empty() {
+        warn!("No CORS origins configured; cross-origin requests (such as from the frontend) are refused");
+    }
+
+    let bind_address = config.server.bind_address.clone();
+    let max_body_bytes = config.server.max_body_bytes;
+    let allowed_origins = config.server.cors_allowed_origins.clone();
+    let config = web::Data::new(config);
 
-    println!("Starting server at http://127.0.0.1:8080");
+    println!("Starting server at http://{}", bind_address);
 
     HttpServer::new(move || {
         // Configure CORS middleware
-        let cors = Cors::default()
-            .allow_any_origin() // In production, you'd want to restrict this to your frontend origin
+        // No configured origins means none are allowed, not all of them
+        let cors = allowed_origins.iter().fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
             .allow_any_method()
             .allow_any_header()
             .max_age(3600); // 1 hour cache for preflight requests
 
         App::new()
             .wrap(cors) // Add CORS middleware first
-            .wrap(middleware::Logger::default())
+            .wrap(TracingLogger::default())
             .wrap(middleware::Compress::default())
+            .wrap(from_fn(metrics::track_requests))
+            .app_data(web::JsonConfig::default().limit(max_body_bytes).error_handler(|err, _req| errors::ApiError::from_json_error(err).into()))
+            .app_data(web::QueryConfig::default().error_handler(|err, _req| errors::ApiError::from_query_error(err).into()))
+            .app_data(web::PathConfig::default().error_handler(|err, _req| errors::ApiError::from_path_error(err).into()))
+            // Raw bodies (read by the idempotency middleware) may be as large as JSON ones
+            .app_data(web::PayloadConfig::new(max_body_bytes))
             .app_data(web::Data::new(db_pool.clone()))
             .app_data(placement_service.clone())
+            .app_data(event_bus.clone())
+            .app_data(idempotency_config.clone())
+            .app_data(auth_config.clone())
+            .app_data(config.clone())
+            .app_data(metrics.clone())
+            // Probes and scrapes come from the platform, not users, so they sit outside auth
+            .route("/healthz", web::get().to(handlers::healthz))
+            .route("/readyz", web::get().to(handlers::readyz))
+            .route("/metrics", web::get().to(handlers::prometheus_metrics))
+            // Registered ahead of the /api scope, which would otherwise claim these paths
+            .service(
+                SwaggerUi::new("/api/docs/{_:.*}")
+                    .url("/api/openapi.json", openapi::ApiDoc::openapi())
+            )
             .service(
                 web::scope("/api")
-                    .route("/placement", web::post().to(handlers::optimize_placement))
-                    .route("/place", web::post().to(handlers::place_item))
-                    .route("/retrieve", web::post().to(handlers::retrieve_item))
-                    .route("/waste-management", web::get().to(handlers::get_waste_management))
-                    .route("/cargo-return", web::get().to(handlers::get_cargo_return_plan))
+                    // Wrapped in reverse: authentication runs first so idempotency keys are scoped to the caller
+                    .wrap(from_fn(idempotency::idempotency))
+                    .wrap(from_fn(auth::authenticate))
+                    .route("/placement", web::post().to(handlers::optimize_placement).wrap(from_fn(auth::require_planner)))
+                    .route("/placement/suggest", web::post().to(handlers::suggest_placement).wrap(from_fn(auth::require_crew)))
+                    .route("/place", web::post().to(handlers::place_item).wrap(from_fn(auth::require_crew)))
+                    .route("/rearrangement/validate", web::post().to(handlers::validate_rearrangement_plan).wrap(from_fn(auth::require_crew)))
+                    .route("/validate", web::get().to(handlers::validate_arrangement).wrap(from_fn(auth::require_crew)))
+                    .route("/containers/{id}/compact", web::post().to(handlers::compact_container).wrap(from_fn(auth::require_planner)))
+                    .route("/zones/{zone}/compact", web::post().to(handlers::compact_zone).wrap(from_fn(auth::require_planner)))
+                    .route("/free-space", web::get().to(handlers::get_free_space).wrap(from_fn(auth::require_crew)))
+                    .route("/free-space/fit", web::get().to(handlers::get_fitting_containers).wrap(from_fn(auth::require_crew)))
+                    .route("/items", web::get().to(handlers::list_items).wrap(from_fn(auth::require_crew)))
+                    .route("/items", web::post().to(handlers::create_item).wrap(from_fn(auth::require_planner)))
+                    .route("/items/{id}", web::get().to(handlers::get_item).wrap(from_fn(auth::require_crew)))
+                    .route("/items/{id}", web::patch().to(handlers::update_item).wrap(from_fn(auth::require_planner)))
+                    .route("/items/{id}", web::delete().to(handlers::delete_item).wrap(from_fn(auth::require_admin)))
+                    .route("/containers", web::get().to(handlers::list_containers).wrap(from_fn(auth::require_crew)))
+                    .route("/containers", web::post().to(handlers::create_container).wrap(from_fn(auth::require_planner)))
+                    .route("/containers/{id}", web::get().to(handlers::get_container).wrap(from_fn(auth::require_crew)))
+                    .route("/containers/{id}", web::patch().to(handlers::update_container).wrap(from_fn(auth::require_planner)))
+                    .route("/containers/{id}", web::delete().to(handlers::delete_container).wrap(from_fn(auth::require_admin)))
+                    .route("/retrieve", web::post().to(handlers::retrieve_item).wrap(from_fn(auth::require_crew)))
+                    .route("/waste", web::get().to(handlers::list_waste).wrap(from_fn(auth::require_crew)))
+                    .route("/waste-management", web::get().to(handlers::get_waste_management).wrap(from_fn(auth::require_crew)))
+                    .route("/cargo-return", web::get().to(handlers::get_cargo_return_plan).wrap(from_fn(auth::require_crew)))
+                    .route("/logs", web::get().to(handlers::list_logs).wrap(from_fn(auth::require_crew)))
+                    .route("/events", web::get().to(handlers::stream_events).wrap(from_fn(auth::require_crew)))
+                    .route("/tokens", web::get().to(handlers::list_tokens).wrap(from_fn(auth::require_admin)))
+                    .route("/tokens", web::post().to(handlers::create_token).wrap(from_fn(auth::require_admin)))
+                    .route("/tokens/{id}", web::delete().to(handlers::revoke_token).wrap(from_fn(auth::require_admin)))
+                    .route("/admin/config", web::get().to(handlers::get_config).wrap(from_fn(auth::require_admin)))
             )
     })
-    .bind("127.0.0.1:8080")?
+    .bind(&bind_address)?
     .run()
     .await
-} 
\ No newline at end of file
+} 
+
+// Spans (the request span from TracingLogger and the placement phase/item spans)
+// are printed with every event inside them; `log` records from dependencies are
+// forwarded too.
+fn init_tracing(logging: &config::LoggingConfig) {
+    let filter = EnvFilter::new(&logging.filter);
+    match logging.format {
+        config::LogFormat::Text => tracing_subscriber::fmt().with_ansi(std::io::stdout().is_terminal()).with_env_filter(filter).init(),
+        config::LogFormat::Json => tracing_subscriber::fmt().json().with_current_span(true).with_span_list(true).with_env_filter(filter).init(),
+    }
+}
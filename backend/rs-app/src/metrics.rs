@@ -0,0 +1,124 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::web;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, Histogram, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::SqlitePool;
+use std::time::{Duration, Instant};
+
+// Items per placement run, from a single item up to a full cargo manifest
+const RUN_ITEM_BUCKETS: &[f64] = &[0.0, 1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 5000.0];
+
+/// Prometheus collectors for the service. Request and placement metrics are
+/// recorded as they happen; inventory gauges are refreshed on every scrape.
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    placement_duration: Histogram,
+    placement_items: HistogramVec,
+    zone_utilisation: GaugeVec,
+    waste_items: IntGaugeVec,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by method, route pattern and status"),
+            &["method", "route", "status"],
+        ).expect("valid metric");
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by method and route pattern"),
+            &["method", "route"],
+        ).expect("valid metric");
+        let placement_duration = Histogram::with_opts(
+            HistogramOpts::new("placement_run_duration_seconds", "Duration of committed placement runs")
+                .buckets(vec![0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]),
+        ).expect("valid metric");
+        let placement_items = HistogramVec::new(
+            HistogramOpts::new("placement_run_items", "Items placed, failed and rearranged per placement run")
+                .buckets(RUN_ITEM_BUCKETS.to_vec()),
+            &["outcome"],
+        ).expect("valid metric");
+        let zone_utilisation = GaugeVec::new(
+            Opts::new("zone_utilisation_ratio", "Placed item volume over container volume, per zone"),
+            &["zone"],
+        ).expect("valid metric");
+        let waste_items = IntGaugeVec::new(
+            Opts::new("waste_items", "Items marked as waste, by status"),
+            &["status"],
+        ).expect("valid metric");
+
+        let registry = Registry::new_custom(Some("iss_cargo".to_string()), None).expect("valid registry");
+        registry.register(Box::new(http_requests.clone())).expect("unique metric");
+        registry.register(Box::new(http_duration.clone())).expect("unique metric");
+        registry.register(Box::new(placement_duration.clone())).expect("unique metric");
+        registry.register(Box::new(placement_items.clone())).expect("unique metric");
+        registry.register(Box::new(zone_utilisation.clone())).expect("unique metric");
+        registry.register(Box::new(waste_items.clone())).expect("unique metric");
+
+        Metrics { registry, http_requests, http_duration, placement_duration, placement_items, zone_utilisation, waste_items }
+    }
+
+    pub fn observe_placement_run(&self, duration: Duration, placed: usize, failed: usize, rearranged: usize) {
+        self.placement_duration.observe(duration.as_secs_f64());
+        self.placement_items.with_label_values(&["placed"]).observe(placed as f64);
+        self.placement_items.with_label_values(&["failed"]).observe(failed as f64);
+        self.placement_items.with_label_values(&["rearranged"]).observe(rearranged as f64);
+    }
+
+    /// Refreshes the inventory gauges and renders everything in the text format.
+    pub async fn render(&self, db_pool: &SqlitePool) -> Result<String, sqlx::Error> {
+        let zones: Vec<(String, f64, f64)> = sqlx::query_as(
+            r#"SELECT c.zone, SUM(c.width * c.depth * c.height),
+                      IFNULL((SELECT SUM((p.end_w - p.start_w) * (p.end_d - p.start_d) * (p.end_h - p.start_h))
+                              FROM placements p JOIN containers pc ON pc."containerId" = p."containerId_fk"
+                              WHERE pc.zone = c.zone), 0)
+               FROM containers c GROUP BY c.zone"#)
+            .fetch_all(db_pool)
+            .await?;
+        // Zones whose last container was removed should disappear, not keep their old value
+        self.zone_utilisation.reset();
+        for (zone, capacity, used) in zones {
+            let ratio = if capacity > 0.0 { used / capacity } else { 0.0 };
+            self.zone_utilisation.with_label_values(&[zone.as_str()]).set(ratio);
+        }
+
+        let waste: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT status, COUNT(*) FROM items WHERE status LIKE 'WASTE%' GROUP BY status")
+            .fetch_all(db_pool)
+            .await?;
+        self.waste_items.reset();
+        for (status, count) in waste {
+            self.waste_items.with_label_values(&[status.as_str()]).set(count);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer).expect("text encoding cannot fail");
+        Ok(String::from_utf8(buffer).unwrap_or_default())
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Records the count and latency of every request, labelled by route pattern
+/// (`/api/items/{id}`) rather than the concrete path to keep cardinality bounded.
+pub async fn track_requests<B: MessageBody>(req: ServiceRequest, next: Next<B>) -> Result<ServiceResponse<B>, actix_web::Error> {
+    let metrics = req.app_data::<web::Data<Metrics>>().cloned();
+    let method = req.method().to_string();
+    let started = Instant::now();
+    let res = next.call(req).await?;
+
+    if let Some(metrics) = metrics {
+        let route = res.request().match_pattern().unwrap_or_else(|| "unmatched".to_string());
+        metrics.http_requests.with_label_values(&[method.as_str(), route.as_str(), res.status().as_str()]).inc();
+        metrics.http_duration.with_label_values(&[method.as_str(), route.as_str()]).observe(started.elapsed().as_secs_f64());
+    }
+    Ok(res)
+}
@@ -1,15 +1,19 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use crate::config::Config;
+use crate::integrity::Violation;
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Coordinates {
     pub width: f64,
     pub depth: f64,
     pub height: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Position {
     #[serde(rename = "startCoordinates")]
     pub start_coordinates: Coordinates,
@@ -17,7 +21,7 @@ pub struct Position {
     pub end_coordinates: Coordinates,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Item {
     #[serde(rename = "itemId")]
     pub item_id: String,
@@ -44,7 +48,9 @@ fn default_current_uses() -> i32 {
     0
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+// Variant names match the status strings stored in the items table
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
 pub enum ItemStatus {
     ACTIVE,
     WASTE_EXPIRED,
@@ -52,7 +58,16 @@ pub enum ItemStatus {
     DISPOSED,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+// Ordered by privilege: each role may do everything the previous one can
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Crew,
+    Planner,
+    Admin,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct Container {
     #[serde(rename = "containerId")]
     pub container_id: String,
@@ -68,27 +83,41 @@ pub struct Container {
 
 // --- Request Structs ---
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlacementRequest {
     pub items: Vec<Item>,
+    #[serde(default)]
     pub containers: Vec<Container>,
+    // Plan against every container in the database, not just `containers`
+    #[serde(rename = "useStoredContainers", default)]
+    pub use_stored_containers: bool,
+    // Restricts the stored containers to these zones
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zones: Option<Vec<String>>,
+    // Attach an explanation of the planner's decisions to every item
+    #[serde(default)]
+    pub explain: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlaceRequest {
     #[serde(rename = "itemId")]
     pub item_id: String,
-    #[serde(rename = "userId")]
-    pub user_id: String,
+    // Ignored: the authenticated identity is recorded instead of what the client claims
+    #[serde(rename = "userId", default, skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
     #[serde(rename = "containerId")]
     pub container_id: String,
     pub position: Position,
+    // Rejects the placement if the container changed since this version was read
+    #[serde(rename = "expectedVersion", default, skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<i64>,
 }
 
 
 // --- Response Structs ---
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct PlacementResult {
     #[serde(rename = "itemId")]
     pub item_id: String,
@@ -96,12 +125,99 @@ pub struct PlacementResult {
     pub container_id: String,
     pub position: Position,
     #[serde(skip_serializing)]
+    #[allow(dead_code)]
     pub retrieval_steps: i32,
     #[serde(skip_serializing)]
+    #[allow(dead_code)]
     pub is_preferred_zone: bool,
+    // Only filled in when the request sets `explain`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub explanation: Option<PlacementExplanation>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How the planner arrived at an item's spot, or why it found none.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PlacementExplanation {
+    pub phase: PlacementPhase,
+    #[serde(rename = "preferredZone", skip_serializing_if = "Option::is_none")]
+    pub preferred_zone: Option<String>,
+    // Containers that rejected the item, with the latest reason each gave
+    #[serde(rename = "containersTried")]
+    pub containers_tried: Vec<ContainerAttempt>,
+    #[serde(rename = "displacementAttempted")]
+    pub displacement_attempted: bool,
+    #[serde(rename = "rejectedDisplacees")]
+    pub rejected_displacees: Vec<RejectedDisplacee>,
+    // For items moved out of the way: the item they made room for
+    #[serde(rename = "displacedFor", skip_serializing_if = "Option::is_none")]
+    pub displaced_for: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum PlacementPhase {
+    /// Already stored and left where it was.
+    Existing,
+    /// Placed in a preferred-zone container without moving anything.
+    PreferredZone,
+    /// Placed in the preferred zone after moving a lower-priority item out.
+    Rearrangement,
+    /// Placed wherever there was room once the preferred zone was exhausted.
+    AnyZone,
+    /// Moved elsewhere to make room for a higher-priority item.
+    Displaced,
+    NotPlaced,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RejectionReason {
+    /// Larger than the container in every orientation.
+    NoFit,
+    /// Every spot the search tried overlaps an item already there.
+    Overlap,
+    /// The only free spots would leave the item floating.
+    Unsupported,
+    /// The item would take the container over its weight capacity.
+    Weight,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ContainerAttempt {
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    pub zone: String,
+    pub reason: RejectionReason,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum DisplaceeRejection {
+    /// Taking it out would still not leave room for the item.
+    NoSpaceFreed,
+    /// No other container could take it.
+    NoNewHome,
+    /// Other items rest on it and would be left floating.
+    CarriesLoad,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RejectedDisplacee {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    pub reason: DisplaceeRejection,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UnplacedItem {
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    pub explanation: PlacementExplanation,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct RearrangementStep {
     pub step: i32,
     pub action: String,
@@ -117,27 +233,184 @@ pub struct RearrangementStep {
     pub to_position: Option<Position>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlacementResponse {
     pub success: bool,
     pub placements: Vec<PlacementResult>,
     pub rearrangements: Vec<RearrangementStep>,
+    // Version of every container the plan used, after it was committed
+    #[serde(rename = "containerVersions")]
+    pub container_versions: HashMap<String, i64>,
+    // Items that could not be placed, with the reasons; only when the request sets `explain`
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub unplaced: Vec<UnplacedItem>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PlaceResponse {
     pub success: bool,
+    // Version of the container after the placement
+    #[serde(rename = "containerVersion", skip_serializing_if = "Option::is_none")]
+    pub container_version: Option<i64>,
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RearrangementPlanRequest {
+    pub steps: Vec<RearrangementStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct RearrangementPlanResponse {
+    pub success: bool,
+    pub steps: Vec<RearrangementStep>,
+}
+
+// `success` says the check ran; `valid` says it found nothing wrong
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArrangementReport {
+    pub success: bool,
+    pub valid: bool,
+    pub containers: usize,
+    pub items: usize,
+    pub placements: usize,
+    pub violations: Vec<Violation>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompactionQuery {
+    #[serde(rename = "maxMoves", default = "default_max_moves")]
+    pub max_moves: usize,
+}
+
+fn default_max_moves() -> usize {
+    20
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerCompaction {
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    #[serde(rename = "moveCount")]
+    pub move_count: usize,
+    pub truncated: bool,
+    pub rearrangements: Vec<RearrangementStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompactionResponse {
+    pub success: bool,
+    pub containers: Vec<ContainerCompaction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FreeSpaceQuery {
+    pub zone: Option<String>,
+    #[serde(default = "default_free_space_top")]
+    pub top: usize,
+}
+
+fn default_free_space_top() -> usize {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FreeSpace {
+    pub position: Position,
+    pub volume: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerFreeSpace {
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    pub zone: String,
+    #[serde(rename = "largestFreeVolume")]
+    pub largest_free_volume: f64,
+    #[serde(rename = "freeSpaces")]
+    pub free_spaces: Vec<FreeSpace>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FreeSpaceResponse {
+    pub success: bool,
+    pub containers: Vec<ContainerFreeSpace>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FitQuery {
+    pub width: f64,
+    pub depth: f64,
+    pub height: f64,
+    // Containers the item would push over their weight capacity are left out
+    pub mass: Option<f64>,
+    pub zone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerFit {
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    pub zone: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FitResponse {
+    pub success: bool,
+    pub containers: Vec<ContainerFit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestionQuery {
+    #[serde(default = "default_suggestion_top")]
+    pub top: usize,
+}
+
+fn default_suggestion_top() -> usize {
+    5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ScoreBreakdown {
+    #[serde(rename = "zoneMatch")]
+    pub zone_match: f64,
+    pub retrieval: f64,
+    pub utilisation: f64,
+    pub stability: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PlacementSuggestion {
+    #[serde(rename = "containerId")]
+    pub container_id: String,
+    pub zone: String,
+    pub position: Position,
+    #[serde(rename = "retrievalSteps")]
+    pub retrieval_steps: i32,
+    pub score: f64,
+    #[serde(rename = "scoreBreakdown")]
+    pub score_breakdown: ScoreBreakdown,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SuggestionResponse {
+    pub success: bool,
+    pub suggestions: Vec<PlacementSuggestion>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RetrievalRequest {
     #[serde(rename = "itemId")]
     pub item_id: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct RetrievalResponse {
     pub success: bool,
     pub item: Option<Item>,
@@ -151,7 +424,272 @@ pub struct RetrievalResponse {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// --- Item & Container Resources ---
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ItemRecord {
+    #[serde(flatten)]
+    pub item: Item,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ContainerRecord {
+    #[serde(flatten)]
+    pub container: Container,
+    #[serde(rename = "itemCount")]
+    pub item_count: i64,
+    pub version: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ItemPatch {
+    pub name: Option<String>,
+    pub width: Option<f64>,
+    pub depth: Option<f64>,
+    pub height: Option<f64>,
+    pub mass: Option<f64>,
+    pub priority: Option<i32>,
+    #[serde(rename = "expiryDate")]
+    pub expiry_date: Option<DateTime<Utc>>,
+    #[serde(rename = "usageLimit")]
+    pub usage_limit: Option<i32>,
+    #[serde(rename = "currentUses")]
+    pub current_uses: Option<i32>,
+    #[serde(rename = "preferredZone")]
+    pub preferred_zone: Option<String>,
+    pub status: Option<ItemStatus>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, ToSchema)]
+pub struct ContainerPatch {
+    pub zone: Option<String>,
+    pub width: Option<f64>,
+    pub depth: Option<f64>,
+    pub height: Option<f64>,
+    pub is_waste_container: Option<bool>,
+    #[serde(rename = "maxWeightCapacity")]
+    pub max_weight_capacity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteContainerQuery {
+    #[serde(default)]
+    pub force: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ItemListQuery {
+    // Case-insensitive substring of the item's ID or name
+    pub search: Option<String>,
+    // Matches the item's preferred zone
+    pub zone: Option<String>,
+    pub status: Option<ItemStatus>,
+    #[serde(rename = "minPriority")]
+    pub min_priority: Option<i32>,
+    #[serde(rename = "maxPriority")]
+    pub max_priority: Option<i32>,
+    #[serde(rename = "expiresAfter")]
+    pub expires_after: Option<DateTime<Utc>>,
+    #[serde(rename = "expiresBefore")]
+    pub expires_before: Option<DateTime<Utc>>,
+    // Only items currently placed in this container
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+    pub sort: Option<String>,
+    #[serde(default = "crate::listing::default_page_size")]
+    pub limit: i64,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ContainerListQuery {
+    // Case-insensitive substring of the container's ID or zone
+    pub search: Option<String>,
+    pub zone: Option<String>,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+    #[serde(rename = "isWasteContainer")]
+    pub is_waste_container: Option<bool>,
+    pub sort: Option<String>,
+    #[serde(default = "crate::listing::default_page_size")]
+    pub limit: i64,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ItemListResponse {
+    pub success: bool,
+    pub items: Vec<ItemRecord>,
+    // Matches across all pages
+    pub total: i64,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ItemResponse {
+    pub success: bool,
+    pub item: ItemRecord,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerListResponse {
+    pub success: bool,
+    pub containers: Vec<ContainerRecord>,
+    pub total: i64,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WasteListQuery {
+    // Zone of the container the item is in
+    pub zone: Option<String>,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+    // WASTE_EXPIRED or WASTE_DEPLETED; both when absent
+    pub status: Option<ItemStatus>,
+    pub sort: Option<String>,
+    #[serde(default = "crate::listing::default_page_size")]
+    pub limit: i64,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ActionType {
+    Placement,
+    Retrieval,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    #[serde(rename = "userId")]
+    pub user_id: String,
+    #[serde(rename = "actionType")]
+    pub action_type: ActionType,
+    #[serde(rename = "itemId")]
+    pub item_id: String,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogListQuery {
+    #[serde(rename = "itemId")]
+    pub item_id: Option<String>,
+    #[serde(rename = "userId")]
+    pub user_id: Option<String>,
+    #[serde(rename = "actionType")]
+    pub action_type: Option<ActionType>,
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+    #[serde(rename = "startDate")]
+    pub start_date: Option<DateTime<Utc>>,
+    #[serde(rename = "endDate")]
+    pub end_date: Option<DateTime<Utc>>,
+    pub sort: Option<String>,
+    #[serde(default = "crate::listing::default_page_size")]
+    pub limit: i64,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LogListResponse {
+    pub success: bool,
+    pub logs: Vec<LogEntry>,
+    pub total: i64,
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContainerResponse {
+    pub success: bool,
+    pub container: ContainerRecord,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeleteResponse {
+    pub success: bool,
+    #[serde(rename = "removedPlacements")]
+    pub removed_placements: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiToken {
+    pub id: i64,
+    pub name: String,
+    pub role: Role,
+    #[serde(rename = "createdAt")]
+    pub created_at: DateTime<Utc>,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<DateTime<Utc>>,
+    #[serde(rename = "revokedAt")]
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    pub role: Role,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatedTokenResponse {
+    pub success: bool,
+    // The secret itself; it is not stored and cannot be shown again
+    pub token: String,
+    #[serde(rename = "apiToken")]
+    pub api_token: ApiToken,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TokenListResponse {
+    pub success: bool,
+    pub tokens: Vec<ApiToken>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthResponse {
+    pub success: bool,
+    pub status: String, // "ok", "ready" or "not ready"
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub checks: Vec<HealthCheck>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthCheck {
+    pub name: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConfigResponse {
+    pub success: bool,
+    pub config: Config,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TokenResponse {
+    pub success: bool,
+    #[serde(rename = "apiToken")]
+    pub api_token: ApiToken,
+}
+
+#[allow(dead_code)] // Returned once waste management is implemented
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct WasteManagementResponse {
     #[serde(rename = "expiredItems")]
     pub expired_items: Vec<Item>,
@@ -163,8 +701,83 @@ pub struct WasteManagementResponse {
     pub total_waste_mass: f64,
 }
 
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Crew => "crew",
+            Role::Planner => "planner",
+            Role::Admin => "admin",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Role> {
+        match value {
+            "crew" => Some(Role::Crew),
+            "planner" => Some(Role::Planner),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+impl ActionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ActionType::Placement => "placement",
+            ActionType::Retrieval => "retrieval",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ActionType> {
+        match value {
+            "placement" => Some(ActionType::Placement),
+            "retrieval" => Some(ActionType::Retrieval),
+            _ => None,
+        }
+    }
+}
+
+impl ItemStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ItemStatus::ACTIVE => "ACTIVE",
+            ItemStatus::WASTE_EXPIRED => "WASTE_EXPIRED",
+            ItemStatus::WASTE_DEPLETED => "WASTE_DEPLETED",
+            ItemStatus::DISPOSED => "DISPOSED",
+        }
+    }
+}
+
+impl ItemPatch {
+    pub fn apply(self, item: &mut Item) {
+        if let Some(v) = self.name { item.name = v; }
+        if let Some(v) = self.width { item.width = v; }
+        if let Some(v) = self.depth { item.depth = v; }
+        if let Some(v) = self.height { item.height = v; }
+        if let Some(v) = self.mass { item.mass = Some(v); }
+        if let Some(v) = self.priority { item.priority = v; }
+        if let Some(v) = self.expiry_date { item.expiry_date = Some(v); }
+        if let Some(v) = self.usage_limit { item.usage_limit = v; }
+        if let Some(v) = self.current_uses { item.current_uses = v; }
+        if let Some(v) = self.preferred_zone { item.preferred_zone = v; }
+        if let Some(v) = self.status { item.status = Some(v); }
+    }
+}
+
+impl ContainerPatch {
+    pub fn apply(self, container: &mut Container) {
+        if let Some(v) = self.zone { container.zone = v; }
+        if let Some(v) = self.width { container.width = v; }
+        if let Some(v) = self.depth { container.depth = v; }
+        if let Some(v) = self.height { container.height = v; }
+        if let Some(v) = self.is_waste_container { container.is_waste_container = Some(v); }
+        if let Some(v) = self.max_weight_capacity { container.max_weight_capacity = Some(v); }
+    }
+}
+
 // Helper methods for item comparison
 impl Item {
+    #[allow(dead_code)]
     pub fn is_waste(&self) -> bool {
         // Check if status is explicitly set to Waste
         if let Some(status) = &self.status {
@@ -184,6 +797,7 @@ impl Item {
         self.current_uses >= self.usage_limit
     }
 
+    #[allow(dead_code)]
     pub fn compare_priority(&self, other: &Item) -> Ordering {
         other.priority.cmp(&self.priority)
             .then_with(|| {
@@ -207,6 +821,7 @@ impl Position {
         width * depth * height
     }
 
+    #[allow(dead_code)]
     pub fn overlaps(&self, other: &Position) -> bool {
         !(self.end_coordinates.width <= other.start_coordinates.width ||
           self.start_coordinates.width >= other.end_coordinates.width ||
@@ -221,4 +836,22 @@ impl Position {
         // Item is accessible if it's directly visible from the open face
         (self.start_coordinates.depth - 0.0).abs() < f64::EPSILON
     }
-} 
\ No newline at end of file
+} 
+
+impl PlacementExplanation {
+    pub fn new(phase: PlacementPhase, preferred_zone: Option<String>) -> Self {
+        PlacementExplanation {
+            phase,
+            preferred_zone,
+            containers_tried: Vec::new(),
+            displacement_attempted: false,
+            rejected_displacees: Vec::new(),
+            displaced_for: None,
+        }
+    }
+
+    pub fn reject(&mut self, container: &Container, reason: RejectionReason) {
+        self.containers_tried.retain(|a| a.container_id != container.container_id);
+        self.containers_tried.push(ContainerAttempt { container_id: container.container_id.clone(), zone: container.zone.clone(), reason });
+    }
+}
@@ -0,0 +1,87 @@
+use crate::config::*;
+use crate::events::Event;
+use crate::errors::{ErrorBody, ErrorResponse, FieldError};
+use crate::handlers;
+use crate::integrity::Violation;
+use crate::models::*;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+// Paths come from the `#[utoipa::path]` annotations on the handlers and schemas
+// from the models' serde attributes, so the document follows the code.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "ISS Cargo Placement Service",
+        description = "POST, PUT, PATCH and DELETE requests may carry an `Idempotency-Key` header. \
+            A retry by the same caller with the same key and body gets the stored response (marked `Idempotent-Replayed: true`) \
+            instead of running again. Only successful responses are stored; failed requests may be retried with their key.\n\n\
+            Every endpoint needs an `Authorization: Bearer <token>` header carrying an API token or a signed JWT. \
+            Crew may read, place and retrieve; planners may also run placement, compaction and edit records; \
+            admins may also delete records and manage tokens.",
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    paths(
+        handlers::optimize_placement,
+        handlers::suggest_placement,
+        handlers::place_item,
+        handlers::validate_rearrangement_plan,
+        handlers::validate_arrangement,
+        handlers::compact_container,
+        handlers::compact_zone,
+        handlers::get_free_space,
+        handlers::get_fitting_containers,
+        handlers::list_items,
+        handlers::create_item,
+        handlers::get_item,
+        handlers::update_item,
+        handlers::delete_item,
+        handlers::list_containers,
+        handlers::create_container,
+        handlers::get_container,
+        handlers::update_container,
+        handlers::delete_container,
+        handlers::retrieve_item,
+        handlers::get_waste_management,
+        handlers::get_cargo_return_plan,
+        handlers::list_waste,
+        handlers::list_logs,
+        handlers::stream_events,
+        handlers::list_tokens,
+        handlers::create_token,
+        handlers::revoke_token,
+        handlers::get_config,
+        handlers::healthz,
+        handlers::readyz,
+        handlers::prometheus_metrics,
+    ),
+    components(schemas(
+        Coordinates, Position, Item, ItemStatus, Container,
+        PlacementRequest, PlacementResponse, PlacementResult, PlacementExplanation, PlacementPhase,
+        RejectionReason, ContainerAttempt, DisplaceeRejection, RejectedDisplacee, UnplacedItem,
+        PlaceRequest, PlaceResponse,
+        RearrangementStep, RearrangementPlanRequest, RearrangementPlanResponse, ArrangementReport, Violation,
+        ContainerCompaction, CompactionResponse,
+        FreeSpace, ContainerFreeSpace, FreeSpaceResponse, ContainerFit, FitResponse,
+        ScoreBreakdown, PlacementSuggestion, SuggestionResponse,
+        RetrievalRequest, RetrievalResponse, WasteManagementResponse,
+        ActionType, LogEntry, LogListResponse,
+        ItemRecord, ItemPatch, ItemListResponse, ItemResponse,
+        ContainerRecord, ContainerPatch, ContainerListResponse, ContainerResponse, DeleteResponse,
+        Role, ApiToken, CreateTokenRequest, CreatedTokenResponse, TokenListResponse, TokenResponse,
+        HealthResponse, HealthCheck,
+        Config, ServerConfig, DatabaseConfig, AuthConfig, IdempotencySettings, PlacementTuning, LoggingConfig, LogFormat, ConfigResponse,
+        Event, ErrorResponse, ErrorBody, FieldError,
+    )),
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme("bearer", SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)));
+    }
+}
@@ -1,12 +1,73 @@
+use crate::config;
 use crate::models::*;
+use crate::rearrangement;
+use crate::simulation::{boxes_overlap, is_supported, retrieval_steps};
 use anyhow::Result;
-use std::collections::HashMap;
-use log::{info, debug};
+use std::collections::{BTreeMap, HashMap};
+use tracing::{info, debug};
+
+// Upper bound on grid offsets per axis in the fallback search, which is cubic in them
+const MAX_FALLBACK_STEPS: usize = 40;
+
+// (retrieval steps, depth, height, width); compared in that order
+type PositionScore = (i32, f64, f64, f64);
+
+// A corner a box can be placed at, with the free length from it along each axis
+// (width, depth, height) before the first item or wall
+#[derive(Debug, Clone, Copy)]
+struct ExtremePoint {
+    point: [f64; 3],
+    residual: [f64; 3],
+}
+
+impl ExtremePoint {
+    fn fits(&self, width: f64, depth: f64, height: f64, tol: f64) -> bool {
+        width <= self.residual[0] + tol && depth <= self.residual[1] + tol && height <= self.residual[2] + tol
+    }
+}
+
+// Start and end of a placement as (width, depth, height) arrays, for axis-generic code
+fn corners(position: &Position) -> ([f64; 3], [f64; 3]) {
+    let (s, e) = (&position.start_coordinates, &position.end_coordinates);
+    ([s.width, s.depth, s.height], [e.width, e.depth, e.height])
+}
+
+// Whether the line through `point` parallel to `axis` passes through the box's cross-section
+fn in_line(point: &[f64; 3], axis: usize, (start, end): &([f64; 3], [f64; 3]), tol: f64) -> bool {
+    (0..3).filter(|&a| a != axis).all(|a| start[a] <= point[a] + tol && point[a] + tol < end[a])
+}
+
+// How far back toward zero `point` can slide along `axis` before meeting an item
+fn project(point: &[f64; 3], axis: usize, boxes: &[([f64; 3], [f64; 3])], tol: f64) -> f64 {
+    boxes.iter()
+        .filter(|b| in_line(point, axis, b, tol) && b.1[axis] <= point[axis] + tol)
+        .map(|b| b.1[axis])
+        .fold(0.0, f64::max)
+}
+
+// Free length from `point` along `axis` up to the first item or the wall at `limit`
+fn residual(point: &[f64; 3], axis: usize, limit: f64, boxes: &[([f64; 3], [f64; 3])], tol: f64) -> f64 {
+    let mut reach = limit;
+    for b in boxes.iter().filter(|b| in_line(point, axis, b, tol)) {
+        if b.1[axis] > point[axis] + tol {
+            reach = reach.min(b.0[axis].max(point[axis]));
+        }
+    }
+    reach - point[axis]
+}
 
 pub struct PlacementService {
+    #[allow(dead_code)]
     placement_cache: HashMap<String, Vec<PlacementResult>>,
 }
 
+impl Default for PlacementService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Extreme-point engine; placement-cli can run it, the API uses the phased planner
 impl PlacementService {
     pub fn new() -> Self {
         PlacementService {
@@ -21,6 +82,8 @@ impl PlacementService {
             success: true,
             placements: Vec::new(),
             rearrangements: Vec::new(),
+            container_versions: HashMap::new(),
+            unplaced: Vec::new(),
             error: None,
         };
 
@@ -28,8 +91,8 @@ impl PlacementService {
         let mut sorted_items = items;
         sorted_items.sort_by(|a, b| a.compare_priority(b));
 
-        // Group containers by zone for efficient lookup
-        let mut zone_containers: HashMap<String, Vec<&Container>> = HashMap::new();
+        // Group containers by zone, in a fixed order so equal inputs give equal placements
+        let mut zone_containers: BTreeMap<String, Vec<&Container>> = BTreeMap::new();
         for container in &containers {
             zone_containers
                 .entry(container.zone.clone())
@@ -63,7 +126,20 @@ impl PlacementService {
                 None => {
                     // Try rearrangement if direct placement fails
                     debug!("Direct placement failed for item {}, trying rearrangement", item.item_id);
-                    match self.try_rearrangement(&item, &zone_containers, &mut container_spaces)? {
+                    let before = container_spaces.clone();
+                    let rearranged = match self.try_rearrangement(&item, &zone_containers, &mut container_spaces)? {
+                        // The moves are planned together; order them so each one can be carried out
+                        Some((steps, final_placement)) => match rearrangement::sequence_plan(&before, &steps) {
+                            Ok(steps) => Some((steps, final_placement)),
+                            Err(e) => {
+                                debug!("Rearrangement for item {} cannot be carried out: {}", item.item_id, e);
+                                container_spaces = before;
+                                None
+                            }
+                        },
+                        None => None,
+                    };
+                    match rearranged {
                         Some((steps, final_placement)) => {
                             debug!("Successfully found rearrangement for item {}", item.item_id);
                             response.rearrangements.extend(steps);
@@ -85,7 +161,7 @@ impl PlacementService {
     fn find_optimal_placement(
         &self,
         item: &Item,
-        zone_containers: &HashMap<String, Vec<&Container>>,
+        zone_containers: &BTreeMap<String, Vec<&Container>>,
         container_spaces: &HashMap<String, Vec<(String, Position)>>,
     ) -> Result<Option<PlacementResult>> {
         debug!("Finding optimal placement for item {}", item.item_id);
@@ -136,6 +212,7 @@ impl PlacementService {
                     position,
                     retrieval_steps: steps,
                     is_preferred_zone,
+                    explanation: None,
                 }));
             }
         }
@@ -163,7 +240,9 @@ impl PlacementService {
         orientations
     }
 
-    fn find_position(
+    /// Best spot for a box of exactly `width` x `depth` x `height` (no rotation),
+    /// with the number of items in front of it.
+    pub fn find_position(
         &self,
         width: f64,
         depth: f64,
@@ -171,199 +250,175 @@ impl PlacementService {
         container: &Container,
         existing_items: &[(String, Position)],
     ) -> Result<Option<(Position, i32)>> {
-        let mut best_position = None;
-        let mut min_steps = i32::MAX;
+        let tol = config::placement().tolerance;
+        // Nothing to search for when the free volume is smaller than the box
+        let used: f64 = existing_items.iter().map(|(_, p)| {
+            let (s, e) = (&p.start_coordinates, &p.end_coordinates);
+            (e.width - s.width) * (e.depth - s.depth) * (e.height - s.height)
+        }).sum();
+        if width * depth * height > container.width * container.depth * container.height - used + tol {
+            return Ok(None);
+        }
 
-        // Try positions using Extreme Point-Based Best Fit (EPBF) approach
-        let extreme_points = self.calculate_extreme_points(container, existing_items);
-        
-        for ep in extreme_points {
-            let x = ep.0;
-            let z = ep.1;
-            let y = ep.2;
-            
-            if x + width <= container.width && 
-               z + depth <= container.depth && 
-               y + height <= container.height {
-                
-                let position = Position {
-                    start_coordinates: Coordinates {
-                        width: x,
-                        depth: z,
-                        height: y,
-                    },
-                    end_coordinates: Coordinates {
-                        width: x + width,
-                        depth: z + depth,
-                        height: y + height,
-                    },
-                };
-
-                // Check if position is valid
-                if self.is_valid_position(&position, existing_items) {
-                    // Calculate retrieval steps
-                    let steps = self.calculate_retrieval_steps(&position, existing_items);
-                    
-                    // Update if this is the best position so far
-                    // Prefer positions with fewer steps and closer to the front
-                    if steps < min_steps || (steps == min_steps && z < position.start_coordinates.depth) {
-                        min_steps = steps;
-                        best_position = Some(position);
-                    }
-                }
+        let candidate = |(x, z, y): (f64, f64, f64)| -> Option<(PositionScore, Position, i32)> {
+            if x + width > container.width + tol || z + depth > container.depth + tol || y + height > container.height + tol {
+                return None;
             }
-        }
+            let position = Position {
+                start_coordinates: Coordinates { width: x, depth: z, height: y },
+                end_coordinates: Coordinates { width: x + width, depth: z + depth, height: y + height },
+            };
+            // Floating positions are never offered, matching the single-item suggestions
+            if !self.is_valid_position(&position, existing_items) || !is_supported(&position, existing_items) {
+                return None;
+            }
+            let steps = self.calculate_retrieval_steps(&position, existing_items);
+            Some((self.score_position(&position, steps), position, steps))
+        };
+        // Equal scores keep the first candidate
+        let by_score = |a: &(PositionScore, Position, i32), b: &(PositionScore, Position, i32)| {
+            a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal)
+        };
 
-        // If no valid extreme point was found, try a simple 3D grid search as fallback
-        if best_position.is_none() {
-            // Try positions starting from the front (depth = 0) with a grid step
-            let grid_step = 0.1; // Define a suitable grid step for your application
-            
-            for z in (0..=(container.depth as i32 - depth as i32)).map(|i| i as f64 * grid_step) {
-                if z + depth > container.depth { continue; }
-                
-                for x in (0..=(container.width as i32 - width as i32)).map(|i| i as f64 * grid_step) {
-                    if x + width > container.width { continue; }
-                    
-                    for y in (0..=(container.height as i32 - height as i32)).map(|i| i as f64 * grid_step) {
-                        if y + height > container.height { continue; }
-                        
-                        let position = Position {
-                            start_coordinates: Coordinates {
-                                width: x,
-                                depth: z,
-                                height: y,
-                            },
-                            end_coordinates: Coordinates {
-                                width: x + width,
-                                depth: z + depth,
-                                height: y + height,
-                            },
-                        };
-
-                        // Check if position is valid
-                        if self.is_valid_position(&position, existing_items) {
-                            // Calculate retrieval steps
-                            let steps = self.calculate_retrieval_steps(&position, existing_items);
-                            
-                            // Update if this is the best position so far
-                            if steps < min_steps || (steps == min_steps && z < position.start_coordinates.depth) {
-                                min_steps = steps;
-                                best_position = Some(position);
-                            }
+        // Try positions using Extreme Point-Based Best Fit (EPBF) approach
+        let mut best = self.calculate_extreme_points(container, existing_items).into_iter()
+            .filter(|ep| ep.fits(width, depth, height, tol))
+            .map(|ep| (ep.point[0], ep.point[1], ep.point[2]))
+            .filter_map(candidate)
+            .min_by(by_score);
+
+        // If no extreme point can take the box, search a grid over the whole container.
+        // Items are narrowed down per width slab and then per column, so occupied
+        // columns are ruled out without checking every item at every height.
+        if best.is_none() {
+            let overlaps = |start: f64, length: f64, lo: f64, hi: f64| start + length > lo + tol && hi > start + tol;
+            let xs = self.fallback_coordinates(container.width, width, existing_items.iter().map(|(_, p)| p.end_coordinates.width));
+            let zs = self.fallback_coordinates(container.depth, depth, existing_items.iter().map(|(_, p)| p.end_coordinates.depth));
+            let ys = self.fallback_coordinates(container.height, height, existing_items.iter().map(|(_, p)| p.end_coordinates.height));
+            let mut points = Vec::new();
+            for &x in &xs {
+                let slab: Vec<&Position> = existing_items.iter().map(|(_, p)| p)
+                    .filter(|p| overlaps(x, width, p.start_coordinates.width, p.end_coordinates.width))
+                    .collect();
+                for &z in &zs {
+                    let mut column: Vec<(f64, f64)> = slab.iter()
+                        .filter(|p| overlaps(z, depth, p.start_coordinates.depth, p.end_coordinates.depth))
+                        .map(|p| (p.start_coordinates.height, p.end_coordinates.height))
+                        .collect();
+                    column.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+                    // Walk the gaps between the items in the column, bottom to top
+                    let mut floor = 0.0;
+                    for (bottom, top) in column.into_iter().chain(std::iter::once((f64::INFINITY, f64::INFINITY))) {
+                        if bottom - floor + tol >= height {
+                            points.extend(ys.iter().copied()
+                                .filter(|&y| y + tol >= floor && y + height <= bottom + tol)
+                                .map(|y| (x, z, y)));
                         }
+                        floor = f64::max(floor, top);
                     }
                 }
             }
+            best = points.into_iter().filter_map(candidate).min_by(by_score);
         }
 
-        Ok(best_position.map(|pos| (pos, min_steps)))
+        Ok(best.map(|(_, position, steps)| (position, steps)))
     }
 
-    fn calculate_extreme_points(
-        &self, 
-        container: &Container,
-        existing_items: &[(String, Position)]
-    ) -> Vec<(f64, f64, f64)> {
-        let mut extreme_points = vec![
-            (0.0, 0.0, 0.0), // Start with the bottom-front-left corner
-        ];
+    // Lower is better: fewer items to move on retrieval, then closer to the open
+    // face, lower down and further left
+    fn score_position(&self, position: &Position, steps: i32) -> PositionScore {
+        let start = &position.start_coordinates;
+        (steps, start.depth, start.height, start.width)
+    }
 
-        // For each existing item, generate new extreme points
-        for (_, pos) in existing_items {
-            // Add 3 extreme points for each item:
-            // - Top face
-            extreme_points.push((
-                pos.start_coordinates.width,
-                pos.start_coordinates.depth,
-                pos.end_coordinates.height
-            ));
-            
-            // - Right face
-            extreme_points.push((
-                pos.end_coordinates.width,
-                pos.start_coordinates.depth,
-                pos.start_coordinates.height
-            ));
-            
-            // - Front face
-            extreme_points.push((
-                pos.start_coordinates.width,
-                pos.end_coordinates.depth,
-                pos.start_coordinates.height
-            ));
+    // Offsets along one axis where a box of `length` may start: a grid of
+    // `fallback_grid_step` (coarsened so long axes stay affordable), the far wall,
+    // and the far faces of the items already there, so boxes smaller than the step
+    // can still sit flush against their neighbours.
+    fn fallback_coordinates(&self, container_length: f64, length: f64, faces: impl Iterator<Item = f64>) -> Vec<f64> {
+        let tuning = config::placement();
+        let span = container_length - length;
+        if span < -tuning.tolerance {
+            return Vec::new();
         }
+        let span = span.max(0.0);
+        let step = tuning.fallback_grid_step.max(span / MAX_FALLBACK_STEPS as f64);
+
+        let mut coordinates: Vec<f64> = (0..=(span / step).floor() as usize).map(|i| i as f64 * step).collect();
+        coordinates.push(span);
+        coordinates.extend(faces.filter(|&f| f <= span + tuning.tolerance).map(|f| f.min(span)));
+        coordinates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        coordinates.dedup_by(|a, b| (*a - *b).abs() < tuning.tolerance);
+        coordinates
+    }
 
-        // Remove any points that are inside existing items
-        extreme_points.retain(|&(x, z, y)| {
-            for (_, pos) in existing_items {
-                if x >= pos.start_coordinates.width && x < pos.end_coordinates.width &&
-                   z >= pos.start_coordinates.depth && z < pos.end_coordinates.depth &&
-                   y >= pos.start_coordinates.height && y < pos.end_coordinates.height {
-                    return false;
+    // Extreme points (Crainic, Perboli & Tadei): each placed item offers the corners
+    // beside, in front of and on top of it, each projected back along the other two
+    // axes until it meets an item or a wall, so boxes end up resting against something.
+    fn calculate_extreme_points(
+        &self,
+        container: &Container,
+        existing_items: &[(String, Position)]
+    ) -> Vec<ExtremePoint> {
+        let tol = config::placement().tolerance;
+        let boxes: Vec<([f64; 3], [f64; 3])> = existing_items.iter().map(|(_, p)| corners(p)).collect();
+        let size = [container.width, container.depth, container.height];
+
+        let mut points = vec![[0.0, 0.0, 0.0]]; // The bottom-front-left corner
+        for (start, end) in &boxes {
+            for axis in 0..3 {
+                // The corner past the item's far face along `axis`, projected along the other two
+                let mut corner = *start;
+                corner[axis] = end[axis];
+                for along in (0..3).filter(|&a| a != axis) {
+                    let mut point = corner;
+                    point[along] = project(&point, along, &boxes, tol);
+                    points.push(point);
                 }
             }
-            return true;
-        });
+        }
 
-        // Filter points that are outside the container
-        extreme_points.retain(|&(x, z, y)| {
-            x >= 0.0 && x <= container.width &&
-            z >= 0.0 && z <= container.depth &&
-            y >= 0.0 && y <= container.height
-        });
+        let mut extreme_points: Vec<ExtremePoint> = Vec::with_capacity(points.len());
+        for point in points {
+            if (0..3).any(|a| point[a] < -tol || point[a] > size[a] + tol) {
+                continue;
+            }
+            if extreme_points.iter().any(|ep| (0..3).all(|a| (ep.point[a] - point[a]).abs() < tol)) {
+                continue; // Duplicate
+            }
+            let residual = [0, 1, 2].map(|a| residual(&point, a, size[a], &boxes, tol));
+            // No room at all: the point is inside an item or against a wall
+            if residual.iter().any(|&r| r <= tol) {
+                continue;
+            }
+            extreme_points.push(ExtremePoint { point, residual });
+        }
 
-        // Sort extreme points to prefer front positions first (z coordinate smallest)
+        // Sort extreme points to prefer front positions first (depth, then height, then width)
         extreme_points.sort_by(|a, b| {
-            a.1.partial_cmp(&b.1).unwrap()
-                .then(a.2.partial_cmp(&b.2).unwrap())
-                .then(a.0.partial_cmp(&b.0).unwrap())
+            let key = |ep: &ExtremePoint| [ep.point[1], ep.point[2], ep.point[0]];
+            key(a).partial_cmp(&key(b)).unwrap_or(std::cmp::Ordering::Equal)
         });
 
         extreme_points
     }
 
     fn is_valid_position(&self, position: &Position, existing_items: &[(String, Position)]) -> bool {
-        // Check for overlaps with existing items
-        for (_, existing_pos) in existing_items {
-            if position.overlaps(existing_pos) {
-                return false;
-            }
-        }
-        true
+        // Check for overlaps with existing items; touching faces are allowed within the tolerance
+        !existing_items.iter().any(|(_, existing_pos)| boxes_overlap(
+            &position.start_coordinates, &position.end_coordinates,
+            &existing_pos.start_coordinates, &existing_pos.end_coordinates,
+        ))
     }
 
     fn calculate_retrieval_steps(&self, position: &Position, existing_items: &[(String, Position)]) -> i32 {
-        let mut steps = 0;
-        
-        // If item is at the front (depth = 0), it's directly accessible
-        if (position.start_coordinates.depth - 0.0).abs() < f64::EPSILON {
-            return 0;
-        }
-
-        // Count items that need to be moved
-        for (_, other_pos) in existing_items {
-            if other_pos.start_coordinates.depth < position.start_coordinates.depth &&
-               self.blocks_retrieval(other_pos, position) {
-                steps += 1;
-            }
-        }
-
-        steps
-    }
-
-    fn blocks_retrieval(&self, blocking_pos: &Position, target_pos: &Position) -> bool {
-        // Check if the blocking item overlaps with the retrieval path
-        !(blocking_pos.end_coordinates.width <= target_pos.start_coordinates.width ||
-          blocking_pos.start_coordinates.width >= target_pos.end_coordinates.width ||
-          blocking_pos.end_coordinates.height <= target_pos.start_coordinates.height ||
-          blocking_pos.start_coordinates.height >= target_pos.end_coordinates.height)
+        retrieval_steps(position, existing_items) as i32
     }
 
     fn try_rearrangement(
         &self,
         item: &Item,
-        zone_containers: &HashMap<String, Vec<&Container>>,
+        zone_containers: &BTreeMap<String, Vec<&Container>>,
         container_spaces: &mut HashMap<String, Vec<(String, Position)>>,
     ) -> Result<Option<(Vec<RearrangementStep>, PlacementResult)>> {
         debug!("Attempting rearrangement to place item {}", item.item_id);
@@ -389,7 +444,7 @@ impl PlacementService {
                 // Try moving each item
                 for (item_id, current_pos) in &movable_items {
                     // Remove item temporarily
-                    temp_spaces.get_mut(&container.container_id).unwrap()
+                    temp_spaces.entry(container.container_id.clone()).or_default()
                         .retain(|(id, _)| id != item_id);
 
                     // Try to place our target item
@@ -399,6 +454,11 @@ impl PlacementService {
                         &temp_spaces,
                         true,
                     )? {
+                        // Hold the target's spot, so the moved item cannot be put back there
+                        temp_spaces.entry(container.container_id.clone())
+                            .or_default()
+                            .push((item.item_id.clone(), result.position.clone()));
+
                         // Find new place for moved item
                         if let Some((new_container, new_pos)) = self.find_alternative_placement(
                             item_id,
@@ -423,11 +483,6 @@ impl PlacementService {
                                 .or_default()
                                 .push((item_id.clone(), new_pos));
 
-                            // Place target item
-                            temp_spaces.entry(container.container_id.clone())
-                                .or_default()
-                                .push((item.item_id.clone(), result.position.clone()));
-
                             // Record the placement
                             rearrangement_steps.push(RearrangementStep {
                                 step: step_counter,
@@ -447,6 +502,8 @@ impl PlacementService {
                     }
 
                     // Restore item if move didn't work
+                    temp_spaces.entry(container.container_id.clone()).or_default()
+                        .retain(|(id, _)| id != &item.item_id);
                     temp_spaces.entry(container.container_id.clone())
                         .or_default()
                         .push((item_id.clone(), current_pos.clone()));
@@ -465,7 +522,7 @@ impl PlacementService {
     fn try_complex_rearrangement(
         &self,
         item: &Item,
-        zone_containers: &HashMap<String, Vec<&Container>>,
+        zone_containers: &BTreeMap<String, Vec<&Container>>,
         container_spaces: &mut HashMap<String, Vec<(String, Position)>>,
     ) -> Result<Option<(Vec<RearrangementStep>, PlacementResult)>> {
         debug!("Attempting complex rearrangement for item {}", item.item_id);
@@ -495,7 +552,7 @@ impl PlacementService {
                 // Try moving single items first
                 for (item1_id, item1_pos) in &items_to_try {
                     // Remove item temporarily
-                    temp_spaces.get_mut(&target_container.container_id).unwrap()
+                    temp_spaces.entry(target_container.container_id.clone()).or_default()
                         .retain(|(id, _)| id != item1_id);
 
                     // Try to place our target item
@@ -505,6 +562,11 @@ impl PlacementService {
                         &temp_spaces,
                         true,
                     )? {
+                        // Hold the target's spot, so the moved item cannot be put back there
+                        temp_spaces.entry(target_container.container_id.clone())
+                            .or_default()
+                            .push((item.item_id.clone(), result.position.clone()));
+
                         // Find new place for moved item
                         if let Some((new_container1, new_pos1)) = self.find_alternative_placement(
                             item1_id,
@@ -529,11 +591,6 @@ impl PlacementService {
                                 .or_default()
                                 .push((item1_id.clone(), new_pos1));
 
-                            // Place target item
-                            temp_spaces.entry(target_container.container_id.clone())
-                                .or_default()
-                                .push((item.item_id.clone(), result.position.clone()));
-
                             // Record the placement
                             rearrangement_steps.push(RearrangementStep {
                                 step: step_counter,
@@ -553,6 +610,8 @@ impl PlacementService {
                     }
 
                     // Reset for next iteration
+                    temp_spaces.entry(target_container.container_id.clone()).or_default()
+                        .retain(|(id, _)| id != &item.item_id);
                     temp_spaces.entry(target_container.container_id.clone())
                         .or_default()
                         .push((item1_id.clone(), item1_pos.clone()));
@@ -565,7 +624,7 @@ impl PlacementService {
                         let (item2_id, item2_pos) = items_to_try[j];
 
                         // Remove both items temporarily
-                        temp_spaces.get_mut(&target_container.container_id).unwrap()
+                        temp_spaces.entry(target_container.container_id.clone()).or_default()
                             .retain(|(id, _)| id != item1_id && id != item2_id);
 
                         // Try to place our target item
@@ -575,6 +634,11 @@ impl PlacementService {
                             &temp_spaces,
                             true,
                         )? {
+                            // Hold the target's spot, so the moved items cannot be put back there
+                            temp_spaces.entry(target_container.container_id.clone())
+                                .or_default()
+                                .push((item.item_id.clone(), result.position.clone()));
+
                             // Find new places for moved items
                             if let Some((new_container1, new_pos1)) = self.find_alternative_placement(
                                 item1_id,
@@ -621,11 +685,6 @@ impl PlacementService {
                                         .or_default()
                                         .push((item2_id.clone(), new_pos2));
 
-                                    // Place target item
-                                    temp_spaces.entry(target_container.container_id.clone())
-                                        .or_default()
-                                        .push((item.item_id.clone(), result.position.clone()));
-
                                     // Record the placement
                                     rearrangement_steps.push(RearrangementStep {
                                         step: step_counter,
@@ -643,13 +702,15 @@ impl PlacementService {
                                     return Ok(Some((rearrangement_steps, result)));
                                 } else {
                                     // Remove first item if second placement failed
-                                    temp_spaces.get_mut(&new_container1).unwrap()
+                                    temp_spaces.entry(new_container1.clone()).or_default()
                                         .retain(|(id, _)| id != item1_id);
                                 }
                             }
                         }
 
                         // Reset for next iteration
+                        temp_spaces.entry(target_container.container_id.clone()).or_default()
+                            .retain(|(id, _)| id != &item.item_id);
                         temp_spaces.entry(target_container.container_id.clone())
                             .or_default()
                             .push((item1_id.clone(), item1_pos.clone()));
@@ -668,7 +729,7 @@ impl PlacementService {
         &self,
         _item_id: &str,  // Prefix with underscore since it's not used
         current_pos: &Position,
-        zone_containers: &HashMap<String, Vec<&Container>>,
+        zone_containers: &BTreeMap<String, Vec<&Container>>,
         temp_spaces: &HashMap<String, Vec<(String, Position)>>,
     ) -> Result<Option<(String, Position)>> {
         let item_width = current_pos.end_coordinates.width - current_pos.start_coordinates.width;
@@ -0,0 +1,435 @@
+use crate::config;
+use crate::models::*;
+use crate::rearrangement::{self, PlanError};
+use crate::simulation::{carried_by, exceeds_capacity, find_spot_in_container, is_supported, search_spot, ContainerState};
+use tracing::{debug, error, info, info_span, warn, Span};
+use std::collections::{HashMap, HashSet};
+
+/// Outcome of a planning run over a set of containers.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    // Final spot of every item in the planned containers, moved or not
+    pub placements: HashMap<String, PlacementResult>,
+    // Moves of already-placed items, in an order that can be carried out
+    pub rearrangements: Vec<RearrangementStep>,
+    // Incoming items no container could take
+    pub failed: Vec<String>,
+    pub explanations: HashMap<String, PlacementExplanation>,
+}
+
+/// Places `items` into `containers`, which already hold `initial`.
+///
+/// High-priority items go to their preferred zone first, displacing lower-priority
+/// items to other containers when needed; whatever is left goes anywhere it fits.
+/// `stored_items` describes the items in `initial`, so they can be weighed and moved.
+/// Runs without I/O: the API handler and `placement-cli` both plan through here.
+pub fn plan(
+    containers: &HashMap<String, Container>,
+    initial: &ContainerState,
+    stored_items: &[Item],
+    items: &[Item],
+) -> Result<Plan, PlanError> {
+    // Containers are always tried in the same order, so equal inputs give equal plans
+    let mut container_order: Vec<&String> = containers.keys().collect();
+    container_order.sort();
+
+    let mut sim_placements: ContainerState = initial.clone();
+    let mut final_placements: HashMap<String, PlacementResult> = HashMap::new();
+    for (container_id, placed) in initial {
+        for (item_id, position) in placed {
+            final_placements.insert(item_id.clone(), PlacementResult {
+                item_id: item_id.clone(),
+                container_id: container_id.clone(),
+                position: position.clone(),
+                retrieval_steps: 0, // Not used in response, placeholder
+                is_preferred_zone: false, // Placeholder
+                explanation: None,
+            });
+        }
+    }
+
+    // Combine item properties (priority, dimensions) for simulation lookup
+    // HashMap<ItemId, (Priority, Width, Depth, Height)>
+    let mut all_item_props: HashMap<String, (i32, f64, f64, f64)> = HashMap::new();
+    // Masses are tracked separately, for the containers' weight capacity
+    let mut item_masses: HashMap<String, f64> = HashMap::new();
+    for item in stored_items.iter().chain(items) {
+        // Incoming items come last, so their data wins if an ID somehow appears twice
+        if let Some(mass) = item.mass { item_masses.insert(item.item_id.clone(), mass); }
+        all_item_props.insert(item.item_id.clone(), (item.priority, item.width, item.depth, item.height));
+    }
+
+    let mut rearrangements_result: Vec<RearrangementStep> = Vec::new();
+    // Moves are carried out before anything new is loaded, so only these can hold a moved item up
+    let initially_placed: HashSet<String> = initial.values().flatten().map(|(id, _)| id.clone()).collect();
+    let mut processed_item_ids_in_request: HashSet<String> = HashSet::new();
+    let mut items_failed_completely: Vec<String> = Vec::new();
+    let mut sorted_incoming_items = items.to_vec();
+    // Built for every run (it is cheap) but only returned when the caller asks
+    let mut explanations: HashMap<String, PlacementExplanation> = items.iter()
+        .map(|i| (i.item_id.clone(), PlacementExplanation::new(PlacementPhase::NotPlaced, Some(i.preferred_zone.clone()))))
+        .collect();
+    sorted_incoming_items.sort_by_key(|i| std::cmp::Reverse(i.priority)); // Descending priority
+
+    // --- Phase 1: Initial Placement Attempt (Preferred Zones First) ---
+    let phase = info_span!("phase", phase = "preferred_zones").entered();
+    let mut items_requiring_placement_pass_2: Vec<Item> = Vec::new(); // Items for next phase
+
+    for item_req in &sorted_incoming_items {
+        if processed_item_ids_in_request.contains(&item_req.item_id) { continue; } // Already handled?
+
+        let _item = item_span(item_req).entered();
+        let mut placed = false;
+        let is_high_prio = item_req.priority >= config::placement().high_priority_threshold;
+
+        let preferred_container_ids: Vec<&String> = container_order.iter().copied()
+            .filter(|cid| containers[*cid].zone == item_req.preferred_zone)
+            .collect();
+
+        if preferred_container_ids.is_empty() {
+            debug!(outcome = "no_preferred_container", "no container in the preferred zone");
+        } else {
+            for container_id in preferred_container_ids {
+                let container = &containers[container_id];
+                let current_sim_placements_in_cont = sim_placements.get(container_id).map_or(vec![], |v| v.clone()); // Clone for find_spot
+
+                match try_container(item_req, container, &current_sim_placements_in_cont, &item_masses, is_high_prio) {
+                  Err(reason) => {
+                    debug!(container_id = %container_id, ?reason, "preferred container rejected the item");
+                    explanations.entry(item_req.item_id.clone()).and_modify(|e| e.reject(container, reason));
+                  }
+                  Ok(position) => {
+                    // Update Simulation State
+                    sim_placements.entry(container_id.clone()).or_default().push((item_req.item_id.clone(), position.clone()));
+                    // Update final results
+                    final_placements.insert(item_req.item_id.clone(), PlacementResult {
+                        item_id: item_req.item_id.clone(), container_id: container_id.clone(), position,
+                        retrieval_steps: 0, is_preferred_zone: true, explanation: None
+                    });
+                    processed_item_ids_in_request.insert(item_req.item_id.clone());
+                    debug!(container_id = %container_id, zone = %container.zone, outcome = "placed_preferred", "placed in preferred zone");
+                    explanations.entry(item_req.item_id.clone()).and_modify(|e| e.phase = PlacementPhase::PreferredZone);
+                    placed = true;
+                    break;
+                  }
+                }
+            }
+        }
+
+        if !placed {
+            debug!(outcome = "preferred_zone_full", "no free spot in the preferred zone");
+            items_requiring_placement_pass_2.push(item_req.clone());
+        }
+    }
+    drop(phase);
+
+
+    // --- Phase 2: Rearrangement Simulation ---
+    let phase = info_span!("phase", phase = "rearrangement").entered();
+    let mut items_requiring_placement_pass_3 = items_requiring_placement_pass_2; // Start with items needing placement
+    let mut rearrangement_step_counter = 0;
+    let mut made_rearrangement_in_iteration = true; // Loop control
+
+    while made_rearrangement_in_iteration {
+        made_rearrangement_in_iteration = false;
+        let mut items_still_needing_placement_after_iter: Vec<Item> = Vec::new();
+
+        // Evaluate highest priority items first
+        items_requiring_placement_pass_3.sort_by_key(|i| std::cmp::Reverse(i.priority));
+
+        for high_prio_item in &items_requiring_placement_pass_3 {
+            if processed_item_ids_in_request.contains(&high_prio_item.item_id) { continue; }
+
+            let _item = item_span(high_prio_item).entered();
+            let mut rearrangement_successful_for_this_item = false;
+
+            let preferred_container_ids: Vec<&String> = container_order.iter().copied()
+                .filter(|cid| containers[*cid].zone == high_prio_item.preferred_zone)
+                .collect();
+
+             if preferred_container_ids.is_empty() {
+                 debug!(outcome = "no_preferred_container", "nothing to rearrange without a preferred-zone container");
+                 items_still_needing_placement_after_iter.push(high_prio_item.clone());
+                 continue;
+             }
+
+            // Try direct placement again first
+            let mut placed_directly_in_phase2 = false;
+             for container_id in &preferred_container_ids {
+                 let container = &containers[*container_id];
+                 let current_sim_placements_in_cont = sim_placements.get(*container_id).map_or(vec![], |v| v.clone());
+                 match try_container(high_prio_item, container, &current_sim_placements_in_cont, &item_masses, true) {
+                  Err(reason) => {
+                     explanations.entry(high_prio_item.item_id.clone()).and_modify(|e| e.reject(container, reason));
+                  }
+                  Ok(position) => {
+                     sim_placements.entry((*container_id).clone()).or_default().push((high_prio_item.item_id.clone(), position.clone()));
+                     final_placements.insert(high_prio_item.item_id.clone(), PlacementResult {
+                         item_id: high_prio_item.item_id.clone(), container_id: (*container_id).clone(), position,
+                         retrieval_steps: 0, is_preferred_zone: true, explanation: None
+                     });
+                     processed_item_ids_in_request.insert(high_prio_item.item_id.clone());
+                     debug!(container_id = %container_id, zone = %container.zone, outcome = "placed_preferred", "placed in preferred zone after earlier moves");
+                     explanations.entry(high_prio_item.item_id.clone()).and_modify(|e| e.phase = PlacementPhase::PreferredZone);
+                     placed_directly_in_phase2 = true;
+                     made_rearrangement_in_iteration = true; // State changed
+                     break;
+                  }
+                 }
+             }
+             if placed_directly_in_phase2 { continue; } // Go to next high_prio_item
+
+
+            // Identify potential items to displace in preferred containers
+            let mut potential_displacees = vec![];
+            for container_id in &preferred_container_ids {
+                if let Some(current_sim_placements_in_cont) = sim_placements.get(*container_id) {
+                    for (existing_item_id, existing_pos) in current_sim_placements_in_cont {
+                        let existing_prio = all_item_props.get(existing_item_id).map_or(-1, |props| props.0);
+                        if existing_prio >= 0 && existing_prio < high_prio_item.priority {
+                            potential_displacees.push((existing_item_id.clone(), existing_prio, (*container_id).clone(), existing_pos.clone()));
+                        }
+                    }
+                }
+            }
+            potential_displacees.sort_by_key(|&(_, prio, _, _)| prio); // Sort by priority ASC
+            if !potential_displacees.is_empty() {
+                explanations.entry(high_prio_item.item_id.clone()).and_modify(|e| e.displacement_attempted = true);
+            }
+
+            if potential_displacees.is_empty() {
+                debug!(outcome = "nothing_to_displace", "preferred zone holds no lower-priority items");
+                items_still_needing_placement_after_iter.push(high_prio_item.clone());
+                continue;
+            }
+
+            // Try displacing items one by one
+            for (displacee_id, _, source_container_id, source_position) in potential_displacees {
+                debug!(displacee = %displacee_id, container_id = %source_container_id, "trying to displace lower-priority item");
+
+                // 1. Simulate removal
+                let mut temp_sim_placements_in_source = sim_placements.get(&source_container_id).map_or(vec![], |v| v.clone());
+                temp_sim_placements_in_source.retain(|(id, _)| id != &displacee_id);
+                if carried_by(&source_position, &temp_sim_placements_in_source).next().is_some() {
+                    debug!(displacee = %displacee_id, "other items rest on it");
+                    reject_displacee(&mut explanations, &high_prio_item.item_id, &displacee_id, &source_container_id, DisplaceeRejection::CarriesLoad);
+                    continue;
+                }
+
+                // 2. Check if high-prio item fits now
+                let source_container = &containers[&source_container_id];
+                if let Ok(position_high_prio) = try_container(high_prio_item, source_container, &temp_sim_placements_in_source, &item_masses, true) {
+                     // 3. Try to find NEW home for displacee
+                    let Some(&(_, width, depth, height)) = all_item_props.get(&displacee_id) else {
+                        error!("Cannot find props for displacee {}. Skipping displacement.", displacee_id);
+                        continue;
+                    };
+                    let displacee_dims = (width, depth, height);
+                    let mut relocated = false;
+
+                    for target_container_id in container_order.iter().copied() {
+                        if target_container_id == &source_container_id { continue; } // Don't try same container
+
+                        let target_container = &containers[target_container_id];
+                        let current_sim_placements_in_target = sim_placements.get(target_container_id).map_or(vec![], |v| v.clone());
+                        if exceeds_capacity(target_container, &current_sim_placements_in_target, &item_masses, item_masses.get(&displacee_id).copied().unwrap_or(0.0)) {
+                            continue;
+                        }
+
+                        if let Some(spot_for_displacee) = find_spot_in_container(
+                            displacee_dims, target_container, &current_sim_placements_in_target, false) // Low prio placement
+                        {
+                            let (new_position_displacee, _) = spot_for_displacee;
+                            let stored_in_target: Vec<(String, Position)> = current_sim_placements_in_target.iter()
+                                .filter(|(id, _)| initially_placed.contains(id))
+                                .cloned()
+                                .collect();
+                            if !is_supported(&new_position_displacee, &stored_in_target) {
+                                debug!(displacee = %displacee_id, container_id = %target_container_id, "spot rests on an item not loaded yet");
+                                continue;
+                            }
+
+                            // 4. Commit simulation changes: remove displacee from its old spot and
+                            // put the high-prio item in the freed one
+                            let Some(source_placements) = sim_placements.get_mut(&source_container_id) else { continue };
+                            source_placements.retain(|(id, _)| id != &displacee_id);
+                            source_placements.push((high_prio_item.item_id.clone(), position_high_prio.clone()));
+                            sim_placements.entry(target_container_id.clone()).or_default().push((displacee_id.clone(), new_position_displacee.clone()));
+
+                            rearrangement_step_counter += 1;
+                            rearrangements_result.push(RearrangementStep {
+                                step: rearrangement_step_counter, action: "move".to_string(), item_id: displacee_id.clone(),
+                                from_container: Some(source_container_id.clone()), from_position: Some(source_position.clone()),
+                                to_container: Some(target_container_id.clone()), to_position: Some(new_position_displacee.clone())
+                            });
+
+                            // Update final response for displaced item
+                            final_placements.insert(displacee_id.clone(), PlacementResult {
+                                item_id: displacee_id.clone(), container_id: target_container_id.clone(), position: new_position_displacee,
+                                retrieval_steps: 0, is_preferred_zone: false, explanation: None // Zone check needed if API requires it
+                            });
+
+                             // Update final response for high-prio item
+                             final_placements.insert(high_prio_item.item_id.clone(), PlacementResult {
+                                item_id: high_prio_item.item_id.clone(), container_id: source_container_id.clone(), position: position_high_prio,
+                                retrieval_steps: 0, is_preferred_zone: true, explanation: None
+                            });
+
+                            processed_item_ids_in_request.insert(high_prio_item.item_id.clone());
+                            explanations.entry(high_prio_item.item_id.clone()).and_modify(|e| e.phase = PlacementPhase::Rearrangement);
+                            let displacee_explanation = explanations.entry(displacee_id.clone())
+                                .or_insert_with(|| PlacementExplanation::new(PlacementPhase::Existing, None));
+                            displacee_explanation.phase = PlacementPhase::Displaced;
+                            displacee_explanation.displaced_for = Some(high_prio_item.item_id.clone());
+                            rearrangement_successful_for_this_item = true;
+                            made_rearrangement_in_iteration = true; // State changed
+                            info!(
+                                container_id = %source_container_id,
+                                zone = %source_container.zone,
+                                displacee = %displacee_id,
+                                displacee_to = %target_container_id,
+                                displacee_to_zone = %target_container.zone,
+                                outcome = "placed_after_rearrangement",
+                                "moved a lower-priority item out of the preferred zone to make room",
+                            );
+                            relocated = true;
+                            break; // Stop trying to relocate this specific displacee
+                        }
+                    }
+                    if !relocated {
+                        debug!(displacee = %displacee_id, "no other container can take the displaced item");
+                        reject_displacee(&mut explanations, &high_prio_item.item_id, &displacee_id, &source_container_id, DisplaceeRejection::NoNewHome);
+                        // Don't commit simulation changes, loop continues to next displacee
+                    }
+
+                } else {
+                     debug!(displacee = %displacee_id, "removing it would not free enough space");
+                     reject_displacee(&mut explanations, &high_prio_item.item_id, &displacee_id, &source_container_id, DisplaceeRejection::NoSpaceFreed);
+                }
+
+                if rearrangement_successful_for_this_item {
+                    break; // Stop trying to displace other items for this high_prio_item
+                }
+            } // End loop through potential displacees
+
+            // If no successful rearrangement occurred for this item in this iteration
+            if !rearrangement_successful_for_this_item {
+                 debug!(outcome = "rearrangement_failed", "no displacement makes room in the preferred zone");
+                 items_still_needing_placement_after_iter.push(high_prio_item.clone());
+            }
+        } // End loop through items needing placement in this iteration
+
+        items_requiring_placement_pass_3 = items_still_needing_placement_after_iter;
+    } // End while made_rearrangement_in_iteration
+    drop(phase);
+
+
+    // --- Phase 3: Final Placement Attempt (Anywhere) ---
+    let phase = info_span!("phase", phase = "any_zone").entered();
+    for item_req in &items_requiring_placement_pass_3 { // Use items remaining after Phase 2
+        if processed_item_ids_in_request.contains(&item_req.item_id) { continue; }
+
+        let _item = item_span(item_req).entered();
+        let mut placed = false;
+        let is_high_prio = item_req.priority >= config::placement().high_priority_threshold;
+
+        for container_id in container_order.iter().copied() {
+            let container = &containers[container_id];
+            let current_sim_placements_in_cont = sim_placements.get(container_id).map_or(vec![], |v| v.clone());
+
+            match try_container(item_req, container, &current_sim_placements_in_cont, &item_masses, is_high_prio) {
+              Err(reason) => {
+                explanations.entry(item_req.item_id.clone()).and_modify(|e| e.reject(container, reason));
+              }
+              Ok(position) => {
+                sim_placements.entry(container_id.clone()).or_default().push((item_req.item_id.clone(), position.clone()));
+                final_placements.insert(item_req.item_id.clone(), PlacementResult {
+                    item_id: item_req.item_id.clone(), container_id: container_id.clone(), position,
+                    retrieval_steps: 0, is_preferred_zone: container.zone == item_req.preferred_zone, explanation: None
+                });
+                processed_item_ids_in_request.insert(item_req.item_id.clone());
+                let outcome = if container.zone == item_req.preferred_zone { "placed_preferred" } else { "placed_other_zone" };
+                info!(container_id = %container_id, zone = %container.zone, outcome, "placed after the preferred zone was exhausted");
+                explanations.entry(item_req.item_id.clone()).and_modify(|e| e.phase = PlacementPhase::AnyZone);
+                placed = true;
+                break;
+              }
+            }
+        }
+
+        if !placed {
+            warn!(outcome = "failed", "no container has a free spot for the item");
+            explanations.entry(item_req.item_id.clone()).and_modify(|e| e.phase = PlacementPhase::NotPlaced);
+            items_failed_completely.push(item_req.item_id.clone());
+            processed_item_ids_in_request.insert(item_req.item_id.clone()); // Mark as processed (failed)
+            // Remove from final response map if it was somehow added
+            final_placements.remove(&item_req.item_id);
+        }
+    }
+    drop(phase);
+
+    // Moves of items that are only being placed by this run are not physical moves;
+    // those items go straight to their final spot.
+    rearrangements_result.retain(|s| initially_placed.contains(&s.item_id));
+    let rearrangements = rearrangement::sequence_plan(initial, &rearrangements_result)?;
+
+    Ok(Plan {
+        placements: final_placements,
+        rearrangements,
+        failed: items_failed_completely,
+        explanations,
+    })
+}
+
+impl Plan {
+    /// Splits the plan into the placements to report and the items left out. With
+    /// `explain`, each carries the reasoning behind it; otherwise `unplaced` is empty.
+    pub fn into_results(mut self, explain: bool) -> (Vec<PlacementResult>, Vec<UnplacedItem>) {
+        let mut placements: Vec<PlacementResult> = self.placements.into_values().collect();
+        let mut unplaced = Vec::new();
+        if explain {
+            for result in &mut placements {
+                result.explanation = Some(self.explanations.remove(&result.item_id)
+                    .unwrap_or_else(|| PlacementExplanation::new(PlacementPhase::Existing, None)));
+            }
+            for item_id in &self.failed {
+                if let Some(explanation) = self.explanations.remove(item_id) {
+                    unplaced.push(UnplacedItem { item_id: item_id.clone(), explanation });
+                }
+            }
+        }
+        (placements, unplaced)
+    }
+}
+
+// Weight first: a container at capacity rejects the item whatever the geometry
+fn try_container(
+    item: &Item,
+    container: &Container,
+    placed: &[(String, Position)],
+    masses: &HashMap<String, f64>,
+    is_high_priority: bool,
+) -> Result<Position, RejectionReason> {
+    if exceeds_capacity(container, placed, masses, item.mass.unwrap_or(0.0)) {
+        return Err(RejectionReason::Weight);
+    }
+    search_spot((item.width, item.depth, item.height), container, placed, is_high_priority).map(|(position, _)| position)
+}
+
+fn reject_displacee(
+    explanations: &mut HashMap<String, PlacementExplanation>,
+    item_id: &str,
+    displacee_id: &str,
+    container_id: &str,
+    reason: DisplaceeRejection,
+) {
+    if let Some(explanation) = explanations.get_mut(item_id) {
+        explanation.rejected_displacees.retain(|r| r.item_id != displacee_id);
+        explanation.rejected_displacees.push(RejectedDisplacee { item_id: displacee_id.to_string(), container_id: container_id.to_string(), reason });
+    }
+}
+
+// Every event about one item in a placement run carries these fields
+fn item_span(item: &Item) -> Span {
+    info_span!("item", item_id = %item.item_id, priority = item.priority, preferred_zone = %item.preferred_zone)
+}
@@ -0,0 +1,315 @@
+use crate::config::tolerance;
+use crate::models::*;
+use crate::simulation::{boxes_overlap, carried_by, is_supported, obstructs, rests_on, ContainerState};
+use tracing::debug;
+use std::collections::HashSet;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PlanError {
+    #[error("Step {step}: action '{action}' is not supported")]
+    UnsupportedAction { step: i32, action: String },
+    #[error("Step {step}: item '{item_id}' is missing its container or position")]
+    IncompleteStep { step: i32, item_id: String },
+    #[error("Step {step}: item '{item_id}' is not at the stated position in container '{container_id}'")]
+    ItemNotAtSource { step: i32, item_id: String, container_id: String },
+    #[error("Step {step}: destination of item '{item_id}' in container '{container_id}' overlaps item '{other_id}'")]
+    DestinationOccupied { step: i32, item_id: String, container_id: String, other_id: String },
+    #[error("Step {step}: item '{item_id}' would rest on nothing in container '{container_id}'")]
+    Unsupported { step: i32, item_id: String, container_id: String },
+    #[error("Step {step}: item '{other_id}' still rests on item '{item_id}'")]
+    CarriesLoad { step: i32, item_id: String, other_id: String },
+    #[error("Steps {steps:?} wait on each other's destinations and cannot be ordered")]
+    CircularDependency { steps: Vec<i32> },
+}
+
+pub fn same_position(a: &Position, b: &Position) -> bool {
+    (a.start_coordinates.width - b.start_coordinates.width).abs() < tolerance() &&
+    (a.start_coordinates.depth - b.start_coordinates.depth).abs() < tolerance() &&
+    (a.start_coordinates.height - b.start_coordinates.height).abs() < tolerance() &&
+    (a.end_coordinates.width - b.end_coordinates.width).abs() < tolerance() &&
+    (a.end_coordinates.depth - b.end_coordinates.depth).abs() < tolerance() &&
+    (a.end_coordinates.height - b.end_coordinates.height).abs() < tolerance()
+}
+
+/// Every item in `items` that must be taken out before `target` is reachable,
+/// including the items blocking those items and anything resting only on them.
+fn obstruction_closure(
+    items: &[(String, Position)],
+    target: &Position,
+    exclude: &HashSet<String>,
+) -> Vec<(String, Position)> {
+    let mut found: Vec<(String, Position)> = Vec::new();
+    let mut frontier = vec![target.clone()];
+    loop {
+        while let Some(pos) = frontier.pop() {
+            for (id, other) in items {
+                if exclude.contains(id) || found.iter().any(|(f, _)| f == id) { continue; }
+                if obstructs(other, &pos) {
+                    found.push((id.clone(), other.clone()));
+                    frontier.push(other.clone());
+                }
+            }
+        }
+
+        // A load cannot stay behind in mid-air once what it rests on is out
+        let left: Vec<(String, Position)> = items.iter()
+            .filter(|(id, _)| !exclude.contains(id) && !found.iter().any(|(f, _)| f == id))
+            .cloned()
+            .collect();
+        let loose: Vec<(String, Position)> = left.iter()
+            .filter(|(_, p)| !is_supported(p, &left) && is_supported(p, items))
+            .cloned()
+            .collect();
+        if loose.is_empty() {
+            return found;
+        }
+        for (id, pos) in loose {
+            frontier.push(pos.clone());
+            found.push((id, pos));
+        }
+    }
+}
+
+/// Orders boxes so each one goes in after everything behind it and everything it
+/// rests on; reversed, the same order takes them out. Ties go deepest-first.
+fn placement_order<T>(mut boxes: Vec<T>, key: impl Fn(&T) -> (&str, &Position)) -> Vec<T> {
+    boxes.sort_by(|a, b| key(b).1.start_coordinates.depth.partial_cmp(&key(a).1.start_coordinates.depth).unwrap_or(std::cmp::Ordering::Equal));
+    let mut ordered = Vec::with_capacity(boxes.len());
+    while !boxes.is_empty() {
+        let waits = |i: usize| {
+            let (container_id, pos) = key(&boxes[i]);
+            boxes.iter().enumerate().any(|(j, other)| {
+                let (other_container, other_pos) = key(other);
+                j != i && other_container == container_id && (obstructs(pos, other_pos) || rests_on(pos, other_pos))
+            })
+        };
+        // Boxes cannot block or carry each other in a cycle, but never loop if they seem to
+        let next = (0..boxes.len()).find(|&i| !waits(i)).unwrap_or(0);
+        ordered.push(boxes.remove(next));
+    }
+    ordered
+}
+
+fn step(action: &str, item_id: &str, from: Option<(&str, &Position)>, to: Option<(&str, &Position)>) -> RearrangementStep {
+    RearrangementStep {
+        step: 0, // Renumbered once the whole sequence is known
+        action: action.to_string(),
+        item_id: item_id.to_string(),
+        from_container: from.map(|(c, _)| c.to_string()),
+        from_position: from.map(|(_, p)| p.clone()),
+        to_container: to.map(|(c, _)| c.to_string()),
+        to_position: to.map(|(_, p)| p.clone()),
+    }
+}
+
+// Whether `plan_step` takes `item_id` out of `position` in `container_id`
+fn vacates(plan_step: &RearrangementStep, item_id: &str, container_id: &str, position: &Position) -> bool {
+    plan_step.item_id == item_id
+        && plan_step.from_container.as_deref() == Some(container_id)
+        && plan_step.from_position.as_ref().is_some_and(|from| same_position(from, position))
+}
+
+/// Checks the source and destination of `plan_step` against `state`.
+/// Returns `Ok(None)` when the step can run now, `Ok(Some(id))` when the
+/// destination is held by an item that one of the `pending` steps moves away.
+fn check_step(
+    state: &ContainerState,
+    plan_step: &RearrangementStep,
+    pending: &[&RearrangementStep],
+) -> Result<Option<String>, PlanError> {
+    let from = match (&plan_step.from_container, &plan_step.from_position) {
+        (Some(c), Some(p)) => Some((c, p)),
+        (None, None) => None,
+        _ => return Err(PlanError::IncompleteStep { step: plan_step.step, item_id: plan_step.item_id.clone() }),
+    };
+    let to = match (&plan_step.to_container, &plan_step.to_position) {
+        (Some(c), Some(p)) => Some((c, p)),
+        (None, None) => None,
+        _ => return Err(PlanError::IncompleteStep { step: plan_step.step, item_id: plan_step.item_id.clone() }),
+    };
+    let expected = match plan_step.action.as_str() {
+        "move" => from.is_some() && to.is_some(),
+        "place" => from.is_none() && to.is_some(),
+        "remove" => from.is_some() && to.is_none(),
+        _ => return Err(PlanError::UnsupportedAction { step: plan_step.step, action: plan_step.action.clone() }),
+    };
+    if !expected {
+        return Err(PlanError::IncompleteStep { step: plan_step.step, item_id: plan_step.item_id.clone() });
+    }
+
+    if let Some((container_id, position)) = from {
+        let present = state.get(container_id).is_some_and(|items| {
+            items.iter().any(|(id, p)| id == &plan_step.item_id && same_position(p, position))
+        });
+        if !present {
+            return Err(PlanError::ItemNotAtSource {
+                step: plan_step.step, item_id: plan_step.item_id.clone(), container_id: container_id.clone(),
+            });
+        }
+
+        // Whatever rests on the item has to be moved off it first
+        let others = without(state, container_id, &plan_step.item_id);
+        let load = carried_by(position, &others).next().cloned();
+        if let Some((load_id, load_pos)) = load {
+            if pending.iter().any(|s| vacates(s, &load_id, container_id, &load_pos)) {
+                return Ok(Some(load_id));
+            }
+            return Err(PlanError::CarriesLoad { step: plan_step.step, item_id: plan_step.item_id.clone(), other_id: load_id });
+        }
+    }
+
+    if let Some((container_id, position)) = to {
+        for (other_id, other_pos) in state.get(container_id).map_or(&[][..], |v| &v[..]) {
+            if other_id == &plan_step.item_id || !boxes_overlap(&other_pos.start_coordinates, &other_pos.end_coordinates, &position.start_coordinates, &position.end_coordinates) { continue; }
+            if pending.iter().any(|s| vacates(s, other_id, container_id, other_pos)) {
+                return Ok(Some(other_id.clone()));
+            }
+            return Err(PlanError::DestinationOccupied {
+                step: plan_step.step, item_id: plan_step.item_id.clone(),
+                container_id: container_id.clone(), other_id: other_id.clone(),
+            });
+        }
+
+        if !is_supported(position, &without(state, container_id, &plan_step.item_id)) {
+            let support = pending.iter().find(|s| {
+                s.to_container.as_ref() == Some(container_id) && s.to_position.as_ref().is_some_and(|p| rests_on(position, p))
+            });
+            if let Some(support) = support {
+                return Ok(Some(support.item_id.clone()));
+            }
+            return Err(PlanError::Unsupported {
+                step: plan_step.step, item_id: plan_step.item_id.clone(), container_id: container_id.clone(),
+            });
+        }
+    }
+    Ok(None)
+}
+
+// The items of `container_id` other than `item_id`
+fn without(state: &ContainerState, container_id: &str, item_id: &str) -> Vec<(String, Position)> {
+    state.get(container_id).map_or(&[][..], |v| &v[..]).iter()
+        .filter(|(id, _)| id != item_id)
+        .cloned()
+        .collect()
+}
+
+// Puts `item_id` at `position`, which has to rest on the floor or an item by now
+fn put(state: &mut ContainerState, plan_step: &RearrangementStep, container_id: &str, item_id: &str, position: &Position) -> Result<(), PlanError> {
+    let items = state.entry(container_id.to_string()).or_default();
+    if !is_supported(position, items) {
+        return Err(PlanError::Unsupported { step: plan_step.step, item_id: item_id.to_string(), container_id: container_id.to_string() });
+    }
+    items.push((item_id.to_string(), position.clone()));
+    Ok(())
+}
+
+/// Expands one plan step into the physical sub-steps needed to carry it out and
+/// applies them to `state`.
+fn execute_step(state: &mut ContainerState, plan_step: &RearrangementStep, out: &mut Vec<RearrangementStep>) -> Result<(), PlanError> {
+    let item_id = &plan_step.item_id;
+    let from = plan_step.from_container.as_ref().zip(plan_step.from_position.as_ref());
+    let to = plan_step.to_container.as_ref().zip(plan_step.to_position.as_ref());
+
+    let mut exclude: HashSet<String> = HashSet::from([item_id.clone()]);
+    // (containerId, itemId, original position) of everything taken out temporarily
+    let mut set_aside: Vec<(String, String, Position)> = Vec::new();
+    for (container_id, target) in from.iter().chain(to.iter()) {
+        let items = state.get(*container_id).map_or(&[][..], |v| &v[..]);
+        for (id, pos) in obstruction_closure(items, target, &exclude) {
+            exclude.insert(id.clone());
+            set_aside.push(((*container_id).clone(), id, pos));
+        }
+    }
+
+    // Take blockers out front-first and top-first; put them back the other way round
+    let mut set_aside = placement_order(set_aside, |(c, _, pos)| (c, pos));
+    set_aside.reverse();
+    for (container_id, id, pos) in &set_aside {
+        out.push(step("remove", id, Some((container_id, pos)), None));
+        if let Some(items) = state.get_mut(container_id) {
+            items.retain(|(other, _)| other != id);
+        }
+    }
+
+    if let Some((container_id, _)) = from {
+        if let Some(items) = state.get_mut(container_id) {
+            items.retain(|(other, _)| other != item_id);
+        }
+    }
+
+    let Some((to_container, to_position)) = to else {
+        out.push(plan_step.clone());
+        for (container_id, id, pos) in set_aside.iter().rev() {
+            out.push(step("placeBack", id, None, Some((container_id, pos))));
+            put(state, plan_step, container_id, id, pos)?;
+        }
+        return Ok(());
+    };
+
+    // If the destination is in front of or on top of something that has to go back,
+    // the item is held aside and placed in order together with the blockers.
+    let held = set_aside.iter().any(|(c, _, pos)| c == to_container && (obstructs(to_position, pos) || rests_on(to_position, pos)));
+    if !held {
+        out.push(plan_step.clone());
+        put(state, plan_step, to_container, item_id, to_position)?;
+        for (container_id, id, pos) in set_aside.iter().rev() {
+            out.push(step("placeBack", id, None, Some((container_id, pos))));
+            put(state, plan_step, container_id, id, pos)?;
+        }
+        return Ok(());
+    }
+
+    if let Some((container_id, position)) = from {
+        out.push(step("remove", item_id, Some((container_id, position)), None));
+    }
+    let mut put_back: Vec<(String, String, Position, bool)> = set_aside.into_iter()
+        .map(|(c, id, pos)| (c, id, pos, false))
+        .collect();
+    put_back.push((to_container.clone(), item_id.clone(), to_position.clone(), true));
+    for (container_id, id, pos, is_target) in placement_order(put_back, |(c, _, pos, _)| (c, pos)) {
+        let action = if is_target { "place" } else { "placeBack" };
+        out.push(step(action, &id, None, Some((&container_id, &pos))));
+        put(state, plan_step, &container_id, &id, &pos)?;
+    }
+    Ok(())
+}
+
+/// Replays `plan` against `initial`, reordering steps whose destination is only
+/// freed by a later step and inserting the `remove`/`placeBack` sub-steps needed
+/// to reach buried items. Returns the executable, renumbered sequence.
+pub fn sequence_plan(initial: &ContainerState, plan: &[RearrangementStep]) -> Result<Vec<RearrangementStep>, PlanError> {
+    let mut state = initial.clone();
+    let mut pending: Vec<&RearrangementStep> = plan.iter().collect();
+    let mut sequenced: Vec<RearrangementStep> = Vec::new();
+
+    while !pending.is_empty() {
+        let mut executed = None;
+        for idx in 0..pending.len() {
+            // Steps for the same item keep their relative order
+            if pending[..idx].iter().any(|s| s.item_id == pending[idx].item_id) { continue; }
+
+            let others: Vec<&RearrangementStep> = pending.iter().enumerate()
+                .filter(|(i, _)| *i != idx)
+                .map(|(_, s)| *s)
+                .collect();
+            match check_step(&state, pending[idx], &others)? {
+                None => { executed = Some(idx); break; }
+                Some(waiting_on) => debug!("Deferring step {} ({}): waiting on {}", pending[idx].step, pending[idx].item_id, waiting_on),
+            }
+        }
+
+        match executed {
+            Some(idx) => {
+                let plan_step = pending.remove(idx);
+                execute_step(&mut state, plan_step, &mut sequenced)?;
+            }
+            None => return Err(PlanError::CircularDependency { steps: pending.iter().map(|s| s.step).collect() }),
+        }
+    }
+
+    for (i, s) in sequenced.iter_mut().enumerate() {
+        s.step = i as i32 + 1;
+    }
+    Ok(sequenced)
+}
@@ -0,0 +1,88 @@
+use crate::models::{Container, Item, PlacementResult, RearrangementStep};
+use crate::simulation::{retrieval_steps, ContainerState};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::Duration;
+
+/// Figures that describe how good a placement run was, independent of the
+/// strategy that produced it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub strategy: String,
+    pub items: usize,
+    pub placed: usize,
+    pub failed: usize,
+    // Stored items moved to make room
+    pub rearranged: usize,
+    // Share of placed items that ended up in their preferred zone
+    #[serde(rename = "preferredZoneRate")]
+    pub preferred_zone_rate: f64,
+    // Placed volume over container volume, overall and per zone
+    #[serde(rename = "volumeUtilisation")]
+    pub volume_utilisation: f64,
+    #[serde(rename = "zoneUtilisation")]
+    pub zone_utilisation: BTreeMap<String, f64>,
+    // Items that have to come out before a placed item can, on average
+    #[serde(rename = "meanRetrievalSteps")]
+    pub mean_retrieval_steps: f64,
+    #[serde(rename = "durationMs")]
+    pub duration_ms: f64,
+}
+
+fn volume(width: f64, depth: f64, height: f64) -> f64 {
+    width * depth * height
+}
+
+fn ratio(part: f64, whole: f64) -> f64 {
+    if whole > 0.0 { part / whole } else { 0.0 }
+}
+
+/// Summarises a run that placed `items` into `containers`. `placements` is the
+/// final arrangement, including items that were already there.
+pub fn summarize(
+    strategy: &str,
+    containers: &[Container],
+    items: &[Item],
+    placements: &[PlacementResult],
+    rearrangements: &[RearrangementStep],
+    duration: Duration,
+) -> RunSummary {
+    let zone_of: HashMap<&String, &String> = containers.iter().map(|c| (&c.container_id, &c.zone)).collect();
+    let incoming: HashMap<&String, &Item> = items.iter().map(|i| (&i.item_id, i)).collect();
+
+    let mut state: ContainerState = HashMap::new();
+    for p in placements {
+        state.entry(p.container_id.clone()).or_default().push((p.item_id.clone(), p.position.clone()));
+    }
+
+    let mut capacity: BTreeMap<String, f64> = BTreeMap::new();
+    for c in containers {
+        *capacity.entry(c.zone.clone()).or_default() += volume(c.width, c.depth, c.height);
+    }
+    let mut used: BTreeMap<String, f64> = BTreeMap::new();
+    let (mut placed, mut in_preferred_zone, mut steps) = (0, 0, 0);
+    for p in placements {
+        let Some(zone) = zone_of.get(&p.container_id) else { continue };
+        let (s, e) = (&p.position.start_coordinates, &p.position.end_coordinates);
+        *used.entry((*zone).clone()).or_default() += volume(e.width - s.width, e.depth - s.depth, e.height - s.height);
+        steps += retrieval_steps(&p.position, &state[&p.container_id]);
+        if let Some(item) = incoming.get(&p.item_id) {
+            placed += 1;
+            if **zone == item.preferred_zone { in_preferred_zone += 1; }
+        }
+    }
+
+    let rearranged: HashSet<&String> = rearrangements.iter().map(|s| &s.item_id).collect();
+    RunSummary {
+        strategy: strategy.to_string(),
+        items: items.len(),
+        placed,
+        failed: items.len() - placed,
+        rearranged: rearranged.len(),
+        preferred_zone_rate: ratio(in_preferred_zone as f64, placed as f64),
+        volume_utilisation: ratio(used.values().sum(), capacity.values().sum()),
+        zone_utilisation: capacity.iter().map(|(zone, cap)| (zone.clone(), ratio(used.get(zone).copied().unwrap_or(0.0), *cap))).collect(),
+        mean_retrieval_steps: ratio(steps as f64, placements.len() as f64),
+        duration_ms: duration.as_secs_f64() * 1000.0,
+    }
+}
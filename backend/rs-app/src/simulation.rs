@@ -0,0 +1,180 @@
+use crate::config;
+use crate::models::*;
+use std::collections::HashMap;
+
+// Simulation state: containerId -> [(itemId, Position)]
+pub type ContainerState = HashMap<String, Vec<(String, Position)>>;
+
+// ==============================================================================
+// == Placement Simulation Helpers (shared by handlers and planners) ============
+// ==============================================================================
+
+pub fn boxes_overlap(
+    start1: &Coordinates, end1: &Coordinates, start2: &Coordinates, end2: &Coordinates
+) -> bool {
+    let tol = config::placement().tolerance;
+    let no_overlap_w = end1.width <= start2.width + tol || end2.width <= start1.width + tol;
+    let no_overlap_d = end1.depth <= start2.depth + tol || end2.depth <= start1.depth + tol;
+    let no_overlap_h = end1.height <= start2.height + tol || end2.height <= start1.height + tol;
+    ! (no_overlap_w || no_overlap_d || no_overlap_h)
+}
+
+// `blocking` sits between the open face (depth 0) and `target`, so it has to come out first
+pub fn obstructs(blocking: &Position, target: &Position) -> bool {
+    let tol = config::placement().tolerance;
+    blocking.start_coordinates.depth + tol < target.start_coordinates.depth &&
+    !(blocking.end_coordinates.width <= target.start_coordinates.width + tol ||
+      target.end_coordinates.width <= blocking.start_coordinates.width + tol ||
+      blocking.end_coordinates.height <= target.start_coordinates.height + tol ||
+      target.end_coordinates.height <= blocking.start_coordinates.height + tol)
+}
+
+// Number of items that have to be removed before `target` can be taken out
+pub fn retrieval_steps(target: &Position, placements_in_container: &[(String, Position)]) -> usize {
+    placements_in_container.iter().filter(|(_, p)| obstructs(p, target)).count()
+}
+
+// Find spot function adapted for Rust, using API models for simulation
+pub fn find_spot_in_container(
+    item_dims: (f64, f64, f64),    // Width, Depth, Height of the item being placed
+    container: &Container,         // Container dimensions (API model)
+    current_placements_in_container: &[(String, Position)], // Current simulation state (itemId, Position)
+    is_high_priority: bool
+) -> Option<(Position, (f64, f64, f64))> { // Returns (Position, orientation_used)
+    search_spot(item_dims, container, current_placements_in_container, is_high_priority).ok()
+}
+
+/// Same search as [`find_spot_in_container`], but says why nothing was found.
+pub fn search_spot(
+    item_dims: (f64, f64, f64),
+    container: &Container,
+    current_placements_in_container: &[(String, Position)],
+    is_high_priority: bool
+) -> Result<(Position, (f64, f64, f64)), RejectionReason> {
+
+    let tuning = config::placement();
+    let tol = tuning.tolerance;
+    let item_w = item_dims.0;
+    let item_d = item_dims.1;
+    let item_h = item_dims.2;
+
+    let orientations = [
+        (item_w, item_d, item_h), (item_w, item_h, item_d),
+        (item_d, item_w, item_h), (item_d, item_h, item_w),
+        (item_h, item_w, item_d), (item_h, item_d, item_w),
+    ];
+
+    // What the closest candidate got past, for the rejection reason
+    let mut fits_some_orientation = false;
+    let mut found_free_spot = false;
+
+    for (w, d, h) in orientations {
+        if w > container.width + tol || d > container.depth + tol || h > container.height + tol {
+            continue;
+        }
+        fits_some_orientation = true;
+
+        // Simplified grid search strategy
+        let width_increment = (container.width / tuning.grid_divisions as f64).max(tuning.min_grid_step);
+        let depth_increment = (container.depth / tuning.grid_divisions as f64).max(tuning.min_grid_step);
+
+        let mut search_depths: Vec<f64> = (0..=( (container.depth / depth_increment).floor() as i32 + 1))
+                                            .map(|i| (i as f64 * depth_increment).min(container.depth - d).max(0.0))
+                                            .collect();
+        search_depths.dedup_by(|a, b| (*a - *b).abs() < tol);
+        if !is_high_priority { search_depths.reverse(); } // Low prio tries deep spots first
+
+
+        let mut possible_base_heights = vec![0.0];
+        possible_base_heights.extend(current_placements_in_container.iter().map(|(_, p)| p.end_coordinates.height));
+        possible_base_heights.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        possible_base_heights.dedup_by(|a, b| (*a - *b).abs() < tol);
+
+
+        for start_h_base in possible_base_heights {
+            let start_h = start_h_base; // Precision applied later if needed
+            if start_h + h > container.height + tol { continue; }
+
+            for start_d in &search_depths {
+                let start_d = *start_d;
+                if start_d + d > container.depth + tol { continue; }
+
+                 let mut search_widths: Vec<f64> = (0..=( (container.width / width_increment).floor() as i32 + 1))
+                                            .map(|i| (i as f64 * width_increment).min(container.width - w).max(0.0))
+                                            .collect();
+                search_widths.dedup_by(|a, b| (*a - *b).abs() < tol);
+
+                for start_w in search_widths {
+                     if start_w + w > container.width + tol { continue; }
+
+                    let start_coords = Coordinates { width: start_w, depth: start_d, height: start_h };
+                    let end_coords = Coordinates {
+                        width: start_w + w,
+                        depth: start_d + d,
+                        height: start_h + h,
+                    };
+                    let candidate_position = Position { start_coordinates: start_coords.clone(), end_coordinates: end_coords.clone() };
+
+                    // 1. Boundary Check (redundant if start calc is correct, but good safety)
+                     if end_coords.width > container.width + tol || end_coords.depth > container.depth + tol || end_coords.height > container.height + tol {
+                         continue;
+                     }
+
+                    // 2. Overlap Check
+                    let mut overlaps = false;
+                    for (_, existing_pos) in current_placements_in_container {
+                        if boxes_overlap(&start_coords, &end_coords, &existing_pos.start_coordinates, &existing_pos.end_coordinates) {
+                            overlaps = true;
+                            break;
+                        }
+                    }
+                    if overlaps { continue; }
+                    found_free_spot = true;
+
+                    // 3. Stability Check (Simplified)
+                    if !is_supported(&candidate_position, current_placements_in_container) { continue; } // Skip floating positions
+
+                    // All checks passed
+                    return Ok((candidate_position, (w, d, h)));
+                }
+            }
+        }
+    }
+    // No spot found
+    Err(if !fits_some_orientation {
+        RejectionReason::NoFit
+    } else if found_free_spot {
+        RejectionReason::Unsupported
+    } else {
+        RejectionReason::Overlap
+    })
+}
+
+// `upper` sits on the top face of `lower`, with some of its footprint over it
+pub fn rests_on(upper: &Position, lower: &Position) -> bool {
+    let tol = config::placement().tolerance;
+    let (start, end) = (&upper.start_coordinates, &upper.end_coordinates);
+    (lower.end_coordinates.height - start.height).abs() < tol &&
+    !(end.width <= lower.start_coordinates.width + tol || lower.end_coordinates.width <= start.width + tol ||
+      end.depth <= lower.start_coordinates.depth + tol || lower.end_coordinates.depth <= start.depth + tol)
+}
+
+// Resting on the floor or on the top face of some item below it
+pub fn is_supported(position: &Position, placements_in_container: &[(String, Position)]) -> bool {
+    position.start_coordinates.height.abs() < config::placement().tolerance ||
+    placements_in_container.iter().any(|(_, p)| rests_on(position, p))
+}
+
+// Items of `others` that rest on `position` and nothing else, so they would be
+// left floating if the item there were taken away
+pub fn carried_by<'a>(position: &'a Position, others: &'a [(String, Position)]) -> impl Iterator<Item = &'a (String, Position)> {
+    others.iter().filter(move |(_, p)| rests_on(p, position) && !is_supported(p, others))
+}
+
+/// Whether adding `item_mass` would take `container` past its weight capacity.
+/// Items without a known mass count as weightless.
+pub fn exceeds_capacity(container: &Container, placed: &[(String, Position)], masses: &HashMap<String, f64>, item_mass: f64) -> bool {
+    let Some(capacity) = container.max_weight_capacity else { return false };
+    let held: f64 = placed.iter().filter_map(|(id, _)| masses.get(id)).sum();
+    held + item_mass > capacity + config::placement().tolerance
+}
@@ -0,0 +1,57 @@
+use crate::models::{Container, Item, PlacementResult, RearrangementStep, UnplacedItem};
+use crate::placement_service::PlacementService;
+use crate::planner;
+use crate::simulation::ContainerState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Placement engines that can plan a whole manifest into empty containers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Preferred zones first, displacing lower-priority items when needed (as the API does)
+    Phased,
+    /// Extreme-point packing
+    ExtremePoint,
+}
+
+/// Result of running a strategy; `unplaced` is only filled when explanations were asked for.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub placements: Vec<PlacementResult>,
+    pub rearrangements: Vec<RearrangementStep>,
+    pub unplaced: Vec<UnplacedItem>,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 2] = [Strategy::Phased, Strategy::ExtremePoint];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Phased => "phased",
+            Strategy::ExtremePoint => "extreme-point",
+        }
+    }
+
+    /// Plans `items` into empty `containers`. Explanations are only produced by the phased planner.
+    pub fn run(self, items: &[Item], containers: &[Container], explain: bool) -> Result<Outcome, String> {
+        match self {
+            Strategy::Phased => {
+                let by_id: HashMap<String, Container> = containers.iter().map(|c| (c.container_id.clone(), c.clone())).collect();
+                let plan = planner::plan(&by_id, &ContainerState::new(), &[], items).map_err(|e| e.to_string())?;
+                let rearrangements = plan.rearrangements.clone();
+                let (placements, unplaced) = plan.into_results(explain);
+                Ok(Outcome { placements, rearrangements, unplaced })
+            }
+            Strategy::ExtremePoint => {
+                let service = PlacementService::new();
+                let response = futures::executor::block_on(service.optimize_placement(items.to_vec(), containers.to_vec()))
+                    .map_err(|e| e.to_string())?;
+                if let Some(error) = &response.error {
+                    tracing::warn!("{}", error);
+                }
+                Ok(Outcome { placements: response.placements, rearrangements: response.rearrangements, unplaced: Vec::new() })
+            }
+        }
+    }
+}
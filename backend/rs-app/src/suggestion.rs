@@ -0,0 +1,89 @@
+use crate::free_space;
+use crate::config::{self, tolerance};
+use crate::models::*;
+use crate::simulation::{exceeds_capacity, retrieval_steps, ContainerState};
+use std::collections::HashMap;
+
+fn footprint_overlap(a: &Position, b: &Position) -> f64 {
+    let w = a.end_coordinates.width.min(b.end_coordinates.width) - a.start_coordinates.width.max(b.start_coordinates.width);
+    let d = a.end_coordinates.depth.min(b.end_coordinates.depth) - a.start_coordinates.depth.max(b.start_coordinates.depth);
+    w.max(0.0) * d.max(0.0)
+}
+
+// Fraction of the base of `position` resting on the floor or on top of other items
+fn support_fraction(position: &Position, placements: &[(String, Position)]) -> f64 {
+    if position.start_coordinates.height.abs() < tolerance() { return 1.0; }
+    let base = footprint_overlap(position, position);
+    if base <= 0.0 { return 0.0; }
+    let supported: f64 = placements.iter()
+        .filter(|(_, p)| (p.end_coordinates.height - position.start_coordinates.height).abs() < tolerance())
+        .map(|(_, p)| footprint_overlap(position, p))
+        .sum();
+    (supported / base).min(1.0)
+}
+
+/// Ranks candidate spots for a single item across `containers` without changing
+/// any state. Candidates are the supported spots of each container's maximal free
+/// spaces, in every orientation that fits; waste containers (unless the item is
+/// waste) and containers the item would push over their weight capacity are
+/// skipped. `masses` holds the placed items' masses.
+pub fn suggest_placements(
+    item: &Item,
+    containers: &[Container],
+    state: &ContainerState,
+    masses: &HashMap<String, f64>,
+    top: usize,
+) -> Vec<PlacementSuggestion> {
+    let mut suggestions: Vec<PlacementSuggestion> = Vec::new();
+    let (w, d, h) = (item.width, item.depth, item.height);
+    let item_volume = w * d * h;
+
+    for container in containers {
+        let placements: Vec<(String, Position)> = state.get(&container.container_id)
+            .map(|v| v.iter().filter(|(id, _)| id != &item.item_id).cloned().collect())
+            .unwrap_or_default();
+        if container.is_waste_container == Some(true) && !item.is_waste() { continue; }
+        if exceeds_capacity(container, &placements, masses, item.mass.unwrap_or(0.0)) { continue; }
+        let container_volume = container.width * container.depth * container.height;
+        if container_volume <= 0.0 { continue; }
+        let used_volume: f64 = placements.iter().map(|(_, p)| free_space::volume(p)).sum();
+
+        // The same spots the fit query offers, so both endpoints agree
+        let candidates = free_space::supported_spots(container, &placements, (w, d, h));
+
+        for position in candidates {
+            let stability = support_fraction(&position, &placements);
+            let steps = retrieval_steps(&position, &placements);
+            let breakdown = ScoreBreakdown {
+                zone_match: if container.zone == item.preferred_zone { 1.0 } else { 0.0 },
+                retrieval: 1.0 / (1.0 + steps as f64),
+                utilisation: ((used_volume + item_volume) / container_volume).min(1.0),
+                stability,
+            };
+            suggestions.push(PlacementSuggestion {
+                container_id: container.container_id.clone(),
+                zone: container.zone.clone(),
+                position,
+                retrieval_steps: steps as i32,
+                score: breakdown.total(),
+                score_breakdown: breakdown,
+            });
+        }
+    }
+
+    suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal)
+        .then(a.position.start_coordinates.depth.partial_cmp(&b.position.start_coordinates.depth).unwrap_or(std::cmp::Ordering::Equal)));
+    suggestions.truncate(top);
+    suggestions
+}
+
+impl ScoreBreakdown {
+    // The weights sum to 1 (checked at startup) so the total stays in 0..=1
+    pub fn total(&self) -> f64 {
+        let tuning = config::placement();
+        tuning.zone_weight * self.zone_match
+            + tuning.retrieval_weight * self.retrieval
+            + tuning.utilisation_weight * self.utilisation
+            + tuning.stability_weight * self.stability
+    }
+}
@@ -0,0 +1,308 @@
+use crate::errors::{ApiError, FieldError};
+use crate::models::*;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::ops::Deref;
+
+/// Request models check themselves and report every violation, keyed by the
+/// API (serde) field path, e.g. `items[2].width`.
+pub trait Validate {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>);
+
+    fn validate(&self) -> Result<(), ApiError> {
+        let mut violations = Vec::new();
+        self.collect_violations("", &mut violations);
+        if violations.is_empty() { Ok(()) } else { Err(ApiError::Validation(violations)) }
+    }
+}
+
+fn field(path: &str, name: &str) -> String {
+    if path.is_empty() { name.to_string() } else { format!("{}.{}", path, name) }
+}
+
+fn check(out: &mut Vec<FieldError>, path: &str, name: &str, violation: Option<String>) {
+    if let Some(message) = violation {
+        out.push(FieldError::new(field(path, name), message));
+    }
+}
+
+// --- Rules ---
+
+fn not_blank(value: &str) -> Option<String> {
+    value.trim().is_empty().then(|| "must not be empty".to_string())
+}
+
+fn positive(value: f64) -> Option<String> {
+    if !value.is_finite() { return Some("must be a finite number".to_string()); }
+    (value <= 0.0).then(|| format!("must be greater than 0 (got {})", value))
+}
+
+fn non_negative(value: f64) -> Option<String> {
+    if !value.is_finite() { return Some("must be a finite number".to_string()); }
+    (value < 0.0).then(|| format!("must not be negative (got {})", value))
+}
+
+fn in_range(value: i64, min: i64, max: i64) -> Option<String> {
+    (value < min || value > max).then(|| format!("must be between {} and {} (got {})", min, max, value))
+}
+
+fn at_least(value: i64, min: i64) -> Option<String> {
+    (value < min).then(|| format!("must be at least {} (got {})", min, value))
+}
+
+// --- Model rules ---
+
+impl Validate for Item {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "itemId", not_blank(&self.item_id));
+        check(out, path, "name", not_blank(&self.name));
+        check(out, path, "width", positive(self.width));
+        check(out, path, "depth", positive(self.depth));
+        check(out, path, "height", positive(self.height));
+        check(out, path, "mass", self.mass.and_then(non_negative));
+        check(out, path, "priority", in_range(self.priority as i64, 1, 100));
+        check(out, path, "usageLimit", at_least(self.usage_limit as i64, 1));
+        check(out, path, "currentUses", at_least(self.current_uses as i64, 0));
+        check(out, path, "preferredZone", not_blank(&self.preferred_zone));
+    }
+}
+
+impl Validate for Container {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "containerId", not_blank(&self.container_id));
+        check(out, path, "zone", not_blank(&self.zone));
+        check(out, path, "width", positive(self.width));
+        check(out, path, "depth", positive(self.depth));
+        check(out, path, "height", positive(self.height));
+        check(out, path, "maxWeightCapacity", self.max_weight_capacity.and_then(positive));
+    }
+}
+
+impl Validate for Coordinates {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "width", non_negative(self.width));
+        check(out, path, "depth", non_negative(self.depth));
+        check(out, path, "height", non_negative(self.height));
+    }
+}
+
+impl Validate for Position {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        let before = out.len();
+        self.start_coordinates.collect_violations(&field(path, "startCoordinates"), out);
+        self.end_coordinates.collect_violations(&field(path, "endCoordinates"), out);
+        if out.len() > before { return; }
+
+        let (s, e) = (&self.start_coordinates, &self.end_coordinates);
+        for (axis, start, end) in [("width", s.width, e.width), ("depth", s.depth, e.depth), ("height", s.height, e.height)] {
+            if end <= start {
+                out.push(FieldError::new(
+                    field(&field(path, "endCoordinates"), axis),
+                    format!("must be greater than startCoordinates.{} ({} <= {})", axis, end, start),
+                ));
+            }
+        }
+    }
+}
+
+impl Validate for PlacementRequest {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        let mut seen_items = HashSet::new();
+        for (i, item) in self.items.iter().enumerate() {
+            let item_path = format!("{}[{}]", field(path, "items"), i);
+            item.collect_violations(&item_path, out);
+            if !seen_items.insert(&item.item_id) {
+                out.push(FieldError::new(field(&item_path, "itemId"), format!("duplicate itemId '{}'", item.item_id)));
+            }
+        }
+
+        let mut seen_containers = HashSet::new();
+        for (i, container) in self.containers.iter().enumerate() {
+            let container_path = format!("{}[{}]", field(path, "containers"), i);
+            container.collect_violations(&container_path, out);
+            if !seen_containers.insert(&container.container_id) {
+                out.push(FieldError::new(field(&container_path, "containerId"), format!("duplicate containerId '{}'", container.container_id)));
+            }
+        }
+
+        if self.containers.is_empty() && !self.use_stored_containers {
+            out.push(FieldError::new(field(path, "containers"), "must not be empty unless useStoredContainers is set"));
+        }
+
+        // Stored containers are only known to the handler, which checks zones against them
+        if !self.use_stored_containers {
+            let zones: HashSet<&String> = self.containers.iter().map(|c| &c.zone).collect();
+            check_preferred_zones(&self.items, &zones, path, out);
+        }
+    }
+}
+
+/// Flags items whose `preferredZone` no container belongs to.
+pub fn check_preferred_zones(items: &[Item], zones: &HashSet<&String>, path: &str, out: &mut Vec<FieldError>) {
+    if zones.is_empty() { return; }
+    for (i, item) in items.iter().enumerate() {
+        if !item.preferred_zone.trim().is_empty() && !zones.contains(&item.preferred_zone) {
+            out.push(FieldError::new(
+                field(&format!("{}[{}]", field(path, "items"), i), "preferredZone"),
+                format!("no container belongs to zone '{}'", item.preferred_zone),
+            ));
+        }
+    }
+}
+
+impl Validate for PlaceRequest {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "itemId", not_blank(&self.item_id));
+        check(out, path, "containerId", not_blank(&self.container_id));
+        self.position.collect_violations(&field(path, "position"), out);
+    }
+}
+
+impl Validate for CreateTokenRequest {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "name", not_blank(&self.name));
+    }
+}
+
+impl Validate for RetrievalRequest {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "itemId", not_blank(&self.item_id));
+    }
+}
+
+impl Validate for RearrangementStep {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "itemId", not_blank(&self.item_id));
+        check(out, path, "action", not_blank(&self.action));
+        if let Some(position) = &self.from_position {
+            position.collect_violations(&field(path, "fromPosition"), out);
+        }
+        if let Some(position) = &self.to_position {
+            position.collect_violations(&field(path, "toPosition"), out);
+        }
+    }
+}
+
+impl Validate for RearrangementPlanRequest {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        for (i, step) in self.steps.iter().enumerate() {
+            step.collect_violations(&format!("{}[{}]", field(path, "steps"), i), out);
+        }
+    }
+}
+
+impl Validate for CompactionQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "maxMoves", in_range(self.max_moves as i64, 1, 1000));
+    }
+}
+
+impl Validate for FitQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "width", positive(self.width));
+        check(out, path, "depth", positive(self.depth));
+        check(out, path, "height", positive(self.height));
+        check(out, path, "mass", self.mass.and_then(non_negative));
+    }
+}
+
+impl Validate for FreeSpaceQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "top", in_range(self.top as i64, 1, 1000));
+    }
+}
+
+impl Validate for SuggestionQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "top", in_range(self.top as i64, 1, 1000));
+    }
+}
+
+fn page_size(value: i64) -> Option<String> {
+    in_range(value, 1, crate::listing::MAX_PAGE_SIZE)
+}
+
+impl Validate for ItemListQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "limit", page_size(self.limit));
+        check(out, path, "minPriority", self.min_priority.and_then(|p| in_range(p as i64, 1, 100)));
+        check(out, path, "maxPriority", self.max_priority.and_then(|p| in_range(p as i64, 1, 100)));
+        if let (Some(min), Some(max)) = (self.min_priority, self.max_priority) {
+            check(out, path, "maxPriority", (max < min).then(|| format!("must not be below minPriority ({} < {})", max, min)));
+        }
+        if let (Some(after), Some(before)) = (self.expires_after, self.expires_before) {
+            check(out, path, "expiresBefore", (before < after).then(|| "must not be earlier than expiresAfter".to_string()));
+        }
+    }
+}
+
+impl Validate for ContainerListQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "limit", page_size(self.limit));
+    }
+}
+
+impl Validate for WasteListQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "limit", page_size(self.limit));
+        let is_waste = matches!(self.status, None | Some(ItemStatus::WASTE_EXPIRED | ItemStatus::WASTE_DEPLETED));
+        check(out, path, "status", (!is_waste).then(|| "must be WASTE_EXPIRED or WASTE_DEPLETED".to_string()));
+    }
+}
+
+impl Validate for LogListQuery {
+    fn collect_violations(&self, path: &str, out: &mut Vec<FieldError>) {
+        check(out, path, "limit", page_size(self.limit));
+        if let (Some(start), Some(end)) = (self.start_date, self.end_date) {
+            check(out, path, "endDate", (end < start).then(|| "must not be earlier than startDate".to_string()));
+        }
+    }
+}
+
+// --- Extractors ---
+
+/// JSON body that has been deserialized and validated before the handler runs.
+pub struct Validated<T>(pub T);
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for Validated<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let json = web::Json::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let value = json.await?.into_inner();
+            value.validate()?;
+            Ok(Validated(value))
+        })
+    }
+}
+
+/// Query string counterpart of [`Validated`].
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T> Deref for ValidatedQuery<T> {
+    type Target = T;
+    fn deref(&self) -> &T { &self.0 }
+}
+
+impl<T: DeserializeOwned + Validate + 'static> FromRequest for ValidatedQuery<T> {
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let query = web::Query::<T>::from_request(req, payload);
+        Box::pin(async move {
+            let value = query.await?.into_inner();
+            value.validate()?;
+            Ok(ValidatedQuery(value))
+        })
+    }
+}
@@ -0,0 +1,87 @@
+//! Shared setup for the tests: an in-memory database with the real migrations
+//! applied, helpers to seed it, and builders for the models.
+
+// Each test binary uses a different subset of these
+#![allow(dead_code)]
+
+use placement_service::models::{Container, Coordinates, Item, Position};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+
+pub async fn pool() -> SqlitePool {
+    // One connection, since every connection to :memory: is a separate database
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    sqlx::migrate!("./migrations").run(&pool).await.expect("migrations");
+    pool
+}
+
+pub fn pos(start: (f64, f64, f64), end: (f64, f64, f64)) -> Position {
+    Position {
+        start_coordinates: Coordinates { width: start.0, depth: start.1, height: start.2 },
+        end_coordinates: Coordinates { width: end.0, depth: end.1, height: end.2 },
+    }
+}
+
+/// A container with no weight limit that does not take waste.
+pub fn container(id: &str, zone: &str, (width, depth, height): (f64, f64, f64)) -> Container {
+    Container {
+        container_id: id.to_string(),
+        zone: zone.to_string(),
+        width,
+        depth,
+        height,
+        is_waste_container: None,
+        max_weight_capacity: None,
+    }
+}
+
+/// An active item of medium priority, without mass or expiry, that prefers the `Lab` zone.
+pub fn item(id: &str, (width, depth, height): (f64, f64, f64)) -> Item {
+    Item {
+        item_id: id.to_string(),
+        name: id.to_string(),
+        width,
+        depth,
+        height,
+        mass: None,
+        priority: 50,
+        expiry_date: None,
+        usage_limit: 10,
+        current_uses: 0,
+        preferred_zone: "Lab".to_string(),
+        status: None,
+    }
+}
+
+pub async fn insert_container(pool: &SqlitePool, id: &str, zone: &str, (width, depth, height): (f64, f64, f64)) {
+    sqlx::query(r#"INSERT INTO containers ("containerId", zone, width, depth, height) VALUES (?, ?, ?, ?, ?)"#)
+        .bind(id).bind(zone).bind(width).bind(depth).bind(height)
+        .execute(pool).await.expect("insert container");
+}
+
+pub async fn insert_item(pool: &SqlitePool, id: &str, (width, depth, height): (f64, f64, f64)) {
+    sqlx::query(r#"INSERT INTO items ("itemId", name, width, depth, height, "usageLimit") VALUES (?, ?, ?, ?, ?, 10)"#)
+        .bind(id).bind(id).bind(width).bind(depth).bind(height)
+        .execute(pool).await.expect("insert item");
+}
+
+pub async fn insert_placement(pool: &SqlitePool, item_id: &str, container_id: &str, position: &Position) {
+    let (s, e) = (&position.start_coordinates, &position.end_coordinates);
+    sqlx::query(
+        r#"INSERT INTO placements ("itemId_fk", "containerId_fk", start_w, start_d, start_h, end_w, end_d, end_h)
+           VALUES (?, ?, ?, ?, ?, ?, ?, ?)"#)
+        .bind(item_id).bind(container_id)
+        .bind(s.width).bind(s.depth).bind(s.height).bind(e.width).bind(e.depth).bind(e.height)
+        .execute(pool).await.expect("insert placement");
+}
+
+/// Seeds an item of the size of `position` placed there.
+pub async fn place(pool: &SqlitePool, item_id: &str, container_id: &str, position: &Position) {
+    let (s, e) = (&position.start_coordinates, &position.end_coordinates);
+    insert_item(pool, item_id, (e.width - s.width, e.depth - s.depth, e.height - s.height)).await;
+    insert_placement(pool, item_id, container_id, position).await;
+}
@@ -0,0 +1,102 @@
+//! Compaction planning: items move towards the end of the container their
+//! priority calls for, the result is a valid arrangement, and the move budget
+//! is respected.
+
+mod common;
+
+use actix_web::{test as actix_test, web, App};
+use common::{container, pos};
+use placement_service::compaction::plan_compaction;
+use placement_service::errors::ErrorResponse;
+use placement_service::handlers;
+use placement_service::integrity::check_arrangement;
+use placement_service::models::*;
+use placement_service::simulation::ContainerState;
+use std::collections::HashMap;
+
+fn priorities(entries: &[(&str, i32)]) -> HashMap<String, i32> {
+    entries.iter().map(|(id, p)| (id.to_string(), *p)).collect()
+}
+
+// Carries out `steps` on a copy of `placements`
+fn replay(placements: &[(String, Position)], steps: &[RearrangementStep]) -> ContainerState {
+    let mut items = placements.to_vec();
+    for step in steps {
+        items.retain(|(id, _)| id != &step.item_id);
+        if let Some(to) = &step.to_position {
+            items.push((step.item_id.clone(), to.clone()));
+        }
+    }
+    ContainerState::from([("C".to_string(), items)])
+}
+
+#[test]
+fn high_priority_items_are_pulled_to_the_front() {
+    let c = container("C", "Lab", (30.0, 30.0, 10.0));
+    let placements = vec![("H".to_string(), pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0)))];
+
+    let plan = plan_compaction(&c, &placements, &priorities(&[("H", 90)]), 20).unwrap();
+    assert_eq!(plan.moves, 1);
+    let state = replay(&placements, &plan.steps);
+    assert_eq!(state["C"][0].1.start_coordinates.depth, 0.0);
+}
+
+#[test]
+fn items_are_never_left_floating() {
+    // A low-priority item at the open face belongs at the back, but a high-priority
+    // one that is already where it belongs rests on it. With a single move to spend,
+    // nothing could bring the upper item down afterwards.
+    let c = container("C", "Lab", (10.0, 30.0, 20.0));
+    let placements = vec![
+        ("L".to_string(), pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0))),
+        ("H".to_string(), pos((0.0, 0.0, 10.0), (10.0, 10.0, 20.0))),
+    ];
+
+    let plan = plan_compaction(&c, &placements, &priorities(&[("L", 10), ("H", 90)]), 1).unwrap();
+    let state = replay(&placements, &plan.steps);
+    assert_eq!(check_arrangement(&[c], &state, &HashMap::new()), []);
+}
+
+#[test]
+fn plans_stop_at_the_move_budget() {
+    let c = container("C", "Lab", (30.0, 30.0, 10.0));
+    let placements = vec![
+        ("A".to_string(), pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0))),
+        ("B".to_string(), pos((20.0, 20.0, 0.0), (30.0, 30.0, 10.0))),
+    ];
+
+    let plan = plan_compaction(&c, &placements, &priorities(&[("A", 90), ("B", 80)]), 1).unwrap();
+    assert_eq!(plan.moves, 1);
+    assert!(plan.truncated);
+}
+
+async fn compact(uri: &str) -> actix_web::dev::ServiceResponse {
+    let pool = common::pool().await;
+    common::insert_container(&pool, "C", "Lab", (30.0, 30.0, 10.0)).await;
+    common::place(&pool, "H", "C", &pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0))).await;
+    sqlx::query(r#"UPDATE items SET priority = 90 WHERE "itemId" = 'H'"#).execute(&pool).await.unwrap();
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .route("/api/containers/{id}/compact", web::post().to(handlers::compact_container)),
+    ).await;
+    actix_test::call_service(&app, actix_test::TestRequest::post().uri(uri).to_request()).await
+}
+
+#[actix_web::test]
+async fn compact_endpoint_plans_with_stored_priorities() {
+    let resp = compact("/api/containers/C/compact").await;
+    assert_eq!(resp.status(), 200);
+    let body: CompactionResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.containers.len(), 1);
+    assert_eq!(body.containers[0].move_count, 1);
+}
+
+#[actix_web::test]
+async fn compact_endpoint_validates_the_move_budget() {
+    let resp = compact("/api/containers/C/compact?maxMoves=0").await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.code, "VALIDATION_FAILED");
+    assert_eq!(body.error.details[0].field, "maxMoves");
+}
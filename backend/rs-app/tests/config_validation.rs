@@ -0,0 +1,35 @@
+//! Settings checks at startup: a deployment that would hand every caller admin
+//! rights to the network is refused.
+
+use placement_service::config::{Config, ConfigError};
+
+fn problems(config: &Config) -> Vec<String> {
+    match config.validate() {
+        Ok(()) => Vec::new(),
+        Err(ConfigError::Invalid(problems)) => problems,
+        Err(other) => panic!("expected an invalid config, got {:?}", other),
+    }
+}
+
+#[test]
+fn authentication_is_on_by_default() {
+    let config = Config::default();
+    assert!(!config.auth.disabled);
+    assert!(config.server.cors_allowed_origins.is_empty());
+    assert_eq!(problems(&config), Vec::<String>::new());
+}
+
+#[test]
+fn auth_can_only_be_disabled_on_loopback() {
+    let mut config = Config::default();
+    config.auth.disabled = true;
+    for bind_address in ["127.0.0.1:8080", "localhost:8080", "[::1]:8080"] {
+        config.server.bind_address = bind_address.to_string();
+        assert_eq!(problems(&config), Vec::<String>::new(), "{}", bind_address);
+    }
+    for bind_address in ["0.0.0.0:8080", "192.168.1.20:8080", "[::]:8080"] {
+        config.server.bind_address = bind_address.to_string();
+        let problems = problems(&config);
+        assert!(problems.iter().any(|p| p.starts_with("auth.disabled")), "{}: {:?}", bind_address, problems);
+    }
+}
@@ -0,0 +1,135 @@
+//! Free-space queries: maximal empty boxes around the placed items, and fit
+//! suggestions that always rest on the floor or on an item.
+
+mod common;
+
+use actix_web::{test as actix_test, web, App};
+use common::{container, pos};
+use placement_service::free_space::{maximal_free_spaces, supported_spot, supported_spots, volume};
+use placement_service::handlers;
+use placement_service::models::*;
+use placement_service::rearrangement::same_position;
+use placement_service::simulation::{boxes_overlap, is_supported};
+
+fn overlaps(a: &Position, b: &Position) -> bool {
+    boxes_overlap(&a.start_coordinates, &a.end_coordinates, &b.start_coordinates, &b.end_coordinates)
+}
+
+#[test]
+fn an_empty_container_is_one_space() {
+    let spaces = maximal_free_spaces(&container("C", "Lab", (10.0, 20.0, 30.0)), &[]);
+    assert_eq!(spaces.len(), 1);
+    assert!(same_position(&spaces[0], &pos((0.0, 0.0, 0.0), (10.0, 20.0, 30.0))));
+}
+
+#[test]
+fn spaces_wrap_around_an_item() {
+    let placements = vec![("A".to_string(), pos((0.0, 0.0, 0.0), (4.0, 4.0, 4.0)))];
+    let spaces = maximal_free_spaces(&container("C", "Lab", (10.0, 10.0, 10.0)), &placements);
+
+    // Beside, behind and above the item, largest first
+    assert_eq!(spaces.len(), 3);
+    for expected in [
+        pos((4.0, 0.0, 0.0), (10.0, 10.0, 10.0)),
+        pos((0.0, 4.0, 0.0), (10.0, 10.0, 10.0)),
+        pos((0.0, 0.0, 4.0), (10.0, 10.0, 10.0)),
+    ] {
+        assert!(spaces.iter().any(|s| same_position(s, &expected)), "missing {:?}", expected);
+    }
+    assert!(spaces.windows(2).all(|w| volume(&w[0]) >= volume(&w[1])));
+}
+
+#[test]
+fn spaces_never_overlap_items() {
+    let placements = vec![
+        ("A".to_string(), pos((0.0, 0.0, 0.0), (5.0, 5.0, 5.0))),
+        ("B".to_string(), pos((5.0, 5.0, 0.0), (10.0, 10.0, 8.0))),
+        ("C".to_string(), pos((2.0, 2.0, 5.0), (7.0, 7.0, 9.0))),
+    ];
+    let spaces = maximal_free_spaces(&container("C", "Lab", (10.0, 10.0, 10.0)), &placements);
+
+    assert!(!spaces.is_empty());
+    for space in &spaces {
+        assert!(placements.iter().all(|(_, p)| !overlaps(space, p)), "{:?} overlaps an item", space);
+    }
+}
+
+#[test]
+fn spots_in_overhanging_spaces_rest_on_an_item() {
+    // The space above A reaches over the empty floor in front of it
+    let placements = vec![("A".to_string(), pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0)))];
+    let space = pos((0.0, 0.0, 10.0), (10.0, 30.0, 20.0));
+
+    let spot = supported_spot(&space, (10.0, 5.0, 5.0), &placements).unwrap();
+    assert!(same_position(&spot, &pos((0.0, 20.0, 10.0), (10.0, 25.0, 15.0))));
+    assert!(supported_spot(&space, (10.0, 15.0, 5.0), &placements).is_none());
+}
+
+#[test]
+fn spots_are_found_in_every_orientation() {
+    let spots = supported_spots(&container("C", "Lab", (10.0, 10.0, 10.0)), &[], (2.0, 4.0, 6.0));
+    assert_eq!(spots.len(), 6);
+
+    // Only lying on its side does a tall item fit under the low ceiling
+    let spots = supported_spots(&container("C", "Lab", (10.0, 30.0, 5.0)), &[], (5.0, 10.0, 30.0));
+    assert!(!spots.is_empty());
+    assert!(spots.iter().all(|s| s.end_coordinates.height <= 5.0));
+}
+
+async fn fit(pool: &sqlx::SqlitePool, query: &str) -> FitResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .route("/api/free-space/fit", web::get().to(handlers::get_fitting_containers)),
+    ).await;
+    let req = actix_test::TestRequest::get().uri(&format!("/api/free-space/fit?{query}")).to_request();
+    actix_test::call_and_read_body_json(&app, req).await
+}
+
+#[actix_web::test]
+async fn fit_endpoint_skips_waste_and_overloaded_containers() {
+    let pool = common::pool().await;
+    for id in ["Open", "Waste", "Full"] {
+        common::insert_container(&pool, id, "Lab", (10.0, 10.0, 10.0)).await;
+    }
+    sqlx::query(r#"UPDATE containers SET "isWasteContainer" = 1 WHERE "containerId" = 'Waste'"#).execute(&pool).await.unwrap();
+    sqlx::query(r#"UPDATE containers SET "maxWeightCapacity" = 10 WHERE "containerId" = 'Full'"#).execute(&pool).await.unwrap();
+    common::place(&pool, "Heavy", "Full", &pos((0.0, 0.0, 0.0), (2.0, 2.0, 2.0))).await;
+    sqlx::query(r#"UPDATE items SET mass = 8 WHERE "itemId" = 'Heavy'"#).execute(&pool).await.unwrap();
+
+    let light = fit(&pool, "width=2&depth=2&height=2&mass=1").await;
+    let mut ids: Vec<_> = light.containers.iter().map(|c| c.container_id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, ["Full", "Open"]);
+
+    let heavy = fit(&pool, "width=2&depth=2&height=2&mass=5").await;
+    let ids: Vec<_> = heavy.containers.iter().map(|c| c.container_id.as_str()).collect();
+    assert_eq!(ids, ["Open"]);
+}
+
+#[actix_web::test]
+async fn fit_endpoint_suggests_supported_positions() {
+    let pool = common::pool().await;
+    common::insert_container(&pool, "C", "Lab", (10.0, 30.0, 20.0)).await;
+    // A wall at the open face and a low item at the back: the space above the low
+    // item reaches forward over the gap between them
+    let placements = vec![
+        ("W".to_string(), pos((0.0, 0.0, 0.0), (10.0, 10.0, 20.0))),
+        ("L".to_string(), pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0))),
+    ];
+    for (id, p) in &placements {
+        common::place(&pool, id, "C", p).await;
+    }
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .route("/api/free-space/fit", web::get().to(handlers::get_fitting_containers)),
+    ).await;
+
+    let req = actix_test::TestRequest::get().uri("/api/free-space/fit?width=10&depth=5&height=10").to_request();
+    let body: FitResponse = actix_test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.containers.len(), 1);
+    let position = &body.containers[0].position;
+    assert!(is_supported(position, &placements), "{:?} floats", position);
+    assert!(placements.iter().all(|(_, p)| !overlaps(position, p)));
+}
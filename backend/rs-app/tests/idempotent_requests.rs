@@ -0,0 +1,92 @@
+//! Idempotency keys: retries replay a stored success, keys belong to the caller
+//! that sent them, and rejected requests can be retried with the same key.
+
+mod common;
+
+use actix_web::body::BoxBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::{from_fn, Next};
+use actix_web::{test as actix_test, web, App, HttpMessage, HttpResponse};
+use placement_service::auth::Identity;
+use placement_service::idempotency::{self, IdempotencyConfig, IDEMPOTENCY_KEY};
+use placement_service::models::Role;
+use sqlx::SqlitePool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Stands in for authentication: the caller is named by a test header
+async fn as_caller(req: ServiceRequest, next: Next<BoxBody>) -> Result<ServiceResponse<BoxBody>, actix_web::Error> {
+    if let Some(name) = req.headers().get("X-Caller").and_then(|v| v.to_str().ok()) {
+        let identity = Identity { name: name.to_string(), role: Role::Crew };
+        req.extensions_mut().insert(identity);
+    }
+    next.call(req).await
+}
+
+async fn create(runs: web::Data<AtomicUsize>) -> HttpResponse {
+    let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+    HttpResponse::Created().body(run.to_string())
+}
+
+async fn conflict(runs: web::Data<AtomicUsize>) -> HttpResponse {
+    runs.fetch_add(1, Ordering::SeqCst);
+    HttpResponse::Conflict().finish()
+}
+
+async fn send(pool: &SqlitePool, runs: &web::Data<AtomicUsize>, uri: &str, caller: &str, key: &str) -> ServiceResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(IdempotencyConfig { ttl: chrono::Duration::hours(1) }))
+            .app_data(runs.clone())
+            .wrap(from_fn(idempotency::idempotency))
+            .wrap(from_fn(as_caller))
+            .route("/create", web::post().to(create))
+            .route("/conflict", web::post().to(conflict)),
+    ).await;
+    let req = actix_test::TestRequest::post()
+        .uri(uri)
+        .insert_header(("X-Caller", caller))
+        .insert_header((IDEMPOTENCY_KEY, key))
+        .to_request();
+    actix_test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn retries_replay_the_stored_success() {
+    let pool = common::pool().await;
+    let runs = web::Data::new(AtomicUsize::new(0));
+
+    for _ in 0..2 {
+        let resp = send(&pool, &runs, "/create", "alice", "k1").await;
+        assert_eq!(resp.status(), 201);
+        assert_eq!(actix_test::read_body(resp).await, "1");
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[actix_web::test]
+async fn callers_sharing_a_key_do_not_collide() {
+    let pool = common::pool().await;
+    let runs = web::Data::new(AtomicUsize::new(0));
+
+    let first = send(&pool, &runs, "/create", "alice", "k1").await;
+    let second = send(&pool, &runs, "/create", "bob", "k1").await;
+    assert_eq!(first.status(), 201);
+    assert_eq!(second.status(), 201);
+    assert!(second.headers().get("Idempotent-Replayed").is_none());
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[actix_web::test]
+async fn rejected_requests_are_not_stored() {
+    let pool = common::pool().await;
+    let runs = web::Data::new(AtomicUsize::new(0));
+
+    for _ in 0..2 {
+        let resp = send(&pool, &runs, "/conflict", "alice", "k1").await;
+        assert_eq!(resp.status(), 409);
+    }
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+    let (stored,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM idempotency_keys").fetch_one(&pool).await.unwrap();
+    assert_eq!(stored, 0);
+}
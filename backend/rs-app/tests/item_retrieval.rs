@@ -0,0 +1,102 @@
+//! Retrieval: only active items that sit in a container can be used, and every
+//! use is counted and logged exactly once.
+
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::{test as actix_test, web, App, HttpMessage, ResponseError};
+use common::pos;
+use placement_service::auth::Identity;
+use placement_service::errors::{ApiError, ErrorResponse};
+use placement_service::events::EventBus;
+use placement_service::handlers;
+use placement_service::models::*;
+use sqlx::SqlitePool;
+
+async fn current_uses(pool: &SqlitePool, item_id: &str) -> i64 {
+    sqlx::query_scalar(r#"SELECT "currentUses" FROM items WHERE "itemId" = ?"#)
+        .bind(item_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn retrieve(pool: &SqlitePool, item_id: &str) -> ServiceResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(EventBus::new()))
+            .route("/api/retrieve", web::post().to(handlers::retrieve_item)),
+    ).await;
+    let req = actix_test::TestRequest::post()
+        .uri("/api/retrieve")
+        .set_json(RetrievalRequest { item_id: item_id.to_string() })
+        .to_request();
+    req.extensions_mut().insert(Identity { name: "crew".to_string(), role: Role::Crew });
+    actix_test::call_service(&app, req).await
+}
+
+// A container holding the placed item `P`, with `U` stored but never placed
+async fn seed(pool: &SqlitePool) {
+    common::insert_container(pool, "C", "Lab", (30.0, 30.0, 30.0)).await;
+    common::place(pool, "P", "C", &pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0))).await;
+    common::insert_item(pool, "U", (10.0, 10.0, 10.0)).await;
+}
+
+async fn error_code(resp: ServiceResponse) -> String {
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    body.error.code
+}
+
+#[actix_web::test]
+async fn placed_items_are_used_once_per_retrieval() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let resp = retrieve(&pool, "P").await;
+
+    assert_eq!(resp.status(), 200);
+    let body: RetrievalResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.container_id.as_deref(), Some("C"));
+    assert_eq!(current_uses(&pool, "P").await, 1);
+
+    let (user_id, action): (String, String) = sqlx::query_as(r#"SELECT "userId", "actionType" FROM logs WHERE "itemId" = 'P'"#)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((user_id.as_str(), action.as_str()), ("crew", "retrieval"));
+}
+
+#[actix_web::test]
+async fn unplaced_items_cannot_be_retrieved() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let resp = retrieve(&pool, "U").await;
+
+    assert_eq!(resp.status(), 404);
+    assert_eq!(error_code(resp).await, "ITEM_NOT_PLACED");
+    assert_eq!(current_uses(&pool, "U").await, 0);
+}
+
+#[actix_web::test]
+async fn waste_items_cannot_be_retrieved() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    for status in ["WASTE_EXPIRED", "WASTE_DEPLETED", "DISPOSED"] {
+        sqlx::query(r#"UPDATE items SET status = ? WHERE "itemId" = 'P'"#).bind(status).execute(&pool).await.unwrap();
+        let resp = retrieve(&pool, "P").await;
+
+        assert_eq!(resp.status(), 409, "{}", status);
+        assert_eq!(error_code(resp).await, "ITEM_NOT_USABLE");
+        assert_eq!(current_uses(&pool, "P").await, 0);
+    }
+}
+
+#[actix_web::test]
+async fn database_errors_do_not_leak_details() {
+    let err = ApiError::Db(sqlx::Error::Protocol("no such column: secret_column".to_string()));
+    let body = actix_web::body::to_bytes(err.error_response().into_body()).await.unwrap();
+    let body: ErrorResponse = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(body.error.code, "DB_ERROR");
+    assert!(!body.error.message.contains("secret_column"), "{}", body.error.message);
+}
@@ -0,0 +1,161 @@
+//! Keyset pagination shared by the list endpoints: cursors round-trip, sort
+//! columns come from a fixed whitelist, and paging through the logs and waste
+//! lists visits every matching row exactly once.
+
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::{test as actix_test, web, App};
+use placement_service::errors::{ApiError, ErrorResponse};
+use placement_service::handlers;
+use placement_service::listing::{next_cursor, parse_cursor, parse_sort, SortColumn};
+use placement_service::models::*;
+use sqlx::SqlitePool;
+
+const COLUMNS: &[SortColumn] = &[
+    SortColumn { name: "timestamp", expr: "l.timestamp" },
+    SortColumn { name: "userId", expr: r#"l."userId""# },
+];
+
+fn violation_fields(err: ApiError) -> Vec<String> {
+    match err {
+        ApiError::Validation(details) => details.into_iter().map(|d| d.field).collect(),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn sorting_defaults_to_the_first_column() {
+    let sort = parse_sort(None, COLUMNS).unwrap();
+    assert_eq!((sort.expr, sort.descending), ("l.timestamp", false));
+}
+
+#[test]
+fn a_leading_dash_sorts_descending() {
+    let sort = parse_sort(Some("-userId"), COLUMNS).unwrap();
+    assert_eq!((sort.expr, sort.descending), (r#"l."userId""#, true));
+}
+
+#[test]
+fn only_whitelisted_columns_can_be_sorted_on() {
+    for raw in ["name", "l.timestamp", "timestamp; DROP TABLE logs", "--timestamp"] {
+        assert_eq!(violation_fields(parse_sort(Some(raw), COLUMNS).err().unwrap()), ["sort"], "{}", raw);
+    }
+}
+
+#[test]
+fn cursors_round_trip_through_the_last_row_of_a_page() {
+    // One row past the limit means another page follows
+    let mut rows = vec![11_i64, 12, 13];
+    let cursor = next_cursor(&mut rows, 2, |id| *id).unwrap();
+    assert_eq!(rows, [11, 12]);
+    assert_eq!(parse_cursor(Some(&cursor)).unwrap(), Some(12));
+}
+
+#[test]
+fn the_last_page_has_no_cursor() {
+    let mut rows = vec![11_i64, 12];
+    assert_eq!(next_cursor(&mut rows, 2, |id| *id), None);
+    assert_eq!(rows, [11, 12]);
+    assert_eq!(parse_cursor(None).unwrap(), None);
+}
+
+#[test]
+fn foreign_cursors_are_rejected() {
+    for raw in ["", "abc", "1.5", "12;"] {
+        assert_eq!(violation_fields(parse_cursor(Some(raw)).err().unwrap()), ["cursor"], "{}", raw);
+    }
+}
+
+async fn get(pool: &SqlitePool, uri: &str) -> ServiceResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .route("/api/logs", web::get().to(handlers::list_logs))
+            .route("/api/waste", web::get().to(handlers::list_waste)),
+    ).await;
+    actix_test::call_service(&app, actix_test::TestRequest::get().uri(uri).to_request()).await
+}
+
+// Five log entries a minute apart, alternating between two users
+async fn seed_logs(pool: &SqlitePool) {
+    for minute in 0..5 {
+        sqlx::query(r#"INSERT INTO logs (timestamp, "userId", "actionType", "itemId") VALUES (?, ?, ?, ?)"#)
+            .bind(format!("2025-01-01T00:0{}:00Z", minute))
+            .bind(if minute % 2 == 0 { "alice" } else { "bob" })
+            .bind(if minute == 4 { "retrieval" } else { "placement" })
+            .bind(format!("item{}", minute))
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+}
+
+// Follows nextCursor from the first page of `uri` to the last
+async fn page_through_logs(pool: &SqlitePool, uri: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let page_uri = match &cursor {
+            Some(c) => format!("{}&cursor={}", uri, c),
+            None => uri.to_string(),
+        };
+        let resp = get(pool, &page_uri).await;
+        assert_eq!(resp.status(), 200);
+        let page: LogListResponse = actix_test::read_body_json(resp).await;
+        seen.extend(page.logs.into_iter().map(|l| l.item_id));
+        cursor = page.next_cursor;
+        if cursor.is_none() { return seen; }
+    }
+}
+
+#[actix_web::test]
+async fn logs_page_through_every_entry_once() {
+    let pool = common::pool().await;
+    seed_logs(&pool).await;
+
+    assert_eq!(page_through_logs(&pool, "/api/logs?limit=2&sort=-timestamp").await, ["item4", "item3", "item2", "item1", "item0"]);
+    // Ties on the sort column are broken by row, so no entry is skipped or repeated
+    assert_eq!(page_through_logs(&pool, "/api/logs?limit=2&sort=userId").await, ["item0", "item2", "item4", "item1", "item3"]);
+}
+
+#[actix_web::test]
+async fn logs_filter_by_action_and_window() {
+    let pool = common::pool().await;
+    seed_logs(&pool).await;
+
+    let resp = get(&pool, "/api/logs?actionType=placement&startDate=2025-01-01T00:01:00Z&endDate=2025-01-01T00:04:00Z").await;
+    let body: LogListResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.total, 3);
+    assert!(body.logs.iter().all(|l| l.action_type == ActionType::Placement));
+
+    let resp = get(&pool, "/api/logs?startDate=2025-01-02T00:00:00Z&endDate=2025-01-01T00:00:00Z").await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.details[0].field, "endDate");
+}
+
+#[actix_web::test]
+async fn waste_lists_only_expired_and_depleted_items() {
+    let pool = common::pool().await;
+    for (id, status, priority) in [("A", "ACTIVE", 90), ("E", "WASTE_EXPIRED", 20), ("D", "WASTE_DEPLETED", 60), ("X", "DISPOSED", 50)] {
+        common::insert_item(&pool, id, (1.0, 1.0, 1.0)).await;
+        sqlx::query(r#"UPDATE items SET status = ?, priority = ? WHERE "itemId" = ?"#)
+            .bind(status).bind(priority).bind(id)
+            .execute(&pool).await.unwrap();
+    }
+
+    let resp = get(&pool, "/api/waste?sort=-priority&limit=1").await;
+    let first: ItemListResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(first.total, 2);
+    assert_eq!(first.items[0].item.item_id, "D");
+    let resp = get(&pool, &format!("/api/waste?sort=-priority&limit=1&cursor={}", first.next_cursor.unwrap())).await;
+    let second: ItemListResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(second.items[0].item.item_id, "E");
+    assert_eq!(second.next_cursor, None);
+
+    let resp = get(&pool, "/api/waste?status=ACTIVE").await;
+    assert_eq!(resp.status(), 400);
+    let resp = get(&pool, "/api/waste?sort=secret").await;
+    assert_eq!(resp.status(), 400);
+}
@@ -0,0 +1,89 @@
+//! Persisting a placement run: only rows that actually change are written, so
+//! container versions move exactly when their contents or geometry do.
+
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::{test as actix_test, web, App};
+use common::{container, item, pos};
+use placement_service::events::EventBus;
+use placement_service::handlers;
+use placement_service::metrics::Metrics;
+use placement_service::models::*;
+use sqlx::SqlitePool;
+
+const SIZE: (f64, f64, f64) = (100.0, 100.0, 100.0);
+
+// `Lab` holds A; `Air` is empty
+async fn seed(pool: &SqlitePool) {
+    common::insert_container(pool, "Lab", "Lab", SIZE).await;
+    common::insert_container(pool, "Air", "Airlock", SIZE).await;
+    common::place(pool, "A", "Lab", &pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0))).await;
+}
+
+async fn run(pool: &SqlitePool, req: PlacementRequest) -> ServiceResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(EventBus::new()))
+            .app_data(web::Data::new(Metrics::new()))
+            .route("/api/placement", web::post().to(handlers::optimize_placement)),
+    ).await;
+    actix_test::call_service(&app, actix_test::TestRequest::post().uri("/api/placement").set_json(req).to_request()).await
+}
+
+async fn version(pool: &SqlitePool, container_id: &str) -> i64 {
+    sqlx::query_scalar(r#"SELECT version FROM containers WHERE "containerId" = ?"#).bind(container_id).fetch_one(pool).await.unwrap()
+}
+
+async fn placement_row(pool: &SqlitePool, item_id: &str) -> i64 {
+    sqlx::query_scalar(r#"SELECT id FROM placements WHERE "itemId_fk" = ?"#).bind(item_id).fetch_one(pool).await.unwrap()
+}
+
+#[actix_web::test]
+async fn untouched_placements_are_not_rewritten() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let row_before = placement_row(&pool, "A").await;
+
+    let resp = run(&pool, PlacementRequest {
+        items: vec![item("B", (10.0, 10.0, 10.0))],
+        containers: vec![container("Lab", "Lab", SIZE)],
+        use_stored_containers: true,
+        zones: None,
+        explain: false,
+    }).await;
+    assert_eq!(resp.status(), 200);
+
+    assert_eq!(placement_row(&pool, "A").await, row_before);
+    assert_eq!(version(&pool, "Lab").await, 1);
+    assert_eq!(version(&pool, "Air").await, 0);
+}
+
+#[actix_web::test]
+async fn resubmitting_a_stored_container_is_not_a_change() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+
+    // B fits nowhere, so nothing is placed and the plan changes nothing
+    let resp = run(&pool, PlacementRequest {
+        items: vec![item("B", (200.0, 200.0, 200.0))],
+        containers: vec![container("Lab", "Lab", SIZE)],
+        use_stored_containers: false,
+        zones: None,
+        explain: false,
+    }).await;
+    assert_eq!(resp.status(), 207);
+    assert_eq!(version(&pool, "Lab").await, 0);
+
+    let mut wider = container("Lab", "Lab", SIZE);
+    wider.width = 120.0;
+    run(&pool, PlacementRequest {
+        items: vec![item("B", (200.0, 200.0, 200.0))],
+        containers: vec![wider],
+        use_stored_containers: false,
+        zones: None,
+        explain: false,
+    }).await;
+    assert_eq!(version(&pool, "Lab").await, 1);
+}
@@ -0,0 +1,245 @@
+//! Property tests for the placement engines: whatever the input, every placement
+//! lies inside its container, never overlaps another item, is the item in some
+//! orientation, and every rearrangement can be carried out step by step.
+
+mod common;
+
+use common::{container, item};
+use placement_service::integrity::{check_arrangement, Violation};
+use placement_service::models::*;
+use placement_service::placement_service::PlacementService;
+use placement_service::planner;
+use placement_service::rearrangement::same_position;
+use placement_service::simulation::{find_spot_in_container, is_supported, ContainerState};
+use proptest::prelude::*;
+use std::collections::HashMap;
+
+const TOL: f64 = 1e-6;
+
+// Lengths with one decimal, like the datasets
+fn length(min: u32, max: u32) -> impl Strategy<Value = f64> {
+    (min * 10..=max * 10).prop_map(|v| v as f64 / 10.0)
+}
+
+fn dims(min: u32, max: u32) -> impl Strategy<Value = (f64, f64, f64)> {
+    (length(min, max), length(min, max), length(min, max))
+}
+
+fn zone(second: bool) -> String {
+    (if second { "B" } else { "A" }).to_string()
+}
+
+// Up to three containers spread over two zones, and items preferring either zone
+fn manifest(container_dims: (u32, u32), item_dims: (u32, u32), max_items: usize) -> impl Strategy<Value = (Vec<Container>, Vec<Item>)> {
+    let containers = prop::collection::vec((dims(container_dims.0, container_dims.1), prop::bool::ANY), 1..=3)
+        .prop_map(|specs| specs.into_iter().enumerate()
+            .map(|(n, (d, second_zone))| container(&format!("C{}", n), &zone(second_zone), d))
+            .collect::<Vec<_>>());
+    let items = prop::collection::vec((dims(item_dims.0, item_dims.1), 1..=100i32, prop::bool::ANY), 1..=max_items)
+        .prop_map(|specs| specs.into_iter().enumerate()
+            .map(|(n, (d, priority, second_zone))| Item { priority, preferred_zone: zone(second_zone), ..item(&format!("I{:02}", n), d) })
+            .collect::<Vec<_>>());
+    (containers, items)
+}
+
+fn extents(position: &Position) -> (f64, f64, f64) {
+    let (s, e) = (&position.start_coordinates, &position.end_coordinates);
+    (e.width - s.width, e.depth - s.depth, e.height - s.height)
+}
+
+fn is_rotation_of((a, b, c): (f64, f64, f64), (x, y, z): (f64, f64, f64)) -> bool {
+    let mut found = [a, b, c];
+    let mut expected = [x, y, z];
+    found.sort_by(f64::total_cmp);
+    expected.sort_by(f64::total_cmp);
+    found.iter().zip(expected).all(|(f, e)| (f - e).abs() < TOL)
+}
+
+// Overlaps and boxes outside their container; whether items are supported is checked separately
+fn geometry_violations(containers: &[Container], state: &ContainerState) -> Vec<Violation> {
+    check_arrangement(containers, state, &HashMap::new()).into_iter()
+        .filter(|v| !matches!(v, Violation::Unsupported { .. }))
+        .collect()
+}
+
+fn state_of(placements: &[PlacementResult]) -> ContainerState {
+    let mut state = ContainerState::new();
+    for p in placements {
+        state.entry(p.container_id.clone()).or_default().push((p.item_id.clone(), p.position.clone()));
+    }
+    state
+}
+
+/// Carries out one rearrangement step on `state`, failing if the item is not
+/// where the step says or if the result is not a valid arrangement.
+fn replay(containers: &[Container], state: &mut ContainerState, step: &RearrangementStep) -> Result<(), TestCaseError> {
+    if let (Some(container_id), Some(position)) = (&step.from_container, &step.from_position) {
+        let held = state.entry(container_id.clone()).or_default();
+        let before = held.len();
+        held.retain(|(id, p)| !(id == &step.item_id && same_position(p, position)));
+        prop_assert_eq!(held.len() + 1, before, "step {} takes {} from where it is not", step.step, step.item_id);
+    }
+    if let (Some(container_id), Some(position)) = (&step.to_container, &step.to_position) {
+        prop_assert!(
+            state.values().flatten().all(|(id, _)| id != &step.item_id),
+            "step {} puts down {} while it is still placed", step.step, step.item_id
+        );
+        state.entry(container_id.clone()).or_default().push((step.item_id.clone(), position.clone()));
+    }
+    let violations = geometry_violations(containers, state);
+    prop_assert!(violations.is_empty(), "after step {} ({} {}): {:?}", step.step, step.action, step.item_id, violations);
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn find_spot_in_container_keeps_the_arrangement_valid(
+        container_dims in dims(20, 120),
+        items in prop::collection::vec((dims(1, 60), prop::bool::ANY), 1..25),
+    ) {
+        let container = container("C", "A", container_dims);
+        let mut placed: Vec<(String, Position)> = Vec::new();
+        for (n, (item_dims, high_priority)) in items.into_iter().enumerate() {
+            let Some((position, orientation)) = find_spot_in_container(item_dims, &container, &placed, high_priority) else { continue };
+            prop_assert!(is_rotation_of(orientation, item_dims), "orientation {:?} of {:?}", orientation, item_dims);
+            let (w, d, h) = extents(&position);
+            prop_assert!((w - orientation.0).abs() < TOL && (d - orientation.1).abs() < TOL && (h - orientation.2).abs() < TOL,
+                "position {:?} does not match orientation {:?}", position, orientation);
+
+            placed.push((format!("I{:02}", n), position));
+            let state = ContainerState::from([(container.container_id.clone(), placed.clone())]);
+            let violations = check_arrangement(std::slice::from_ref(&container), &state, &HashMap::new());
+            prop_assert!(violations.is_empty(), "{:?}", violations);
+        }
+    }
+
+    // Containers stay small: the grid fallback of find_position is cubic in their size
+    #[test]
+    fn find_position_keeps_the_arrangement_valid(
+        container_dims in dims(10, 50),
+        boxes in prop::collection::vec(dims(1, 30), 1..12),
+    ) {
+        let service = PlacementService::new();
+        let container = container("C", "A", container_dims);
+        let mut placed: Vec<(String, Position)> = Vec::new();
+        for (n, (w, d, h)) in boxes.into_iter().enumerate() {
+            let Some((position, _)) = service.find_position(w, d, h, &container, &placed).expect("find_position does not fail") else { continue };
+            let (pw, pd, ph) = extents(&position);
+            prop_assert!((pw - w).abs() < TOL && (pd - d).abs() < TOL && (ph - h).abs() < TOL,
+                "{:?} is not a {} x {} x {} box", position, w, d, h);
+            prop_assert!(is_supported(&position, &placed), "{:?} floats above {:?}", position, placed);
+
+            placed.push((format!("I{:02}", n), position));
+            let state = ContainerState::from([(container.container_id.clone(), placed.clone())]);
+            let violations = geometry_violations(std::slice::from_ref(&container), &state);
+            prop_assert!(violations.is_empty(), "{:?}", violations);
+        }
+    }
+
+    // Sub-unit boxes in small containers: the fallback search has to work below
+    // its grid step, by trying the faces of the items already placed
+    #[test]
+    fn find_position_finds_room_whenever_there_is_some(
+        container_dims in dims(1, 12),
+        boxes in prop::collection::vec((1..=60u32, 1..=60u32, 1..=60u32), 1..20),
+    ) {
+        let service = PlacementService::new();
+        let container = container("C", "A", container_dims);
+        let mut placed: Vec<(String, Position)> = Vec::new();
+        for (n, (w, d, h)) in boxes.into_iter().enumerate() {
+            let item_dims = (w as f64 / 10.0, d as f64 / 10.0, h as f64 / 10.0);
+            let Some((_, (w, d, h))) = find_spot_in_container(item_dims, &container, &placed, true) else { continue };
+            let found = service.find_position(w, d, h, &container, &placed).expect("find_position does not fail");
+            prop_assert!(found.is_some(), "no spot for a {} x {} x {} box in {:?} holding {:?}", w, d, h, container, placed);
+            if let Some((position, _)) = found {
+                placed.push((format!("I{:02}", n), position));
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_point_placements_and_rearrangements_replay(
+        (containers, items) in manifest((10, 50), (1, 30), 12),
+    ) {
+        let service = PlacementService::new();
+        let response = futures::executor::block_on(service.optimize_placement(items.clone(), containers.clone()))
+            .expect("optimize_placement does not fail");
+        let by_id: HashMap<&String, &Item> = items.iter().map(|i| (&i.item_id, i)).collect();
+
+        let steps = &response.rearrangements;
+        let mut next_step = 0;
+        let mut state = ContainerState::new();
+        for placement in &response.placements {
+            let item = by_id[&placement.item_id];
+            prop_assert!(is_rotation_of(extents(&placement.position), (item.width, item.depth, item.height)),
+                "{} placed as {:?}", item.item_id, placement.position);
+
+            let places_item = |s: &RearrangementStep| s.action == "place" && s.item_id == placement.item_id;
+            if steps[next_step..].iter().any(places_item) {
+                // The rearrangement that made room: every step up to placing the item,
+                // then putting back whatever was taken out to reach its spot
+                while next_step < steps.len() {
+                    next_step += 1;
+                    replay(&containers, &mut state, &steps[next_step - 1])?;
+                    if places_item(&steps[next_step - 1]) { break; }
+                }
+                while next_step < steps.len() && steps[next_step].action == "placeBack" {
+                    next_step += 1;
+                    replay(&containers, &mut state, &steps[next_step - 1])?;
+                }
+            } else {
+                state.entry(placement.container_id.clone()).or_default()
+                    .push((placement.item_id.clone(), placement.position.clone()));
+                let violations = geometry_violations(&containers, &state);
+                prop_assert!(violations.is_empty(), "after placing {}: {:?}", placement.item_id, violations);
+            }
+        }
+        prop_assert_eq!(next_step, steps.len(), "rearrangement steps left over");
+    }
+
+    #[test]
+    fn phased_plan_rearrangements_replay(
+        (containers, stored) in manifest((20, 100), (5, 60), 10),
+        incoming in prop::collection::vec((dims(5, 60), 1..=100i32, prop::bool::ANY), 1..6),
+    ) {
+        let by_id: HashMap<String, Container> = containers.iter().map(|c| (c.container_id.clone(), c.clone())).collect();
+
+        // Whatever fits of the first manifest is already stowed
+        let first = planner::plan(&by_id, &ContainerState::new(), &[], &stored).expect("planning into empty containers succeeds");
+        let placed: Vec<PlacementResult> = first.placements.into_values().collect();
+        let initial = state_of(&placed);
+        let stored: Vec<Item> = stored.into_iter().filter(|i| placed.iter().any(|p| p.item_id == i.item_id)).collect();
+
+        let incoming: Vec<Item> = incoming.into_iter().enumerate()
+            .map(|(n, (d, priority, second_zone))| Item { priority, preferred_zone: zone(second_zone), ..item(&format!("N{:02}", n), d) })
+            .collect();
+        let plan = planner::plan(&by_id, &initial, &stored, &incoming).expect("planning succeeds");
+
+        // Moving the stored items in order must be possible and leave them where the plan says
+        let mut state = initial.clone();
+        for step in &plan.rearrangements {
+            replay(&containers, &mut state, step)?;
+        }
+        for (container_id, held) in &state {
+            for (item_id, position) in held {
+                let planned = &plan.placements[item_id];
+                prop_assert!(&planned.container_id == container_id && same_position(&planned.position, position),
+                    "{} ends up in {} at {:?}, but the plan has it in {} at {:?}",
+                    item_id, container_id, position, planned.container_id, planned.position);
+            }
+        }
+
+        // Then the incoming items go in, and every item is where the plan says
+        let all: Vec<Item> = stored.iter().chain(&incoming).cloned().collect();
+        let final_placements: Vec<PlacementResult> = plan.placements.into_values().collect();
+        for p in &final_placements {
+            let item = all.iter().find(|i| i.item_id == p.item_id).expect("placements are of known items");
+            prop_assert!(is_rotation_of(extents(&p.position), (item.width, item.depth, item.height)),
+                "{} placed as {:?}", item.item_id, p.position);
+        }
+        let violations = geometry_violations(&containers, &state_of(&final_placements));
+        prop_assert!(violations.is_empty(), "{:?}", violations);
+    }
+}
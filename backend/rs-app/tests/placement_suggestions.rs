@@ -0,0 +1,78 @@
+//! Single-item suggestions: only containers that can physically take the item,
+//! within their weight capacity, are proposed, and waste containers only for waste.
+
+mod common;
+
+use common::{container, item, pos};
+use placement_service::free_space::supported_spots;
+use placement_service::models::*;
+use placement_service::rearrangement::same_position;
+use placement_service::simulation::ContainerState;
+use placement_service::suggestion::suggest_placements;
+use std::collections::HashMap;
+
+// A 10 cm cube that does not mind where it goes
+fn new_item(mass: Option<f64>) -> Item {
+    Item { mass, ..item("N", (10.0, 10.0, 10.0)) }
+}
+
+// `Heavy` holds a 40 kg item and can take 50 kg; `Roomy` is empty
+fn heavy_and_roomy() -> (Vec<Container>, ContainerState, HashMap<String, f64>) {
+    let containers = vec![
+        Container { max_weight_capacity: Some(50.0), ..container("Heavy", "Lab", (30.0, 30.0, 30.0)) },
+        container("Roomy", "Lab", (30.0, 30.0, 30.0)),
+    ];
+    let state = ContainerState::from([("Heavy".to_string(), vec![("A".to_string(), pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0)))])]);
+    let masses = HashMap::from([("A".to_string(), 40.0)]);
+    (containers, state, masses)
+}
+
+fn suggested_containers(suggestions: &[PlacementSuggestion]) -> Vec<&str> {
+    let mut ids: Vec<&str> = suggestions.iter().map(|s| s.container_id.as_str()).collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[test]
+fn containers_the_item_would_overload_are_skipped() {
+    let (containers, state, masses) = heavy_and_roomy();
+    let suggestions = suggest_placements(&new_item(Some(15.0)), &containers, &state, &masses, 100);
+    assert_eq!(suggested_containers(&suggestions), ["Roomy"]);
+}
+
+#[test]
+fn items_within_capacity_can_go_anywhere() {
+    let (containers, state, masses) = heavy_and_roomy();
+    for mass in [Some(10.0), None] {
+        let suggestions = suggest_placements(&new_item(mass), &containers, &state, &masses, 100);
+        assert_eq!(suggested_containers(&suggestions), ["Heavy", "Roomy"]);
+    }
+}
+
+#[test]
+fn waste_containers_only_take_waste() {
+    let (mut containers, state, masses) = heavy_and_roomy();
+    containers[1].is_waste_container = Some(true);
+    let suggestions = suggest_placements(&new_item(None), &containers, &state, &masses, 100);
+    assert_eq!(suggested_containers(&suggestions), ["Heavy"]);
+
+    let mut waste = new_item(None);
+    waste.status = Some(ItemStatus::WASTE_EXPIRED);
+    let suggestions = suggest_placements(&waste, &containers, &state, &masses, 100);
+    assert_eq!(suggested_containers(&suggestions), ["Heavy", "Roomy"]);
+}
+
+#[test]
+fn suggestions_match_the_fit_query() {
+    let (containers, state, masses) = heavy_and_roomy();
+    let new = new_item(None);
+    let suggestions = suggest_placements(&new, &containers, &state, &masses, 100);
+    for container in &containers {
+        let placements = state.get(&container.container_id).map_or(&[][..], |v| &v[..]);
+        let spots = supported_spots(container, placements, (new.width, new.depth, new.height));
+        let suggested = suggestions.iter().filter(|s| s.container_id == container.container_id).count();
+        assert_eq!(suggested, spots.len());
+        assert!(spots.iter().all(|spot| suggestions.iter().any(|s| same_position(&s.position, spot))));
+    }
+}
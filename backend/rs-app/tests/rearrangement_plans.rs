@@ -0,0 +1,202 @@
+//! Sequencing of rearrangement plans: steps wait for destinations that a later
+//! step frees or supports, buried items are dug out and put back, nothing is
+//! left floating, and plans that cannot be ordered are rejected.
+
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::{test as actix_test, web, App};
+use common::pos;
+use placement_service::errors::ErrorResponse;
+use placement_service::handlers;
+use placement_service::models::*;
+use placement_service::rearrangement::{same_position, sequence_plan, PlanError};
+use placement_service::simulation::ContainerState;
+
+fn step(n: i32, action: &str, item_id: &str, from: Option<&Position>, to: Option<&Position>) -> RearrangementStep {
+    RearrangementStep {
+        step: n,
+        action: action.to_string(),
+        item_id: item_id.to_string(),
+        from_container: from.map(|_| "C".to_string()),
+        from_position: from.cloned(),
+        to_container: to.map(|_| "C".to_string()),
+        to_position: to.cloned(),
+    }
+}
+
+fn state(items: &[(&str, &Position)]) -> ContainerState {
+    ContainerState::from([("C".to_string(), items.iter().map(|(id, p)| (id.to_string(), (*p).clone())).collect())])
+}
+
+fn summary(steps: &[RearrangementStep]) -> Vec<(i32, &str, &str)> {
+    steps.iter().map(|s| (s.step, s.action.as_str(), s.item_id.as_str())).collect()
+}
+
+// Two items side by side at the open face, and a free spot next to them
+fn side_by_side() -> (Position, Position, Position) {
+    (
+        pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0)),
+        pos((20.0, 0.0, 0.0), (30.0, 10.0, 10.0)),
+        pos((40.0, 0.0, 0.0), (50.0, 10.0, 10.0)),
+    )
+}
+
+#[test]
+fn steps_wait_for_destinations_freed_later() {
+    let (a, b, free) = side_by_side();
+    let plan = [step(1, "move", "A", Some(&a), Some(&b)), step(2, "move", "B", Some(&b), Some(&free))];
+
+    let sequenced = sequence_plan(&state(&[("A", &a), ("B", &b)]), &plan).unwrap();
+    assert_eq!(summary(&sequenced), [(1, "move", "B"), (2, "move", "A")]);
+}
+
+#[test]
+fn swaps_are_circular() {
+    let (a, b, _) = side_by_side();
+    let plan = [step(1, "move", "A", Some(&a), Some(&b)), step(2, "move", "B", Some(&b), Some(&a))];
+
+    match sequence_plan(&state(&[("A", &a), ("B", &b)]), &plan) {
+        Err(PlanError::CircularDependency { steps }) => assert_eq!(steps, [1, 2]),
+        other => panic!("expected a circular dependency, got {:?}", other),
+    }
+}
+
+#[test]
+fn blockers_only_defer_steps_they_move_out_of_the_way() {
+    let (a, x, _) = side_by_side();
+    // A has a pending step, but it places A elsewhere rather than taking it out of
+    // X's destination, so X's step can never run
+    let plan = [step(1, "move", "X", Some(&x), Some(&a)), step(2, "place", "A", None, Some(&x))];
+
+    match sequence_plan(&state(&[("A", &a), ("X", &x)]), &plan) {
+        Err(PlanError::DestinationOccupied { step, other_id, .. }) => assert_eq!((step, other_id.as_str()), (1, "A")),
+        other => panic!("expected an occupied destination, got {:?}", other),
+    }
+}
+
+#[test]
+fn buried_items_are_dug_out_with_everything_in_front_of_them() {
+    let target = pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0));
+    // `front` covers the target; `outer` only covers `front`
+    let front = pos((0.0, 10.0, 0.0), (20.0, 20.0, 10.0));
+    let outer = pos((12.0, 0.0, 0.0), (20.0, 10.0, 10.0));
+    let free = pos((50.0, 0.0, 0.0), (60.0, 10.0, 10.0));
+    let plan = [step(1, "move", "T", Some(&target), Some(&free))];
+
+    let sequenced = sequence_plan(&state(&[("T", &target), ("F", &front), ("O", &outer)]), &plan).unwrap();
+    assert_eq!(summary(&sequenced), [
+        (1, "remove", "O"),
+        (2, "remove", "F"),
+        (3, "move", "T"),
+        (4, "placeBack", "F"),
+        (5, "placeBack", "O"),
+    ]);
+    assert!(sequenced[4].to_position.as_ref().is_some_and(|p| same_position(p, &outer)));
+}
+
+// A support on the floor with a load on top, and two free floor spots
+fn stack() -> (Position, Position, Position, Position) {
+    (
+        pos((0.0, 0.0, 0.0), (10.0, 10.0, 10.0)),
+        pos((0.0, 0.0, 10.0), (10.0, 10.0, 20.0)),
+        pos((20.0, 0.0, 0.0), (30.0, 10.0, 10.0)),
+        pos((40.0, 0.0, 0.0), (50.0, 10.0, 10.0)),
+    )
+}
+
+#[test]
+fn supports_are_moved_after_their_loads() {
+    let (support, load, free1, free2) = stack();
+    let plan = [step(1, "move", "S", Some(&support), Some(&free1)), step(2, "move", "L", Some(&load), Some(&free2))];
+
+    let sequenced = sequence_plan(&state(&[("S", &support), ("L", &load)]), &plan).unwrap();
+    assert_eq!(summary(&sequenced), [(1, "move", "L"), (2, "move", "S")]);
+}
+
+#[test]
+fn loads_are_never_left_floating() {
+    let (support, load, free1, _) = stack();
+    let plan = [step(1, "move", "S", Some(&support), Some(&free1))];
+
+    match sequence_plan(&state(&[("S", &support), ("L", &load)]), &plan) {
+        Err(PlanError::CarriesLoad { step, other_id, .. }) => assert_eq!((step, other_id.as_str()), (1, "L")),
+        other => panic!("expected a carried load, got {:?}", other),
+    }
+}
+
+#[test]
+fn destinations_wait_for_their_support() {
+    let (support, on_top, free1, _) = stack();
+    let plan = [step(1, "place", "N", None, Some(&on_top)), step(2, "move", "S", Some(&free1), Some(&support))];
+
+    let sequenced = sequence_plan(&state(&[("S", &free1)]), &plan).unwrap();
+    assert_eq!(summary(&sequenced), [(1, "move", "S"), (2, "place", "N")]);
+}
+
+#[test]
+fn destinations_whose_support_was_moved_away_are_rejected() {
+    let (support, on_top, free1, _) = stack();
+    let plan = [step(1, "move", "S", Some(&support), Some(&free1)), step(2, "place", "N", None, Some(&on_top))];
+
+    match sequence_plan(&state(&[("S", &support)]), &plan) {
+        Err(PlanError::Unsupported { step, item_id, .. }) => assert_eq!((step, item_id.as_str()), (2, "N")),
+        other => panic!("expected an unsupported destination, got {:?}", other),
+    }
+}
+
+#[test]
+fn loads_on_buried_items_come_out_with_them() {
+    // `front` covers the target; `top` rests on `front` alone and sits above the target
+    let target = pos((0.0, 20.0, 0.0), (10.0, 30.0, 10.0));
+    let front = pos((0.0, 10.0, 0.0), (10.0, 20.0, 10.0));
+    let top = pos((0.0, 10.0, 10.0), (10.0, 20.0, 20.0));
+    let free = pos((50.0, 0.0, 0.0), (60.0, 10.0, 10.0));
+    let plan = [step(1, "move", "T", Some(&target), Some(&free))];
+
+    let sequenced = sequence_plan(&state(&[("T", &target), ("F", &front), ("P", &top)]), &plan).unwrap();
+    assert_eq!(summary(&sequenced), [
+        (1, "remove", "P"),
+        (2, "remove", "F"),
+        (3, "move", "T"),
+        (4, "placeBack", "F"),
+        (5, "placeBack", "P"),
+    ]);
+}
+
+// Posts `steps` to the validate endpoint over a container holding A and B side by side
+async fn validate(steps: Vec<RearrangementStep>) -> ServiceResponse {
+    let pool = common::pool().await;
+    let (a, b, _) = side_by_side();
+    common::insert_container(&pool, "C", "Lab", (100.0, 100.0, 100.0)).await;
+    common::place(&pool, "A", "C", &a).await;
+    common::place(&pool, "B", "C", &b).await;
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool))
+            .route("/api/rearrangement/validate", web::post().to(handlers::validate_rearrangement_plan)),
+    ).await;
+    let req = actix_test::TestRequest::post().uri("/api/rearrangement/validate").set_json(RearrangementPlanRequest { steps }).to_request();
+    actix_test::call_service(&app, req).await
+}
+
+#[actix_web::test]
+async fn validate_endpoint_returns_the_executable_order() {
+    let (a, b, free) = side_by_side();
+    let resp = validate(vec![step(1, "move", "A", Some(&a), Some(&b)), step(2, "move", "B", Some(&b), Some(&free))]).await;
+
+    assert_eq!(resp.status(), 200);
+    let body: RearrangementPlanResponse = actix_test::read_body_json(resp).await;
+    assert!(body.success);
+    assert_eq!(summary(&body.steps), [(1, "move", "B"), (2, "move", "A")]);
+}
+
+#[actix_web::test]
+async fn validate_endpoint_rejects_plans_that_cannot_be_ordered() {
+    let (a, b, _) = side_by_side();
+    let resp = validate(vec![step(1, "move", "A", Some(&a), Some(&b)), step(2, "move", "B", Some(&b), Some(&a))]).await;
+
+    assert_eq!(resp.status(), 422);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.code, "REARRANGEMENT_INFEASIBLE");
+}
@@ -0,0 +1,118 @@
+//! Item and container resources: text search over the lists, and updates that
+//! would invalidate stored placements or overload a container are refused
+//! without writing anything.
+
+mod common;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::test::TestRequest;
+use actix_web::{test as actix_test, web, App};
+use common::pos;
+use placement_service::errors::ErrorResponse;
+use placement_service::events::EventBus;
+use placement_service::handlers;
+use placement_service::models::*;
+use serde_json::json;
+use sqlx::SqlitePool;
+
+async fn call(pool: &SqlitePool, req: TestRequest) -> ServiceResponse {
+    let app = actix_test::init_service(
+        App::new()
+            .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(EventBus::new()))
+            .route("/api/items", web::get().to(handlers::list_items))
+            .route("/api/items/{id}", web::patch().to(handlers::update_item))
+            .route("/api/containers", web::get().to(handlers::list_containers))
+            .route("/api/containers/{id}", web::patch().to(handlers::update_container)),
+    ).await;
+    actix_test::call_service(&app, req.to_request()).await
+}
+
+// Two containers in different zones; `Bolt` is placed in the first, `Drill` is not
+async fn seed(pool: &SqlitePool) {
+    common::insert_container(pool, "contA", "Lab", (30.0, 30.0, 30.0)).await;
+    common::insert_container(pool, "contB", "Airlock", (30.0, 30.0, 30.0)).await;
+    common::place(pool, "Bolt", "contA", &pos((0.0, 0.0, 0.0), (10.0, 10.0, 20.0))).await;
+    common::insert_item(pool, "Drill", (10.0, 10.0, 10.0)).await;
+}
+
+#[actix_web::test]
+async fn lists_match_search_text_case_insensitively() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let body: ItemListResponse = actix_test::read_body_json(call(&pool, TestRequest::get().uri("/api/items?search=dRi")).await).await;
+    assert_eq!(body.total, 1);
+    assert_eq!(body.items[0].item.item_id, "Drill");
+
+    let body: ContainerListResponse = actix_test::read_body_json(call(&pool, TestRequest::get().uri("/api/containers?search=airl")).await).await;
+    assert_eq!(body.total, 1);
+    assert_eq!(body.containers[0].container.container_id, "contB");
+}
+
+#[actix_web::test]
+async fn placed_items_keep_their_size() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let resp = call(&pool, TestRequest::patch().uri("/api/items/Bolt").set_json(json!({ "width": 12.0, "preferredZone": "Lab" }))).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.details[0].field, "width");
+
+    // The seeded items have no preferred zone, which a full item needs
+    let patch = json!({ "width": 12.0, "preferredZone": "Lab" });
+    let body: ItemResponse = actix_test::read_body_json(call(&pool, TestRequest::patch().uri("/api/items/Drill").set_json(patch)).await).await;
+    assert_eq!(body.item.item.width, 12.0);
+}
+
+#[actix_web::test]
+async fn containers_cannot_shrink_through_their_items() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    let resp = call(&pool, TestRequest::patch().uri("/api/containers/contA").set_json(json!({ "height": 15.0 }))).await;
+    assert_eq!(resp.status(), 400);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.details[0].field, "height");
+
+    let (height,): (f64,) = sqlx::query_as(r#"SELECT height FROM containers WHERE "containerId" = 'contA'"#)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(height, 30.0);
+}
+
+async fn version(pool: &SqlitePool, container_id: &str) -> i64 {
+    sqlx::query_scalar(r#"SELECT version FROM containers WHERE "containerId" = ?"#).bind(container_id).fetch_one(pool).await.unwrap()
+}
+
+#[actix_web::test]
+async fn placed_items_cannot_outweigh_their_container() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    sqlx::query(r#"UPDATE containers SET "maxWeightCapacity" = 50 WHERE "containerId" = 'contA'"#).execute(&pool).await.unwrap();
+
+    let resp = call(&pool, TestRequest::patch().uri("/api/items/Bolt").set_json(json!({ "mass": 60.0, "preferredZone": "Lab" }))).await;
+    assert_eq!(resp.status(), 422);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.code, "CONTAINER_OVERWEIGHT");
+    assert_eq!(version(&pool, "contA").await, 0);
+
+    // A mass within capacity is a change to the container's contents
+    let resp = call(&pool, TestRequest::patch().uri("/api/items/Bolt").set_json(json!({ "mass": 40.0, "preferredZone": "Lab" }))).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(version(&pool, "contA").await, 1);
+}
+
+#[actix_web::test]
+async fn container_capacity_cannot_drop_below_its_load() {
+    let pool = common::pool().await;
+    seed(&pool).await;
+    sqlx::query(r#"UPDATE items SET mass = 40 WHERE "itemId" = 'Bolt'"#).execute(&pool).await.unwrap();
+
+    let resp = call(&pool, TestRequest::patch().uri("/api/containers/contA").set_json(json!({ "maxWeightCapacity": 30.0 }))).await;
+    assert_eq!(resp.status(), 422);
+    let body: ErrorResponse = actix_test::read_body_json(resp).await;
+    assert_eq!(body.error.code, "CONTAINER_OVERWEIGHT");
+
+    let resp = call(&pool, TestRequest::patch().uri("/api/containers/contA").set_json(json!({ "maxWeightCapacity": 45.0 }))).await;
+    assert_eq!(resp.status(), 200);
+}
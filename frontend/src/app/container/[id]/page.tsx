@@ -5,6 +5,7 @@ import Link from 'next/link';
 import { useEffect, useState } from 'react';
 import ContainerItemViewer3D from '@/components/ContainerItemViewer3D';
 import { Package, Box, ArrowLeft, Archive } from 'lucide-react';
+import { apiFetch } from "@/lib/api";
 
 interface Container {
   id: string;
@@ -60,7 +61,7 @@ export default function ContainerPage() {
     if (!params?.id) return;
     
     setLoading(true);
-    fetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
+    apiFetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
       .then(response => response.json())
       .then((data: ApiResponse) => {
         const containerItems = data.items.filter(i => i.containerId === params.id);
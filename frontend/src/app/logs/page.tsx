@@ -2,6 +2,7 @@
 import React, { useState, useEffect } from "react";
 import { format } from "date-fns";
 import { User, Clock, ArrowRight } from "lucide-react";
+import { apiFetch } from "@/lib/api";
 
 // Define action types with labels for display
 const actionTypes = [
@@ -192,7 +193,7 @@ export default function LogsTable() {
   useEffect(() => {
     const fetchLogs = async () => {
       try {
-        const response = await fetch(
+        const response = await apiFetch(
           `${process.env.NEXT_PUBLIC_BASE_URL}/api/logs`
         );
         if (!response.ok) {
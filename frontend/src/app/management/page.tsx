@@ -8,6 +8,7 @@ import toast, { Toaster } from 'react-hot-toast';
 import ItemsList from '@/components/management/ItemsList';
 import AddItemModal from '@/components/management/AddItemModal';
 import AddContainerModal from '@/components/management/AddContainerModal';
+import { apiFetch } from "@/lib/api";
 
 interface Item {
   itemId: string;
@@ -294,7 +295,7 @@ export default function ManagementPage() {
         console.log("Sending to Placement API (Batch):", JSON.stringify(apiPayload, null, 2));
 
         const apiUrl = process.env.NEXT_PUBLIC_API_URL || 'http://localhost:8080';
-        const response = await fetch(`${apiUrl}/api/placement`, {
+        const response = await apiFetch(`${apiUrl}/api/placement`, {
           method: 'POST',
           headers: {
             'Content-Type': 'application/json',
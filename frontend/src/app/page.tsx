@@ -6,6 +6,7 @@ import ISS from "@/components/ISS";
 import ZoomControl from "@/components/ZoomControl";
 import "@/app/globals.css";
 import Search from "@/components/SearchRetrieve";
+import { apiFetch } from "@/lib/api";
 
 export default function HomePage() {
   const [translateX, setTranslateX] = useState<number>(0);
@@ -77,7 +78,7 @@ export default function HomePage() {
   useEffect(() => {
     setIsLoading(true);
     
-    fetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
+    apiFetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
       .then(response => response.json())
       .then(data => {
         setContainers(data.containers || []);
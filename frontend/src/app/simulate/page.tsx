@@ -7,6 +7,7 @@ import { DatePicker } from "./_components/DatePicker";
 import { SimulateButton } from "./_components/SimulateButton";
 import { SelectedItems } from "./_components/SelectedItems";
 import { SimulationResults } from "./_components/SimulationResults";
+import { apiFetch } from "@/lib/api";
 
 interface Item {
   itemId: string;
@@ -39,7 +40,7 @@ export default function SimulatePage() {
   useEffect(() => {
     const fetchItems = async () => {
       try {
-        const response = await fetch(
+        const response = await apiFetch(
           `${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`
         );
         if (!response.ok) {
@@ -89,7 +90,7 @@ export default function SimulatePage() {
         })),
       };
 
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/simulate/day`,
         {
           method: "POST",
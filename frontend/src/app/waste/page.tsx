@@ -10,6 +10,7 @@ import {
   Weight,
 } from "lucide-react";
 import toast from "react-hot-toast";
+import { apiFetch } from "@/lib/api";
 
 interface WasteItem {
   itemId: string;
@@ -112,7 +113,7 @@ export default function WasteManagement() {
   const fetchWasteItems = async () => {
     setLoading(true);
     try {
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/waste/identify`,
         {
           method: "GET",
@@ -139,7 +140,7 @@ export default function WasteManagement() {
 
     setLoading(true);
     try {
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/waste/return-plan`,
         {
           method: "POST",
@@ -182,7 +183,7 @@ export default function WasteManagement() {
 
     setLoading(true);
     try {
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/waste/complete-undocking`,
         {
           method: "POST",
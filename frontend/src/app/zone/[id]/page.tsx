@@ -4,6 +4,7 @@ import { useParams } from 'next/navigation';
 import Link from 'next/link';
 import { useEffect, useState } from 'react';
 import { Package, Map } from 'lucide-react';
+import { apiFetch } from "@/lib/api";
 
 interface Container {
   id: string;
@@ -50,7 +51,7 @@ export default function ZonePage() {
 
     // Fetch data from API
     setLoading(true);
-    fetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
+    apiFetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`)
       .then(response => response.json())
       .then((data: ApiResponse) => {
         const zoneContainers = data.containers.filter(c => c.zoneId === params.id);
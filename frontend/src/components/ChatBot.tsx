@@ -6,6 +6,7 @@ import { Input } from "@/components/ui/input";
 import { MessageSquare, X, Send, User, Loader2, Bot } from "lucide-react";
 import ReactMarkdown from "react-markdown";
 import { formatDistanceToNow } from "date-fns";
+import { apiFetch } from "@/lib/api";
 
 // Status messages for the chatbot
 enum StatusMessage {
@@ -326,7 +327,7 @@ Don't mention anything about the API, endpoints, or technical implementation det
         options.body = JSON.stringify(payload);
       }
 
-      const response = await fetch(url, options);
+      const response = await apiFetch(url, options);
 
       if (!response.ok) {
         throw new Error(`API call failed with status: ${response.status}`);
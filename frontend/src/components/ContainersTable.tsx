@@ -1,6 +1,6 @@
 "use client";
 
-import React, { useState, useEffect, useCallback } from "react";
+import React, { useState, useEffect, useCallback, useRef } from "react";
 import {
   Table,
   TableBody,
@@ -11,8 +11,9 @@ import {
 } from "@/components/ui/table";
 import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
-import { Container, PaginatedContainerResponse } from "@/data/types";
-import { Search, FileDown, Box, AlertTriangle } from "lucide-react";
+import { Container, ContainerListResponse } from "@/data/types";
+import { Search, FileDown, Box, Trash2 } from "lucide-react";
+import { apiFetch } from "@/lib/api";
 
 const ITEMS_PER_PAGE = 10;
 
@@ -26,40 +27,44 @@ export function ContainersTable() {
   const [totalContainers, setTotalContainers] = useState(0);
   const [searchTerm, setSearchTerm] = useState("");
   const [debouncedSearchTerm, setDebouncedSearchTerm] = useState(searchTerm);
+  // Page n + 1 starts at cursors.current[n]
+  const cursors = useRef<(string | null)[]>([null]);
 
   useEffect(() => {
     const handler = setTimeout(() => {
       setDebouncedSearchTerm(searchTerm);
-      setCurrentPage(1);
     }, 500);
     return () => clearTimeout(handler);
   }, [searchTerm]);
 
+  // A new search starts over from the first page
+  useEffect(() => {
+    cursors.current = [null];
+    setCurrentPage(1);
+  }, [debouncedSearchTerm]);
+
   const fetchData = useCallback(async () => {
     setIsLoading(true);
     setError(null);
 
     const params = new URLSearchParams();
-    params.append("page", currentPage.toString());
-    params.append("size", ITEMS_PER_PAGE.toString());
+    params.append("limit", ITEMS_PER_PAGE.toString());
+    const cursor = cursors.current[currentPage - 1];
+    if (cursor) params.append("cursor", cursor);
     if (debouncedSearchTerm) {
       params.append("search", debouncedSearchTerm);
     }
 
     try {
-      const response = await fetch(
-        `${
-          process.env.NEXT_PUBLIC_BASE_URL
-        }/api/tables/containers?${params.toString()}`
+      const response = await apiFetch(
+        `${process.env.NEXT_PUBLIC_BASE_URL}/api/containers?${params.toString()}`
       );
       if (!response.ok) throw new Error(`Error ${response.status}`);
-      const data: PaginatedContainerResponse = await response.json();
-      setContainers(data.items);
+      const data: ContainerListResponse = await response.json();
+      cursors.current = [...cursors.current.slice(0, currentPage), data.nextCursor];
+      setContainers(data.containers);
       setTotalContainers(data.total);
-      const total = Math.ceil(data.total / ITEMS_PER_PAGE);
-      setTotalPages(total);
-      if (currentPage > total && total > 0) setCurrentPage(total);
-      else if (data.total === 0) setCurrentPage(1);
+      setTotalPages(Math.max(1, Math.ceil(data.total / ITEMS_PER_PAGE)));
     } catch (err: any) {
       setError(err.message || "Fetch error");
       setContainers([]);
@@ -127,7 +132,7 @@ export function ContainersTable() {
                 Total Items
               </TableHead>
               <TableHead className="text-gray-300 font-semibold">
-                Expired
+                Type
               </TableHead>
             </TableRow>
           </TableHeader>
@@ -220,24 +225,19 @@ export function ContainersTable() {
                     <div className="px-3 py-1 bg-blue-500/20 rounded-md border border-blue-800 inline-flex items-center">
                       <Box size={14} className="text-blue-400 mr-2" />
                       <span className="text-blue-300">
-                        {container.item_count}
+                        {container.itemCount}
                       </span>
                     </div>
                   </TableCell>
                   <TableCell>
-                    {container.expired_item_count > 0 ? (
+                    {container.is_waste_container ? (
                       <div className="px-3 py-1 bg-red-500/20 rounded-md border border-red-800 inline-flex items-center">
-                        <AlertTriangle
-                          size={14}
-                          className="text-red-400 mr-2"
-                        />
-                        <span className="text-red-300">
-                          {container.expired_item_count}
-                        </span>
+                        <Trash2 size={14} className="text-red-400 mr-2" />
+                        <span className="text-red-300">Waste</span>
                       </div>
                     ) : (
                       <span className="px-3 py-1 bg-green-500/20 rounded-md border border-green-800 text-green-300">
-                        0
+                        Storage
                       </span>
                     )}
                   </TableCell>
@@ -267,8 +267,8 @@ export function ContainersTable() {
             <Button
               variant="outline"
               size="sm"
-              onClick={() => setCurrentPage((p) => Math.min(p + 1, totalPages))}
-              disabled={currentPage === totalPages || isLoading}
+              onClick={() => setCurrentPage((p) => p + 1)}
+              disabled={!cursors.current[currentPage] || isLoading}
               className="bg-gray-700 border-2 border-gray-600 hover:bg-gray-600 text-white shadow-lg shadow-black/10 disabled:opacity-50"
             >
               Next
@@ -5,11 +5,12 @@ import { Tabs, TabsContent, TabsList, TabsTrigger } from "@/components/ui/tabs";
 import { ItemsTable } from "@/components/ItemsTable";
 import { ContainersTable } from "@/components/ContainersTable";
 import { Package, Archive } from "lucide-react";
+import { apiFetch } from "@/lib/api";
 
 export function DataTablesTabs() {
   const handleExport = async () => {
     try {
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/export/arrangement`
       );
 
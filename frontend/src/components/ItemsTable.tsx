@@ -1,6 +1,6 @@
 "use client";
 
-import React, { useState, useEffect, useCallback } from "react";
+import React, { useState, useEffect, useCallback, useRef } from "react";
 import {
   Table,
   TableBody,
@@ -18,7 +18,7 @@ import {
   SelectTrigger,
   SelectValue,
 } from "@/components/ui/select";
-import { Item, PaginatedItemResponse, ItemStatus } from "@/data/types";
+import { Item, ItemListResponse, ItemStatus } from "@/data/types";
 import { format } from "date-fns";
 import { Search, FileDown, Filter } from "lucide-react";
 import { MoreVertical } from "lucide-react";
@@ -32,9 +32,17 @@ import {
 import { Label } from "@/components/ui/label";
 import { toast } from "react-hot-toast";
 import { Toaster } from "react-hot-toast";
+import { apiFetch } from "@/lib/api";
 
 const ITEMS_PER_PAGE = 10;
 
+const STATUS_LABELS: Record<ItemStatus, string> = {
+  [ItemStatus.ACTIVE]: "Active",
+  [ItemStatus.WASTE_EXPIRED]: "Waste Expired",
+  [ItemStatus.WASTE_DEPLETED]: "Waste Depleted",
+  [ItemStatus.DISPOSED]: "Disposed",
+};
+
 export function ItemsTable() {
   const [items, setItems] = useState<Item[]>([]);
   const [isLoading, setIsLoading] = useState(false);
@@ -55,40 +63,43 @@ export function ItemsTable() {
   const [endWidth, setEndWidth] = useState(0);
   const [endDepth, setEndDepth] = useState(0);
   const [endHeight, setEndHeight] = useState(0);
+  // cursors.current[n] resumes the list at page n + 1; the API pages by cursor, not offset
+  const cursors = useRef<(string | null)[]>([null]);
 
   useEffect(() => {
     const handler = setTimeout(() => {
       setDebouncedSearchTerm(searchTerm);
-      setCurrentPage(1);
     }, 500);
     return () => clearTimeout(handler);
   }, [searchTerm]);
 
+  // Cursors are only valid for the filters they were issued under
+  useEffect(() => {
+    cursors.current = [null];
+    setCurrentPage(1);
+  }, [debouncedSearchTerm, statusFilter, preferredZoneFilter]);
+
   const fetchData = useCallback(async () => {
     setIsLoading(true);
     setError(null);
     const params = new URLSearchParams();
-    params.append("page", currentPage.toString());
-    params.append("size", ITEMS_PER_PAGE.toString());
+    params.append("limit", ITEMS_PER_PAGE.toString());
+    const cursor = cursors.current[currentPage - 1];
+    if (cursor) params.append("cursor", cursor);
     if (debouncedSearchTerm) params.append("search", debouncedSearchTerm);
     if (statusFilter) params.append("status", statusFilter);
-    if (preferredZoneFilter)
-      params.append("preferred_zone", preferredZoneFilter);
+    if (preferredZoneFilter) params.append("zone", preferredZoneFilter);
 
     try {
-      const response = await fetch(
-        `${
-          process.env.NEXT_PUBLIC_BASE_URL
-        }/api/tables/items?${params.toString()}`
+      const response = await apiFetch(
+        `${process.env.NEXT_PUBLIC_BASE_URL}/api/items?${params.toString()}`
       );
       if (!response.ok) throw new Error(`Error ${response.status}`);
-      const data: PaginatedItemResponse = await response.json();
+      const data: ItemListResponse = await response.json();
+      cursors.current = [...cursors.current.slice(0, currentPage), data.nextCursor];
       setItems(data.items);
       setTotalItems(data.total);
-      const total = Math.ceil(data.total / ITEMS_PER_PAGE);
-      setTotalPages(total);
-      if (currentPage > total && total > 0) setCurrentPage(total);
-      else if (data.total === 0) setCurrentPage(1);
+      setTotalPages(Math.max(1, Math.ceil(data.total / ITEMS_PER_PAGE)));
     } catch (err: any) {
       setError(err.message || "Failed to fetch items");
       setItems([]);
@@ -114,7 +125,7 @@ export function ItemsTable() {
     const toastId = toast.loading("Placing item...");
   
     try {
-      const response = await fetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/place`, {
+      const response = await apiFetch(`${process.env.NEXT_PUBLIC_BASE_URL}/api/place`, {
         method: 'POST',
         headers: {
           'Content-Type': 'application/json',
@@ -172,25 +183,16 @@ export function ItemsTable() {
     }
   };
 
-  const getStatusBadge = (status: ItemStatus) => {
-    // Normalize status to uppercase to match colorMap keys
-    const normalizedStatus = status.toUpperCase();
-    const colorMap: Record<string, string> = {
-      ACTIVE: "bg-green-600",
-      WASTE_EXPIRED: "bg-rose-600",
-      WASTE_DEPLETED: "bg-amber-500",
-      DISPOSED: "bg-gray-600",
+  const getStatusBadge = (status: ItemStatus = ItemStatus.ACTIVE) => {
+    const colorMap: Record<ItemStatus, string> = {
+      [ItemStatus.ACTIVE]: "bg-green-600",
+      [ItemStatus.WASTE_EXPIRED]: "bg-rose-600",
+      [ItemStatus.WASTE_DEPLETED]: "bg-amber-500",
+      [ItemStatus.DISPOSED]: "bg-gray-600",
     };
 
-    const colorClass = colorMap[normalizedStatus] ?? "bg-gray-500";
-
-    // Map status to display text
-    let displayText = '';
-    if (status === 'active') displayText = 'Active';
-    else if (status === 'expired') displayText = 'Waste Expired';
-    else if (status === 'depleted') displayText = 'Waste Depleted';
-    else if (status === 'disposed') displayText = 'Disposed';
-    else displayText = String(status).charAt(0).toUpperCase() + String(status).slice(1);
+    const colorClass = colorMap[status] ?? "bg-gray-500";
+    const displayText = STATUS_LABELS[status] ?? String(status);
 
     return (
       <span
@@ -224,7 +226,7 @@ export function ItemsTable() {
               <Search size={16} className="text-gray-400" />
             </div>
             <Input
-              placeholder="Search name, ID, zones..."
+              placeholder="Search name or ID..."
               value={searchTerm}
               onChange={(e) => setSearchTerm(e.target.value)}
               className="bg-gray-700 text-white border-2 border-gray-600 pl-10 py-6 focus:ring-indigo-500 focus:border-indigo-500 shadow-lg shadow-black/10"
@@ -246,11 +248,7 @@ export function ItemsTable() {
               <SelectItem value="all">All Statuses</SelectItem>
               {Object.values(ItemStatus).map((status) => (
                 <SelectItem key={status} value={status}>
-                  {status === 'active' ? 'Active' : 
-                   status === 'expired' ? 'Waste Expired' :
-                   status === 'depleted' ? 'Waste Depleted' :
-                   status === 'disposed' ? 'Disposed' : 
-                   String(status).charAt(0).toUpperCase() + String(status).slice(1)}
+                  {STATUS_LABELS[status]}
                 </SelectItem>
               ))}
             </SelectContent>
@@ -294,9 +292,6 @@ export function ItemsTable() {
               <TableHead className="text-gray-300 font-semibold">
                 Container
               </TableHead>
-              <TableHead className="text-gray-300 font-semibold">
-                Current Zone
-              </TableHead>
               <TableHead className="text-gray-300 font-semibold">
                 Preferred Zone
               </TableHead>
@@ -421,15 +416,6 @@ export function ItemsTable() {
                       <span className="text-gray-500">N/A</span>
                     )}
                   </TableCell>
-                  <TableCell>
-                    {item.currentZone ? (
-                      <span className="text-indigo-300">
-                        {item.currentZone}
-                      </span>
-                    ) : (
-                      <span className="text-gray-500">N/A</span>
-                    )}
-                  </TableCell>
                   <TableCell>
                     {item.preferredZone ? (
                       <span className="text-purple-300">
@@ -528,8 +514,8 @@ export function ItemsTable() {
             <Button
               variant="outline"
               size="sm"
-              onClick={() => setCurrentPage((p) => Math.min(p + 1, totalPages))}
-              disabled={currentPage === totalPages || isLoading}
+              onClick={() => setCurrentPage((p) => p + 1)}
+              disabled={!cursors.current[currentPage] || isLoading}
               className="bg-gray-700 border-2 border-gray-600 hover:bg-gray-600 text-white shadow-lg shadow-black/10 disabled:opacity-50"
             >
               Next
@@ -11,6 +11,7 @@ import {
   ChevronRight,
 } from "lucide-react";
 import toast from "react-hot-toast";
+import { apiFetch } from "@/lib/api";
 
 interface Item {
   itemId: string;
@@ -65,7 +66,7 @@ export default function SearchRetrieve() {
   useEffect(() => {
     const fetchItems = async () => {
       try {
-        const response = await fetch(
+        const response = await apiFetch(
           `${process.env.NEXT_PUBLIC_BASE_URL}/api/frontend/placements`
         );
         if (!response.ok) {
@@ -107,7 +108,7 @@ export default function SearchRetrieve() {
         ? `/api/search?itemId=${encodeURIComponent(searchValue)}`
         : `/api/search?itemName=${encodeURIComponent(searchValue)}`;
 
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}${endpoint}`
       );
       if (!response.ok) {
@@ -136,7 +137,7 @@ export default function SearchRetrieve() {
   const handleRetrieve = async (itemId: string) => {
     setIsRetrieving(itemId);
     try {
-      const response = await fetch(
+      const response = await apiFetch(
         `${process.env.NEXT_PUBLIC_BASE_URL}/api/retrieve`,
         {
           method: "POST",
@@ -3,50 +3,66 @@
 
 
 export enum ItemStatus {
-    ACTIVE = 'active',
-    WASTE_EXPIRED = 'expired',
-    WASTE_DEPLETED = 'depleted',
-    DISPOSED = 'disposed',
+    ACTIVE = 'ACTIVE',
+    WASTE_EXPIRED = 'WASTE_EXPIRED',
+    WASTE_DEPLETED = 'WASTE_DEPLETED',
+    DISPOSED = 'DISPOSED',
   }
-  
 
+
+export interface Coordinates {
+    width: number;
+    depth: number;
+    height: number;
+}
+
+export interface Position {
+    startCoordinates: Coordinates;
+    endCoordinates: Coordinates;
+}
+
+// A container as returned by /api/containers
 export interface Container {
-    containerId: string; // Corresponds to containerId
-    zone: string; // Corresponds to zone
+    containerId: string;
+    zone: string;
     width: number;
     depth: number;
     height: number;
-    item_count: number;
-    expired_item_count: number;
+    is_waste_container?: boolean;
+    maxWeightCapacity?: number;
+    itemCount: number;
+    version: number;
 }
 
+// An item as returned by /api/items, with its placement if it has one
 export interface Item {
-    itemId: string; // Corresponds to itemId
+    itemId: string;
     name: string;
-    containerId: string | null;
-    quantity: number; // Always 1 in this implementation
-    mass: number;
-    expiryDate: string | null; // ISO date string
     width: number;
     depth: number;
     height: number;
+    mass?: number;
     priority: number;
-    usageLimit: number | null;
+    expiryDate: string | null; // ISO date string
+    usageLimit: number;
     currentUses: number;
-    preferredZone: string | null;
-    currentZone: string | null;
-    status: ItemStatus;
-    expired: boolean;
-    depleted: boolean;
+    preferredZone: string;
+    status?: ItemStatus;
+    containerId: string | null;
+    position: Position | null;
 }
 
-// Base pagination response structure
-interface PaginatedResponse<T> {
+// Keyset-paginated list responses: pass nextCursor back as `cursor` for the next page
+export interface ItemListResponse {
+    success: boolean;
+    items: Item[];
     total: number;
-    page: number;
-    size: number;
-    items: T[];
+    nextCursor: string | null;
 }
 
-export type PaginatedContainerResponse = PaginatedResponse<Container>;
-export type PaginatedItemResponse = PaginatedResponse<Item>;
\ No newline at end of file
+export interface ContainerListResponse {
+    success: boolean;
+    containers: Container[];
+    total: number;
+    nextCursor: string | null;
+}
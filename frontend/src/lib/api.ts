@@ -0,0 +1,12 @@
+// Token the placement service authenticates the dashboard with: an API token
+// issued through POST /api/tokens, with the crew or planner role
+const API_TOKEN = process.env.NEXT_PUBLIC_API_TOKEN;
+
+// fetch() for placement service endpoints, with the bearer token attached
+export function apiFetch(input: string, init: RequestInit = {}): Promise<Response> {
+  const headers = new Headers(init.headers);
+  if (API_TOKEN) {
+    headers.set("Authorization", `Bearer ${API_TOKEN}`);
+  }
+  return fetch(input, { ...init, headers });
+}